@@ -1,19 +1,59 @@
 //! Defines the command line interface.
 
 pub mod cmd;
+pub mod dry_run;
+pub mod fields;
 pub mod opts;
 pub mod sink;
 pub mod table;
+pub mod template;
 
 use crate::config::DsConfig;
+use crate::http;
+use crate::util::events::{self, Event};
 use clap::IntoApp;
 
+use self::cmd::config::{self as config_cmd, ConfigCommand};
 use self::cmd::{Cmd, CmdError, Context};
 use self::opts::{MainOpts, SubCommand};
 
 /// Given the config and arguments, runs the corresponding command.
 pub fn execute_cmd(cfg: DsConfig, opts: MainOpts) -> Result<(), CmdError> {
-    let ctx = Context::new(&opts.common_opts, &cfg);
+    events::set_enabled(opts.common_opts.events);
+    events::emit(&Event::Started {
+        command: opts.subcmd.name(),
+    });
+    let result = run_cmd(&cfg, &opts);
+    http::metrics::log_total();
+    let error = result.as_ref().err().map(|e| e.to_string());
+    events::emit(&Event::Finished {
+        success: result.is_ok(),
+        error: error.as_deref(),
+    });
+    result
+}
+
+fn run_cmd(cfg: &DsConfig, opts: &MainOpts) -> Result<(), CmdError> {
+    // The config check must work even if no context can be created
+    // from the config.
+    if let SubCommand::Config(config_cmd::Input {
+        subcmd: ConfigCommand::Check(input),
+    }) = &opts.subcmd
+    {
+        input
+            .exec(opts.config.as_ref(), &opts.common_opts, cfg)
+            .map_err(|source| config_cmd::Error::Check { source })?;
+        return Ok(());
+    }
+    if opts.common_opts.offline && opts.subcmd.needs_server() {
+        return Err(CmdError::Offline {
+            command: opts.subcmd.name(),
+        });
+    }
+    let ctx = Context::new(&opts.common_opts, cfg, opts.subcmd.name())?;
+    if opts.subcmd.checks_server_version() {
+        cmd::version::warn_version_mismatch(&ctx);
+    }
 
     log::info!("Running command: {:?}", opts.subcmd);
     match &opts.subcmd {
@@ -25,14 +65,21 @@ pub fn execute_cmd(cfg: DsConfig, opts: MainOpts) -> Result<(), CmdError> {
             let mut app = MainOpts::into_app();
             input.print_completions(&mut app);
         }
+        SubCommand::Complete(input) => input.exec(&ctx)?,
+        SubCommand::Schema(input) => input.exec(&ctx)?,
+        SubCommand::Config(input) => input.exec(&ctx)?,
         SubCommand::Item(input) => input.exec(&ctx)?,
         SubCommand::Watch(input) => input.exec(&ctx)?,
         SubCommand::Version(input) => input.exec(&ctx)?,
         SubCommand::Login(input) => input.exec(&ctx)?,
         SubCommand::Logout(input) => input.exec(&ctx)?,
+        SubCommand::Whoami(input) => input.exec(&ctx)?,
+        SubCommand::Ping(input) => input.exec(&ctx)?,
         SubCommand::Search(input) => input.exec(&ctx)?,
         SubCommand::SearchSummary(input) => input.exec(&ctx)?,
         SubCommand::Source(input) => input.exec(&ctx)?,
+        SubCommand::Tag(input) => input.exec(&ctx)?,
+        SubCommand::Folder(input) => input.exec(&ctx)?,
         SubCommand::Admin(input) => input.exec(&ctx)?,
         SubCommand::FileExists(input) => input.exec(&ctx)?,
         SubCommand::GenInvite(input) => input.exec(&ctx)?,
@@ -43,5 +90,7 @@ pub fn execute_cmd(cfg: DsConfig, opts: MainOpts) -> Result<(), CmdError> {
         SubCommand::Cleanup(input) => input.exec(&ctx)?,
         SubCommand::Export(input) => input.exec(&ctx)?,
     };
+    ctx.finish()
+        .map_err(|source| CmdError::WriteSink { source })?;
     Ok(())
 }
@@ -11,30 +11,45 @@
 
 pub mod admin;
 pub mod cleanup;
+pub mod complete;
+pub mod config;
 pub mod download;
 pub mod export;
 pub mod file_exists;
+pub mod folder;
 pub mod generate_completions;
 pub mod geninvite;
 pub mod item;
 pub mod login;
 pub mod logout;
+pub mod ping;
 pub mod register;
+pub mod schema;
 pub mod search;
 pub mod search_summary;
 pub mod source;
+pub mod tag;
 pub mod upload;
 pub mod version;
 pub mod view;
 pub mod watch;
+pub mod whoami;
 
+use super::fields::Projection;
 use super::opts::Format;
-use super::sink::{Error as SinkError, Sink};
+use super::sink::{Error as SinkError, Output, Sink};
 use crate::cli::opts::CommonOpts;
 use crate::config::{ConfigError, DsConfig};
-use crate::http::Client;
+use crate::http::payload::AuthRequest;
+use crate::http::util::normalize_base_url;
+use crate::http::{Client, Error as HttpError, HttpSettings, RateLimit, Retry, DEFAULT_USER_AGENT};
+use crate::util::{events, pass, quiet};
 use serde::Serialize;
 use snafu::Snafu;
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
 
 /// A command for the cli.
 ///
@@ -42,7 +57,7 @@ use snafu::Snafu;
 pub trait Cmd {
     type CmdError;
 
-    fn exec<'a>(&self, args: &'a Context) -> Result<(), Self::CmdError>;
+    fn exec(&self, args: &Context) -> Result<(), Self::CmdError>;
 }
 
 /// An environment for running a command.
@@ -53,28 +68,139 @@ pub struct Context<'a> {
     pub opts: &'a CommonOpts,
     pub cfg: &'a DsConfig,
     pub client: Client,
+    default_format: Format,
+    output: Mutex<Option<Output>>,
+    extra_outputs: Mutex<Option<Vec<(Format, Output)>>>,
 }
 
 impl Context<'_> {
-    pub fn new<'a>(opts: &'a CommonOpts, cfg: &'a DsConfig) -> Context<'a> {
-        Context {
+    /// Creates the context for running the given subcommand, whose
+    /// name selects the default format from the config.
+    pub fn new<'a>(
+        opts: &'a CommonOpts,
+        cfg: &'a DsConfig,
+        command: &str,
+    ) -> Result<Context<'a>, CmdError> {
+        quiet::set_quiet(opts.is_quiet());
+        let settings = http_settings(opts, cfg);
+        if settings.insecure {
+            events::warn(
+                "WARNING: TLS certificate verification is disabled! \
+                 Use this only with development servers.",
+            );
+        }
+        let mut client = Client::with_settings(docspell_url(opts, cfg), settings)
+            .map_err(|source| CmdError::HttpClient { source })?
+            .with_retry(
+                Retry::new(opts.max_retries, Duration::from_millis(opts.retry_delay))
+                    .with_max_retry_after(Duration::from_secs(opts.max_retry_after)),
+            )
+            .with_rate_limit(opts.rate_limit.map(RateLimit::new))
+            .with_session_file(opts.session_file.clone().or_else(|| cfg.session_file()))
+            .with_legacy_session(is_configured_url(opts, cfg))
+            .with_session_store(cfg.session_store)
+            .with_query_aliases(cfg.aliases.clone());
+        if let Some(account) = cfg.default_account.clone() {
+            let pass_entry = cfg.pass_entry.clone();
+            client = client
+                .with_credentials(Box::new(move || stored_credentials(&account, &pass_entry)));
+        }
+        Ok(Context {
             opts,
             cfg,
-            client: Client::new(docspell_url(opts, cfg)),
-        }
+            client,
+            default_format: cfg.format_for(command),
+            output: Mutex::new(None),
+            extra_outputs: Mutex::new(None),
+        })
     }
 
     fn base_url(&self) -> String {
-        docspell_url(self.opts, self.cfg)
+        self.client.base_url().to_string()
     }
 
     fn write_result<A: Sink + Serialize>(&self, value: A) -> Result<(), SinkError> {
-        let fmt = self.format();
-        Sink::write_value(fmt, &value)
+        self.write_result_as(value, self.opts.format, &self.opts.columns)
+    }
+
+    /// Writes the result using the given format and columns instead
+    /// of those from the options. If no format is given, the default
+    /// format is used. A `--template` always takes precedence.
+    fn write_result_as<A: Sink + Serialize>(
+        &self,
+        value: A,
+        format: Option<Format>,
+        columns: &[String],
+    ) -> Result<(), SinkError> {
+        let fmt = format.unwrap_or(self.default_format);
+        write_value(&mut *self.output()?, self.opts, fmt, columns, &value)?;
+        self.write_extra_outputs(&value, columns)
+    }
+
+    /// Writes the result to the files given via `--json-out` and the
+    /// like, each in its own format. Templates and `--fields` are not
+    /// applied to them.
+    fn write_extra_outputs<A: Sink + Serialize>(
+        &self,
+        value: &A,
+        columns: &[String],
+    ) -> Result<(), SinkError> {
+        let mut extra = self.extra_outputs.lock().unwrap();
+        if extra.is_none() {
+            let mut outs = Vec::new();
+            for (fmt, file) in self.opts.extra_outputs() {
+                outs.push((fmt, Output::create(Some(file))?));
+            }
+            *extra = Some(outs);
+        }
+        for (fmt, out) in extra.iter_mut().flatten() {
+            Sink::write_to(out, *fmt, columns, value)?;
+            if matches!(fmt, Format::Json | Format::Yaml) {
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns where to write the result of the command to. This is
+    /// stdout, or the file given via `--output`.
+    fn output(&self) -> Result<OutputGuard<'_>, SinkError> {
+        let mut out = self.output.lock().unwrap();
+        if out.is_none() {
+            *out = Some(Output::create(self.opts.output.as_deref())?);
+        }
+        Ok(OutputGuard(out))
+    }
+
+    /// Completes writing the result. If `--output` or `--json-out`
+    /// and the like are given, the files are replaced only now, so
+    /// they are kept unchanged if the command fails.
+    pub fn finish(self) -> Result<(), SinkError> {
+        if let Some(out) = self.output.into_inner().unwrap() {
+            out.finish()?;
+        }
+        for (_, out) in self
+            .extra_outputs
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .flatten()
+        {
+            out.finish()?;
+        }
+        Ok(())
     }
 
     fn format(&self) -> Format {
-        self.opts.format.unwrap_or(self.cfg.default_format)
+        self.opts.format.unwrap_or(self.default_format)
+    }
+
+    fn show_progress(&self) -> bool {
+        !self.opts.no_progress
+            && !self.opts.is_quiet()
+            && !matches!(self.format(), Format::Json | Format::Ndjson)
+            && console::Term::stdout().is_term()
+            && console::Term::stderr().is_term()
     }
 
     fn pass_entry(&self, given: &Option<String>) -> Option<String> {
@@ -82,6 +208,99 @@ impl Context<'_> {
     }
 }
 
+/// Writes the value as given by the common options: each result is
+/// rendered via the `--template` or the format is reduced to the
+/// `--fields`, if given.
+pub fn write_value<A: Sink + Serialize>(
+    out: &mut Output,
+    opts: &CommonOpts,
+    format: Format,
+    columns: &[String],
+    value: &A,
+) -> Result<(), SinkError> {
+    if let Some(tpl) = &opts.template {
+        Sink::write_template(out, value, tpl, opts.strict)
+    } else if !opts.fields.is_empty() && Projection::applies_to(format) {
+        let mut proj = Projection::new(&opts.fields);
+        Sink::write_projected(out, format, value, &mut proj)?;
+        proj.warn_unknown();
+        Ok(())
+    } else {
+        Sink::write_to(out, format, columns, value)
+    }
+}
+
+/// Gives access to the output of a [`Context`], which is created
+/// on first use.
+struct OutputGuard<'a>(MutexGuard<'a, Option<Output>>);
+
+impl Deref for OutputGuard<'_> {
+    type Target = Output;
+
+    fn deref(&self) -> &Output {
+        self.0.as_ref().expect("Output is created")
+    }
+}
+
+impl DerefMut for OutputGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Output {
+        self.0.as_mut().expect("Output is created")
+    }
+}
+
+/// Credentials to login again when the session cannot be refreshed.
+/// The password is looked up via the pass entry from the config file
+/// or the env variable `DSC_PASSWORD`.
+fn stored_credentials(account: &str, pass_entry: &Option<String>) -> Option<AuthRequest> {
+    let password = match pass_entry {
+        Some(pe) => pass::pass_password(pe)
+            .map_err(|err| log::warn!("Could not get password from pass: {}", err))
+            .ok(),
+        None => std::env::var(login::DSC_PASSWORD).ok(),
+    }?;
+    Some(AuthRequest {
+        account: account.to_string(),
+        password,
+        remember_me: false,
+    })
+}
+
+fn http_settings(opts: &CommonOpts, cfg: &DsConfig) -> HttpSettings {
+    let secs = |n: u64| Some(n).filter(|n| *n > 0).map(Duration::from_secs);
+    HttpSettings {
+        connect_timeout: secs(cfg.connect_timeout),
+        timeout: secs(opts.timeout.unwrap_or(cfg.read_timeout)),
+        transfer_timeout: opts.timeout.and_then(secs),
+        proxy: opts.proxy.clone().or_else(|| cfg.proxy.clone()),
+        client_cert: opts.client_cert.clone().or_else(|| cfg.client_cert.clone()),
+        client_key: opts.client_key.clone().or_else(|| cfg.client_key.clone()),
+        ca_cert: opts.ca_cert.clone().or_else(|| cfg.ca_cert.clone()),
+        insecure: opts.insecure || cfg.insecure,
+        extra_headers: opts
+            .extra_header
+            .iter()
+            .map(|h| (h.name.trim().to_string(), h.value.trim().to_string()))
+            .collect(),
+        user_agent: opts
+            .user_agent
+            .clone()
+            .or_else(|| cfg.user_agent.clone())
+            .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()),
+    }
+}
+
+/// Returns whether the docspell url is the one from the config file,
+/// which may take over the session of older versions.
+fn is_configured_url(opts: &CommonOpts, cfg: &DsConfig) -> bool {
+    match &opts.docspell_url {
+        Some(url) => matches!(
+            (normalize_base_url(url), normalize_base_url(&cfg.docspell_url)),
+            (Ok(a), Ok(b)) if a == b
+        ),
+        None => true,
+    }
+}
+
 fn docspell_url(opts: &CommonOpts, cfg: &DsConfig) -> String {
     opts.docspell_url
         .as_ref()
@@ -91,25 +310,95 @@ fn docspell_url(opts: &CommonOpts, cfg: &DsConfig) -> String {
 
 #[derive(Debug, Snafu)]
 pub enum CmdError {
-    Export { source: export::Error },
-    Watch { source: watch::Error },
-    Upload { source: upload::Error },
-    Admin { source: admin::Error },
-    Cleanup { source: cleanup::Error },
-    Download { source: download::Error },
-    FileExists { source: file_exists::Error },
-    GenInvite { source: geninvite::Error },
-    Item { source: item::Error },
-    Login { source: login::Error },
-    Logout { source: logout::Error },
-    Register { source: register::Error },
-    Search { source: search::Error },
-    SearchSummary { source: search_summary::Error },
-    Source { source: source::Error },
-    Version { source: version::Error },
-    View { source: view::Error },
-    WriteConfig { source: ConfigError },
-    WriteSink { source: SinkError },
+    Export {
+        source: export::Error,
+    },
+    Watch {
+        source: watch::Error,
+    },
+    Upload {
+        source: upload::Error,
+    },
+    Admin {
+        source: admin::Error,
+    },
+    Cleanup {
+        source: cleanup::Error,
+    },
+    Config {
+        source: config::Error,
+    },
+    Download {
+        source: download::Error,
+    },
+    FileExists {
+        source: file_exists::Error,
+    },
+    GenInvite {
+        source: geninvite::Error,
+    },
+    Item {
+        source: item::Error,
+    },
+    Login {
+        source: login::Error,
+    },
+    Logout {
+        source: logout::Error,
+    },
+    Register {
+        source: register::Error,
+    },
+    Search {
+        source: search::Error,
+    },
+    SearchSummary {
+        source: search_summary::Error,
+    },
+    Source {
+        source: source::Error,
+    },
+    Tag {
+        source: tag::Error,
+    },
+    Folder {
+        source: folder::Error,
+    },
+    Version {
+        source: version::Error,
+    },
+    View {
+        source: view::Error,
+    },
+    Whoami {
+        source: whoami::Error,
+    },
+    Ping {
+        source: ping::Error,
+    },
+    Complete {
+        source: complete::Error,
+    },
+    Schema {
+        source: schema::Error,
+    },
+    WriteConfig {
+        source: ConfigError,
+    },
+    WriteSink {
+        source: SinkError,
+    },
+    HttpClient {
+        source: HttpError,
+    },
+
+    #[snafu(display(
+        "The command '{}' needs the docspell server, but --offline is given",
+        command
+    ))]
+    Offline {
+        command: &'static str,
+    },
 }
 
 impl From<ConfigError> for CmdError {
@@ -132,6 +421,36 @@ impl From<logout::Error> for CmdError {
         CmdError::Logout { source }
     }
 }
+impl From<tag::Error> for CmdError {
+    fn from(source: tag::Error) -> Self {
+        CmdError::Tag { source }
+    }
+}
+impl From<folder::Error> for CmdError {
+    fn from(source: folder::Error) -> Self {
+        CmdError::Folder { source }
+    }
+}
+impl From<complete::Error> for CmdError {
+    fn from(source: complete::Error) -> Self {
+        CmdError::Complete { source }
+    }
+}
+impl From<schema::Error> for CmdError {
+    fn from(source: schema::Error) -> Self {
+        CmdError::Schema { source }
+    }
+}
+impl From<whoami::Error> for CmdError {
+    fn from(source: whoami::Error) -> Self {
+        CmdError::Whoami { source }
+    }
+}
+impl From<ping::Error> for CmdError {
+    fn from(source: ping::Error) -> Self {
+        CmdError::Ping { source }
+    }
+}
 impl From<search::Error> for CmdError {
     fn from(source: search::Error) -> Self {
         CmdError::Search { source }
@@ -187,6 +506,11 @@ impl From<cleanup::Error> for CmdError {
         CmdError::Cleanup { source }
     }
 }
+impl From<config::Error> for CmdError {
+    fn from(source: config::Error) -> Self {
+        CmdError::Config { source }
+    }
+}
 impl From<upload::Error> for CmdError {
     fn from(source: upload::Error) -> Self {
         CmdError::Upload { source }
@@ -1,6 +1,8 @@
 pub mod convert_all_pdfs;
 pub mod generate_previews;
+mod jobs;
 pub mod recreate_index;
+pub mod reprocess;
 pub mod reset_password;
 
 use clap::{AppSettings, Clap};
@@ -29,6 +31,7 @@ pub enum Error {
     RecreateIndex { source: recreate_index::Error },
     ResetPassword { source: reset_password::Error },
     ConvertAllPdfs { source: convert_all_pdfs::Error },
+    Reprocess { source: reprocess::Error },
 }
 
 #[derive(Clap, Debug)]
@@ -46,8 +49,12 @@ pub enum AdminCommand {
     ResetPassword(reset_password::Input),
 
     #[clap(setting = AppSettings::ColoredHelp)]
-    #[clap(version)]
+    #[clap(version, alias = "convert-all-pdf")]
     ConvertAllPdfs(convert_all_pdfs::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Reprocess(reprocess::Input),
 }
 
 impl Cmd for Input {
@@ -59,6 +66,7 @@ impl Cmd for Input {
             AdminCommand::RecreateIndex(input) => input.exec(self, ctx).context(RecreateIndex),
             AdminCommand::ResetPassword(input) => input.exec(self, ctx).context(ResetPassword),
             AdminCommand::ConvertAllPdfs(input) => input.exec(self, ctx).context(ConvertAllPdfs),
+            AdminCommand::Reprocess(input) => input.exec(self, ctx).context(Reprocess),
         }
     }
 }
@@ -70,15 +78,15 @@ pub trait AdminCmd {
 }
 
 fn get_secret(opts: &Input, ctx: &Context) -> Option<String> {
-    let secret = opts
-        .admin_secret
-        .as_ref()
-        .or_else(|| ctx.cfg.admin_secret.as_ref())
-        .map(String::clone);
-
-    if secret.is_some() && ctx.opts.verbose > 2 {
-        log::debug!("Using secret: {:?}", secret);
+    match (&opts.admin_secret, &ctx.cfg.admin_secret) {
+        (Some(secret), _) => {
+            log::debug!("Using the admin secret given as option");
+            Some(secret.clone())
+        }
+        (None, Some(secret)) => {
+            log::debug!("Using the admin secret from the config file");
+            Some(secret.clone())
+        }
+        (None, None) => None,
     }
-
-    secret
 }
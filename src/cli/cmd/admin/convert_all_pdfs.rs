@@ -1,6 +1,7 @@
 use clap::Clap;
 use snafu::{ResultExt, Snafu};
 
+use super::jobs::{self, JobWait};
 use super::AdminCmd;
 use super::Context;
 use crate::cli::sink::Error as SinkError;
@@ -9,18 +10,32 @@ use crate::http::Error as HttpError;
 /// Submits a task to convert all pdfs via the configured tool (by
 /// default ocrmypdf).
 #[derive(Clap, std::fmt::Debug)]
-pub struct Input {}
+pub struct Input {
+    /// Wait until the job has finished, showing its progress. This
+    /// requires to be logged in, because the job is looked up in the
+    /// queue of your collective. The command fails if the job fails.
+    #[clap(long)]
+    pub wait: bool,
+}
 
 impl AdminCmd for Input {
     type CmdError = Error;
 
     fn exec(&self, admin_opts: &super::Input, ctx: &Context) -> Result<(), Error> {
         let secret = super::get_secret(admin_opts, ctx).ok_or(Error::NoAdminSecret)?;
+        let waiting = match self.wait {
+            true => Some(JobWait::prepare("convert", ctx).context(Wait)?),
+            false => None,
+        };
         let result = ctx
             .client
             .admin_convert_all_pdfs(secret)
             .context(HttpClient)?;
+        let success = result.success;
         ctx.write_result(result).context(WriteResult)?;
+        if let (true, Some(w)) = (success, waiting) {
+            w.wait(ctx).context(Wait)?;
+        }
         Ok(())
     }
 }
@@ -35,4 +50,7 @@ pub enum Error {
 
     #[snafu(display("No admin secret provided!"))]
     NoAdminSecret,
+
+    #[snafu(display("{}", source))]
+    Wait { source: jobs::Error },
 }
@@ -0,0 +1,99 @@
+//! Waiting for the jobs submitted by admin commands.
+//!
+//! Docspell doesn't return the id of the job it submits. So the jobs
+//! in the queue are remembered before submitting, and the first new
+//! job whose subject contains a keyword is the one to wait for. The
+//! queue only shows jobs of the collective of the logged in user,
+//! which might not include all jobs started by an admin command.
+
+use snafu::{ResultExt, Snafu};
+use std::collections::HashSet;
+use std::time::Duration;
+
+use super::Context;
+use crate::http::payload::JobDetail;
+use crate::http::Error as HttpError;
+use crate::status;
+
+/// How long to wait between asking for the queue.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often to look for the job, before giving up.
+const MAX_MISSES: u32 = 5;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error getting the job queue: {}", source))]
+    Queue { source: HttpError },
+
+    #[snafu(display("The job '{}' finished with state: {}", subject, state))]
+    JobFailed { subject: String, state: String },
+}
+
+/// The jobs present before submitting a new one.
+pub struct JobWait {
+    keyword: String,
+    known: HashSet<String>,
+}
+
+impl JobWait {
+    /// Remembers the current jobs. Call this before submitting the
+    /// job to wait for, whose subject contains `keyword`.
+    pub fn prepare(keyword: &str, ctx: &Context) -> Result<JobWait, Error> {
+        let state = ctx.client.queue_state(&ctx.opts.session).context(Queue)?;
+        Ok(JobWait {
+            keyword: keyword.to_lowercase(),
+            known: state.jobs().map(|j| j.id.clone()).collect(),
+        })
+    }
+
+    /// Polls the queue until all new jobs have finished and returns
+    /// how many there were. It is an error if a job didn't succeed.
+    /// If no job shows up, `0` is returned.
+    pub fn wait(&self, ctx: &Context) -> Result<usize, Error> {
+        let mut misses = 0;
+        let mut last_state = String::new();
+        loop {
+            let state = ctx.client.queue_state(&ctx.opts.session).context(Queue)?;
+            let jobs: Vec<&JobDetail> = state.jobs().filter(|j| self.is_new(j)).collect();
+            let done = jobs.iter().filter(|j| j.is_finished()).count();
+            if jobs.is_empty() {
+                misses += 1;
+                if misses >= MAX_MISSES {
+                    status!(
+                        "The job doesn't show up in the queue of this account, not waiting for it."
+                    );
+                    return Ok(0);
+                }
+            } else if done == jobs.len() {
+                return match jobs.iter().find(|j| !j.is_success()) {
+                    Some(job) => Err(Error::JobFailed {
+                        subject: job.subject.clone(),
+                        state: job.state.clone(),
+                    }),
+                    None => {
+                        status!("Finished {} job(s) successfully", jobs.len());
+                        Ok(jobs.len())
+                    }
+                };
+            } else {
+                let current = match jobs.as_slice() {
+                    [job] => match job.progress {
+                        Some(p) => format!("Job '{}': {} ({}%)", job.subject, job.state, p),
+                        None => format!("Job '{}': {}", job.subject, job.state),
+                    },
+                    _ => format!("Jobs: {} of {} done", done, jobs.len()),
+                };
+                if current != last_state {
+                    status!("{}", current);
+                    last_state = current;
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn is_new(&self, job: &JobDetail) -> bool {
+        !self.known.contains(&job.id) && job.subject.to_lowercase().contains(&self.keyword)
+    }
+}
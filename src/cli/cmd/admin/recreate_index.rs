@@ -1,6 +1,7 @@
 use clap::Clap;
 use snafu::{ResultExt, Snafu};
 
+use super::jobs::{self, JobWait};
 use super::AdminCmd;
 use super::Context;
 use crate::cli::sink::Error as SinkError;
@@ -9,14 +10,28 @@ use crate::http::Error as HttpError;
 
 /// Submits a task to re-create the entire fulltext search index.
 #[derive(Clap, std::fmt::Debug)]
-pub struct Input {}
+pub struct Input {
+    /// Wait until the job has finished, showing its progress. This
+    /// requires to be logged in, because the job is looked up in the
+    /// queue of your collective. The command fails if the job fails.
+    #[clap(long)]
+    pub wait: bool,
+}
 
 impl AdminCmd for Input {
     type CmdError = Error;
 
     fn exec(&self, admin_opts: &super::Input, ctx: &Context) -> Result<(), Error> {
+        let waiting = match self.wait {
+            true => Some(JobWait::prepare("index", ctx).context(Wait)?),
+            false => None,
+        };
         let result = recreate_index(admin_opts, ctx)?;
+        let success = result.success;
         ctx.write_result(result).context(WriteResult)?;
+        if let (true, Some(w)) = (success, waiting) {
+            w.wait(ctx).context(Wait)?;
+        }
         Ok(())
     }
 }
@@ -31,6 +46,9 @@ pub enum Error {
 
     #[snafu(display("No admin secret provided!"))]
     NoAdminSecret,
+
+    #[snafu(display("{}", source))]
+    Wait { source: jobs::Error },
 }
 
 pub fn recreate_index(admin_opts: &super::Input, ctx: &Context) -> Result<BasicResult, Error> {
@@ -0,0 +1,134 @@
+use clap::{ArgGroup, Clap};
+use dialoguer::Confirm;
+use snafu::{ResultExt, Snafu};
+
+use super::jobs::{self, JobWait};
+use super::AdminCmd;
+use super::Context;
+use crate::cli::cmd::{login, search};
+use crate::cli::opts::SearchMode;
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{BasicResult, IdList};
+use crate::http::Error as HttpError;
+
+/// Submits tasks to process the files of items again.
+///
+/// All items matching the query are processed again. To process all
+/// items, use `--all-items`, which must be confirmed unless `--yes`
+/// is given. Unlike the other admin commands, this doesn't need the
+/// admin secret, but uses the current session.
+#[derive(Clap, std::fmt::Debug)]
+#[clap(group = ArgGroup::new("target").required(true))]
+pub struct Input {
+    /// The query selecting the items. See
+    /// https://docspell.org/docs/query/
+    #[clap(long, short, group = "target")]
+    pub query: Option<String>,
+
+    /// Process all items again. Add `--all` to include the items in
+    /// the trash.
+    #[clap(long, group = "target")]
+    pub all_items: bool,
+
+    /// Don't ask for confirmation with `--all-items`.
+    #[clap(long, short)]
+    pub yes: bool,
+
+    #[clap(flatten)]
+    pub search_mode: SearchMode,
+
+    /// Wait until all jobs have finished, showing their progress. The
+    /// command fails if a job fails.
+    #[clap(long)]
+    pub wait: bool,
+}
+
+impl AdminCmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, _admin_opts: &super::Input, ctx: &Context) -> Result<(), Error> {
+        let query = self.query.as_deref().unwrap_or_default();
+        let items =
+            search::find_items(ctx, query, self.search_mode.to_mode()).context(HttpClient)?;
+        if self.all_items && !items.is_empty() {
+            confirm(self, items.len())?;
+        }
+        let waiting = match self.wait {
+            true => Some(JobWait::prepare(JOB_KEYWORD, ctx).context(Wait)?),
+            false => None,
+        };
+        for chunk in items.chunks(CHUNK_SIZE) {
+            let req = IdList {
+                ids: chunk.iter().map(|i| i.id.clone()).collect(),
+            };
+            let result = ctx
+                .client
+                .reprocess_items(&ctx.opts.session, &req)
+                .context(HttpClient)?;
+            if !result.success {
+                return Err(Error::ReprocessFailed {
+                    message: result.message,
+                });
+            }
+        }
+        ctx.write_result(BasicResult {
+            success: true,
+            message: format!("Submitted {} item(s) for reprocessing", items.len()),
+        })
+        .context(WriteResult)?;
+        if let (false, Some(w)) = (items.is_empty(), waiting) {
+            w.wait(ctx).context(Wait)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Reprocessing failed: {}", message))]
+    ReprocessFailed { message: String },
+
+    #[snafu(display("Error reading the confirmation: {}", source))]
+    Prompt { source: std::io::Error },
+
+    #[snafu(display(
+        "Reprocessing all items requires a confirmation, use --yes when not running interactively"
+    ))]
+    ConfirmRequired,
+
+    #[snafu(display("Reprocessing was cancelled"))]
+    Cancelled,
+
+    #[snafu(display("{}", source))]
+    Wait { source: jobs::Error },
+}
+
+fn confirm(opts: &Input, count: usize) -> Result<(), Error> {
+    if opts.yes {
+        return Ok(());
+    }
+    if !login::is_interactive() {
+        return Err(Error::ConfirmRequired);
+    }
+    let ok = Confirm::new()
+        .with_prompt(format!("Process all {} item(s) again?", count))
+        .default(false)
+        .interact()
+        .context(Prompt)?;
+    if ok {
+        Ok(())
+    } else {
+        Err(Error::Cancelled)
+    }
+}
+
+/// A part of the subject of the jobs reprocessing items.
+const JOB_KEYWORD: &str = "process";
+
+const CHUNK_SIZE: usize = 100;
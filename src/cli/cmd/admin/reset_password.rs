@@ -8,8 +8,15 @@ use crate::http::payload::{Account, ResetPasswordResp};
 use crate::http::Error as HttpError;
 
 /// Resets the password of the given account.
+///
+/// The new password is printed in the selected format. To print only
+/// the password, use `--template '{{new_password}}'`. The command
+/// fails if the server didn't reset the password, for example if the
+/// account doesn't exist.
 #[derive(Clap, std::fmt::Debug)]
 pub struct Input {
+    /// The account to reset the password of, either `user` or
+    /// `collective/user`.
     #[clap(long, short, value_hint = ValueHint::Username)]
     pub account: String,
 }
@@ -19,6 +26,11 @@ impl AdminCmd for Input {
 
     fn exec(&self, admin_opts: &super::Input, ctx: &Context) -> Result<(), Error> {
         let result = reset_password(self, admin_opts, ctx)?;
+        if !result.success {
+            return Err(Error::ResetFailed {
+                message: result.message,
+            });
+        }
         ctx.write_result(result).context(WriteResult)?;
         Ok(())
     }
@@ -34,6 +46,9 @@ pub enum Error {
 
     #[snafu(display("No admin secret provided!"))]
     NoAdminSecret,
+
+    #[snafu(display("Resetting the password failed: {}", message))]
+    ResetFailed { message: String },
 }
 
 pub fn reset_password(
@@ -2,9 +2,12 @@ use clap::Clap;
 use snafu::{ResultExt, Snafu};
 use std::path::{Path, PathBuf};
 
+use super::upload::cache::{self, UploadCache};
 use super::{Cmd, Context};
+use crate::cli::dry_run::{DryRun, PlannedFile};
 use crate::http::Error as HttpError;
-use crate::util::{digest, file};
+use crate::status;
+use crate::util::{digest, file, quiet};
 use crate::{
     cli::opts::{EndpointOpts, FileAction},
     util::file::FileActionResult,
@@ -23,6 +26,9 @@ use crate::{cli::sink::Error as SinkError, http::payload::BasicResult};
 /// When using the integration endpoint and a collective is not
 /// specified, it will be guessed from the first subdirectory of the
 /// directory that is specified.
+///
+/// With `--cache`, the local upload cache is cleaned instead: entries
+/// of files that don't exist anymore or have changed are removed.
 #[derive(Clap, Debug)]
 pub struct Input {
     #[clap(flatten)]
@@ -31,13 +37,21 @@ pub struct Input {
     #[clap(flatten)]
     pub action: FileAction,
 
-    /// Each file is printed.
+    /// Doesn't delete or move any file, but prints which files exist
+    /// in Docspell and what would happen to them.
     #[clap(long)]
     pub dry_run: bool,
 
+    /// Remove stale entries from the upload cache instead of checking
+    /// files. The cache file can be given as `--cache=FILE`,
+    /// otherwise the `upload_cache` setting or the default file is
+    /// used.
+    #[clap(long, require_equals = true, conflicts_with_all = &["files", "delete", "move-to", "dry-run"])]
+    pub cache: Option<Option<PathBuf>>,
+
     /// One or more files/directories to check. Directories are
     /// traversed recursively.
-    #[clap(required = true, min_values = 1)]
+    #[clap(required_unless_present = "cache", min_values = 1)]
     pub files: Vec<PathBuf>,
 }
 
@@ -67,6 +81,12 @@ pub enum Error {
     #[snafu(display("The target '{}' is not a directory!", path.display()))]
     TargetNotDirectory { path: PathBuf },
 
+    #[snafu(display("No upload cache file could be determined"))]
+    NoCacheFile,
+
+    #[snafu(display("{}", source))]
+    Cache { source: cache::Error },
+
     #[snafu(display("Calculating digest of file {} failed: {}", path.display(), source))]
     DigestFail {
         source: std::io::Error,
@@ -78,7 +98,15 @@ impl Cmd for Input {
     type CmdError = Error;
 
     fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        if let Some(file) = &self.cache {
+            let result = prune_cache(file.as_ref(), ctx)?;
+            return ctx.write_result(result).context(WriteResult);
+        }
         check_args(self)?;
+        if self.dry_run {
+            let plan = dry_run(self, ctx)?;
+            return ctx.write_result(plan).context(WriteResult);
+        }
         let result = cleanup(self, ctx)?;
         ctx.write_result(BasicResult {
             success: true,
@@ -108,22 +136,72 @@ fn check_args(args: &Input) -> Result<(), Error> {
     }
 }
 
+/// Removes entries of missing or changed files from the upload cache.
+fn prune_cache(file: Option<&PathBuf>, ctx: &Context) -> Result<BasicResult, Error> {
+    let file = file
+        .cloned()
+        .or_else(|| UploadCache::configured_file(ctx.cfg))
+        .ok_or(Error::NoCacheFile)?;
+    let cache = UploadCache::load(&file).context(Cache)?;
+    let removed = cache.prune();
+    cache.store().context(Cache)?;
+    Ok(BasicResult {
+        success: true,
+        message: format!(
+            "Removed {} stale entries from the upload cache, {} remaining",
+            removed,
+            cache.len()
+        ),
+    })
+}
+
 fn cleanup(args: &Input, ctx: &Context) -> Result<u32, Error> {
     let mut counter = 0;
+    for (file, root) in collect_files(args)? {
+        counter += cleanup_and_report(&file, root, args, ctx)?;
+    }
+    Ok(counter)
+}
+
+/// Checks all files, but only reports what would be deleted or moved.
+fn dry_run(args: &Input, ctx: &Context) -> Result<DryRun, Error> {
+    let mut files = Vec::new();
+    for (file, root) in collect_files(args)? {
+        let exists = check_file_exists(&file, root, &args.endpoint, ctx)?;
+        let action = if exists {
+            args.action.plan(&file, root)
+        } else {
+            FileActionResult::Nothing
+        };
+        files.push(PlannedFile::new(file, exists, false, action));
+    }
+    Ok(DryRun {
+        endpoint: ctx
+            .client
+            .checkfile_url(&args.endpoint.to_file_auth(ctx), "{sha256}"),
+        meta: None,
+        files,
+    })
+}
+
+/// Returns all files to check, together with the directory they were
+/// found in.
+fn collect_files(args: &Input) -> Result<Vec<(PathBuf, Option<&PathBuf>)>, Error> {
+    let mut files = Vec::new();
     for file in &args.files {
         if file.is_dir() {
             let pattern = file.join("**/*").display().to_string();
             for child in glob::glob(&pattern).context(Pattern)? {
                 let cf = child.context(Glob)?;
                 if cf.is_file() {
-                    counter += cleanup_and_report(&cf, Some(file), args, ctx)?;
+                    files.push((cf, Some(file)));
                 }
             }
         } else {
-            counter += cleanup_and_report(file, None, args, ctx)?;
+            files.push((file.clone(), None));
         }
     }
-    Ok(counter)
+    Ok(files)
 }
 
 fn cleanup_and_report(
@@ -132,31 +210,28 @@ fn cleanup_and_report(
     args: &Input,
     ctx: &Context,
 ) -> Result<u32, Error> {
-    eprintln!("Check file: {}", file.display());
+    status!("Check file: {}", file.display());
     let exists = check_file_exists(file, root, &args.endpoint, ctx)?;
     log::debug!("Checking file: {} (exists: {})", file.display(), exists);
     if exists {
-        eprint!(" - exists: ");
-        if !args.dry_run {
-            let res = args.action.execute(file, root).context(FileActionError)?;
-            log::debug!("Action executed: {:?}", res);
-            match res {
-                FileActionResult::Deleted(_p) => {
-                    eprintln!("deleted.");
-                    return Ok(1);
-                }
-                FileActionResult::Moved(_p) => {
-                    eprintln!("moved.");
-                    return Ok(1);
-                }
-                FileActionResult::Nothing => {
-                    log::error!("No file action defined. This should not happen, because user was able to not define it");
-                    return Ok(0);
-                }
+        if !quiet::is_quiet() {
+            eprint!(" - exists: ");
+        }
+        let res = args.action.execute(file, root).context(FileActionError)?;
+        log::debug!("Action executed: {:?}", res);
+        match res {
+            FileActionResult::Deleted(_p) => {
+                status!("deleted.");
+                return Ok(1);
+            }
+            FileActionResult::Moved(_p) => {
+                status!("moved.");
+                return Ok(1);
+            }
+            FileActionResult::Nothing => {
+                log::error!("No file action defined. This should not happen, because user was able to not define it");
+                return Ok(0);
             }
-        } else {
-            eprintln!("{}", exists);
-            return Ok(1);
         }
     }
     Ok(0)
@@ -0,0 +1,76 @@
+use clap::{ArgEnum, Clap};
+use snafu::{ResultExt, Snafu};
+use std::io::Write;
+
+use super::{Cmd, Context};
+use crate::http::Error as HttpError;
+
+/// Prints candidates for completing option values, one per line.
+///
+/// This is used by the completion scripts from `generate-completions`
+/// and asks the server for the names of tags and folders or the ids
+/// of sources. Errors are only logged, so a shell completion doesn't
+/// print garbage if the server is not reachable or there is no valid
+/// session.
+#[derive(Clap, Debug)]
+pub struct Input {
+    #[clap(arg_enum)]
+    pub values: Values,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Values {
+    Tags,
+    Folders,
+    Sources,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error writing completions: {}", source))]
+    WriteOut { source: std::io::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        match candidates(self.values, ctx) {
+            Ok(values) => {
+                let mut out = std::io::stdout();
+                for v in values {
+                    writeln!(out, "{}", v).context(WriteOut)?;
+                }
+            }
+            Err(err) => log::debug!("Cannot get {:?} for completion: {}", self.values, err),
+        }
+        Ok(())
+    }
+}
+
+fn candidates(values: Values, ctx: &Context) -> Result<Vec<String>, HttpError> {
+    let token = &ctx.opts.session;
+    Ok(match values {
+        Values::Tags => ctx
+            .client
+            .list_tags(token, "")?
+            .items
+            .into_iter()
+            .map(|t| t.name)
+            .collect(),
+        Values::Folders => ctx
+            .client
+            .list_folders(token, "")?
+            .items
+            .into_iter()
+            .map(|f| f.name)
+            .collect(),
+        Values::Sources => ctx
+            .client
+            .list_sources(token)?
+            .items
+            .into_iter()
+            .map(|s| s.source.id)
+            .collect(),
+    })
+}
@@ -0,0 +1,42 @@
+pub mod check;
+pub mod show;
+
+use clap::{AppSettings, Clap};
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+
+/// Inspect the configuration.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    #[clap(subcommand)]
+    pub subcmd: ConfigCommand,
+}
+
+#[derive(Clap, Debug)]
+pub enum ConfigCommand {
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Show(show::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Check(check::Input),
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    Show { source: show::Error },
+    Check { source: check::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        match &self.subcmd {
+            ConfigCommand::Show(input) => input.exec(ctx).context(Show),
+            ConfigCommand::Check(input) => input.exec(None, ctx.opts, ctx.cfg).context(Check),
+        }
+    }
+}
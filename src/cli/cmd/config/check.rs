@@ -0,0 +1,387 @@
+use clap::{Clap, IntoApp};
+use reqwest::header::HeaderValue;
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use std::path::PathBuf;
+
+use crate::cli::cmd::write_value;
+use crate::cli::opts::{CommonOpts, MainOpts};
+use crate::cli::sink::{Error as SinkError, Output};
+use crate::config::{ConfigError, DsConfig, Profile};
+use crate::http::util::normalize_base_url;
+use crate::util::query_alias;
+
+/// Checks the config file for mistakes.
+///
+/// Syntax errors are reported with their line and column. Unknown
+/// keys are reported as warnings, because they are silently ignored
+/// otherwise. Invalid values, like a malformed url, an unknown format,
+/// a missing certificate file or an unset env variable, are errors.
+///
+/// The command fails if there is at least one error. With `--strict`
+/// warnings are treated as errors.
+#[derive(Clap, Debug)]
+pub struct Input {
+    /// Also fail if there are warnings.
+    #[clap(long)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error locating the config file: {}", source))]
+    LocateConfig { source: ConfigError },
+
+    #[snafu(display("Unable to read config file {}: {}", path.display(), source))]
+    ReadFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("The config file has {} error(s) and {} warning(s)", errors, warnings))]
+    Invalid { errors: usize, warnings: usize },
+}
+
+/// The result of checking a config file.
+#[derive(Serialize, Debug)]
+pub struct ConfigCheck {
+    pub file: Option<PathBuf>,
+    pub valid: bool,
+    pub issues: Vec<Issue>,
+}
+
+/// A problem found in the config file.
+#[derive(Serialize, Debug)]
+pub struct Issue {
+    pub level: Level,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        }
+    }
+}
+
+impl Input {
+    /// Checks the config file. This doesn't need a
+    /// [`crate::cli::cmd::Context`], so it works even if the config
+    /// can't be used to create one.
+    ///
+    /// The config file is given by `cfg`, if it could be read.
+    /// Otherwise it is looked up like for every other command.
+    pub fn exec(
+        &self,
+        file: Option<&PathBuf>,
+        opts: &CommonOpts,
+        cfg: &DsConfig,
+    ) -> Result<(), Error> {
+        let file = match &cfg.config_file {
+            Some(f) => Some(f.clone()),
+            None => DsConfig::locate(file).context(LocateConfig)?,
+        };
+        let issues = match &file {
+            Some(path) => {
+                let cnt = std::fs::read_to_string(path).context(ReadFile { path })?;
+                check_content(&cnt)
+            }
+            None => vec![],
+        };
+
+        let errors = issues.iter().filter(|i| i.level == Level::Error).count();
+        let warnings = issues.len() - errors;
+        let valid = errors == 0 && (!self.strict || warnings == 0);
+        let result = ConfigCheck {
+            file,
+            valid,
+            issues,
+        };
+        let fmt = opts.format.unwrap_or_else(|| cfg.format_for("config"));
+        let mut out = Output::create(opts.output.as_deref()).context(WriteResult)?;
+        write_value(&mut out, opts, fmt, &opts.columns, &result).context(WriteResult)?;
+        out.finish().context(WriteResult)?;
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::Invalid { errors, warnings })
+        }
+    }
+}
+
+/// Checks the contents of a config file.
+fn check_content(cnt: &str) -> Vec<Issue> {
+    let value: toml::Value = match toml::from_str(cnt) {
+        Ok(v) => v,
+        Err(err) => return vec![toml_error(&err, cnt)],
+    };
+    let mut issues = unknown_keys(&value, cnt);
+    match toml::from_str::<DsConfig>(cnt).map(DsConfig::expand_env) {
+        Ok(Ok(cfg)) => issues.extend(check_values(&cfg, cnt)),
+        Ok(Err(err)) => {
+            let line = match &err {
+                ConfigError::UnsetVariable { key, .. } | ConfigError::InvalidVariable { key } => {
+                    find_line(cnt, key.rsplit('.').next().unwrap_or(key))
+                }
+                _ => None,
+            };
+            issues.push(Issue {
+                level: Level::Error,
+                line,
+                column: None,
+                message: err.to_string(),
+            })
+        }
+        Err(err) => issues.push(toml_error(&err, cnt)),
+    }
+    issues
+}
+
+fn toml_error(err: &toml::de::Error, cnt: &str) -> Issue {
+    let pos = err.line_col();
+    let msg = err.to_string();
+    // the position is given separately
+    let message = match msg.rfind(" at line ") {
+        Some(idx) if pos.is_some() => msg[..idx].to_string(),
+        _ => msg,
+    };
+    // Errors about values point to the start of the table, the key
+    // is more helpful.
+    let key_line = message
+        .rsplit_once("for key `")
+        .and_then(|(_, key)| key.strip_suffix('`'))
+        .and_then(|key| find_line(cnt, key.rsplit('.').next().unwrap_or(key)));
+    match key_line {
+        Some(line) => Issue {
+            level: Level::Error,
+            line: Some(line),
+            column: None,
+            message,
+        },
+        None => Issue {
+            level: Level::Error,
+            line: pos.map(|(l, _)| l + 1),
+            column: pos.map(|(_, c)| c + 1),
+            message,
+        },
+    }
+}
+
+fn unknown_keys(value: &toml::Value, cnt: &str) -> Vec<Issue> {
+    let mut known = key_names(&DsConfig::default());
+    // These are not serialized when empty.
+    known.extend(
+        ["profile", "aliases", "default_tags", "format"]
+            .iter()
+            .map(|k| k.to_string()),
+    );
+    let profile_keys = key_names(&Profile::default());
+
+    let mut issues = Vec::new();
+    let table = match value.as_table() {
+        Some(t) => t,
+        None => return issues,
+    };
+    for (key, value) in table {
+        if !known.contains(key) {
+            issues.push(warning(cnt, key, format!("Unknown key '{}'", key)));
+        } else if key == "profile" {
+            let profiles = value.as_table().into_iter().flatten();
+            for (name, profile) in profiles {
+                let keys = profile.as_table().into_iter().flat_map(|t| t.keys());
+                for pkey in keys.filter(|k| !profile_keys.contains(k)) {
+                    let msg = format!("Unknown key '{}' in profile '{}'", pkey, name);
+                    issues.push(warning(cnt, pkey, msg));
+                }
+            }
+        } else if key == "format" {
+            let commands = command_names();
+            let keys = value.as_table().into_iter().flat_map(|t| t.keys());
+            for cmd in keys.filter(|k| !commands.contains(k)) {
+                let msg = format!("Unknown subcommand '{}' in format", cmd);
+                issues.push(warning(cnt, cmd, msg));
+            }
+        }
+    }
+    issues
+}
+
+/// Returns the names of all subcommands.
+fn command_names() -> Vec<String> {
+    MainOpts::into_app()
+        .get_subcommands()
+        .map(|c| c.get_name().to_string())
+        .collect()
+}
+
+/// Returns the names of all fields, as they appear in the config
+/// file.
+fn key_names<S: Serialize>(value: &S) -> Vec<String> {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::Object(map)) => map.keys().cloned().collect(),
+        _ => vec![],
+    }
+}
+
+fn check_values(cfg: &DsConfig, cnt: &str) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut error = |key: &str, message: String| {
+        issues.push(Issue {
+            level: Level::Error,
+            line: find_line(cnt, key),
+            column: None,
+            message,
+        })
+    };
+
+    if let Err(msg) = normalize_base_url(&cfg.docspell_url) {
+        error("docspell_url", format!("Invalid docspell_url: {}", msg));
+    }
+    for (name, profile) in &cfg.profile {
+        if let Some(url) = &profile.docspell_url {
+            if let Err(msg) = normalize_base_url(url) {
+                let msg = format!("Invalid docspell_url in profile '{}': {}", name, msg);
+                error("docspell_url", msg);
+            }
+        }
+    }
+    if let Some(proxy) = &cfg.proxy {
+        if let Err(msg) = check_url(proxy, &["http", "https", "socks5", "socks5h"]) {
+            error("proxy", format!("Invalid proxy: {}", msg));
+        }
+    }
+    if let Some(agent) = &cfg.user_agent {
+        if HeaderValue::from_str(agent).is_err() {
+            error(
+                "user_agent",
+                "Invalid user_agent, it must be a valid header value".into(),
+            );
+        }
+    }
+    let files = [
+        ("client_cert", &cfg.client_cert),
+        ("client_key", &cfg.client_key),
+        ("ca_cert", &cfg.ca_cert),
+    ];
+    for (key, file) in files.iter() {
+        if let Some(path) = file {
+            if !path.is_file() {
+                error(
+                    key,
+                    format!("The {} file {} doesn't exist", key, path.display()),
+                );
+            }
+        }
+    }
+    if cfg.client_cert.is_some() != cfg.client_key.is_some() {
+        let key = if cfg.client_cert.is_some() {
+            "client_cert"
+        } else {
+            "client_key"
+        };
+        error(
+            key,
+            "client_cert and client_key must be given together".into(),
+        );
+    }
+    if let Some(name) = &cfg.default_profile {
+        if !cfg.profile.contains_key(name) {
+            let msg = format!("The default_profile '{}' is not defined", name);
+            error("default_profile", msg);
+        }
+    }
+    for name in cfg.aliases.keys() {
+        if let Err(err) = query_alias::expand(&format!("@{}", name), &cfg.aliases) {
+            error(name, format!("Invalid alias '{}': {}", name, err));
+        }
+    }
+    issues
+}
+
+fn check_url(url: &str, schemes: &[&str]) -> Result<(), String> {
+    match reqwest::Url::parse(url) {
+        Ok(u) if schemes.contains(&u.scheme()) => Ok(()),
+        Ok(u) => Err(format!(
+            "unsupported scheme '{}', expected one of {}",
+            u.scheme(),
+            schemes.join(", ")
+        )),
+        Err(err) => Err(format!("'{}': {}", url, err)),
+    }
+}
+
+fn warning(cnt: &str, key: &str, message: String) -> Issue {
+    Issue {
+        level: Level::Warning,
+        line: find_line(cnt, key),
+        column: None,
+        message,
+    }
+}
+
+/// Finds the (first) line where the given key is defined.
+fn find_line(cnt: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"", key);
+    cnt.lines()
+        .position(|line| {
+            let line = line.trim_start();
+            [key, quoted.as_str()].iter().any(|k| {
+                line.strip_prefix(k)
+                    .map(|rest| rest.trim_start().starts_with('='))
+                    .unwrap_or(false)
+            })
+        })
+        .map(|idx| idx + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_check_content() {
+        let issues = check_content(
+            r#"
+docspell_url = "localhost:7880"
+default_format = "Tabular"
+pdf_viewer = []
+colour = "red"
+
+[profile.test]
+docspell_url = "http://test:7880"
+sesion_file = "/tmp/token.json"
+"#,
+        );
+        let found: Vec<(Level, Option<usize>)> = issues.iter().map(|i| (i.level, i.line)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (Level::Warning, Some(5)),
+                (Level::Warning, Some(9)),
+                (Level::Error, Some(2))
+            ]
+        );
+
+        let issues = check_content("docspell_url = \"http://localhost\"\ndefault_format = ");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(2));
+
+        let issues = check_content("pdf_viewer = []\n\ndefault_format = \"Foo\"\n");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, Some(3));
+    }
+}
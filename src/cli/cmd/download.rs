@@ -1,33 +1,62 @@
-use clap::{ArgEnum, ArgGroup, Clap};
+use clap::{ArgEnum, ArgGroup, Clap, ValueHint};
+use sha2::Sha256;
 use snafu::{ResultExt, Snafu};
+use std::io::{Seek, SeekFrom, Write};
 use std::path::{Display, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use super::{Cmd, Context};
 use crate::http::payload::SearchReq;
+use crate::status;
 use crate::{
-    cli::opts::SearchMode,
-    http::{Downloads, Error as HttpError},
+    cli::opts::{NameFilter, SearchMode},
+    http::{Download, DownloadRef, Downloads, Error as HttpError},
+    util::digest::DigestWriter,
     util::dupes::Dupes,
+    util::events::{self, Event, Tracked},
+    util::file,
+    util::parallel,
+    util::progress::Progress,
 };
 
 /// Downloads files given a query.
 ///
 /// Searches for documents via a query and downloads all associated
-/// files. It downloads by default the converted PDF files, which can
-/// be changed using options `--original` and `--archive`,
-/// respectively.
+/// files. It downloads by default the original files, which can be
+/// changed using `--variant`. If the chosen file doesn't exist for
+/// some attachment, the command fails after downloading the others.
+/// With the older `--archive`, attachments without an archive are
+/// skipped instead.
 ///
 /// Use the `search-summary` command with the same query to get an
 /// idea how much is being downloaded.
+///
+/// If the server provides a checksum for a file, the downloaded bytes
+/// are verified against it. If any file doesn't match, the command
+/// fails after all files have been downloaded.
+///
+/// Files are first written to a `.part` file, which is renamed once
+/// the download is complete. If a `.part` file already exists from an
+/// interrupted download, the download is resumed if the server
+/// supports it. This doesn't apply to `--zip`.
 #[derive(Clap, std::fmt::Debug)]
 #[clap(group = ArgGroup::new("kind"))]
 pub struct Input {
     /// The query string. See https://docspell.org/docs/query/
-    query: String,
+    #[clap(required_unless_present_any = &["query-opt", "name-like"])]
+    query: Option<String>,
+
+    /// The query string, as an alternative to the argument.
+    #[clap(long = "query", conflicts_with = "query")]
+    query_opt: Option<String>,
 
     #[clap(flatten)]
     pub search_mode: SearchMode,
 
+    #[clap(flatten)]
+    pub name_filter: NameFilter,
+
     /// Limit the number of results.
     #[clap(short, long, default_value = "60")]
     limit: u32,
@@ -43,21 +72,32 @@ pub struct Input {
     #[clap(long)]
     overwrite: bool,
 
-    /// Download the original file instead of the converted PDF.
+    /// Which file of each attachment to download: `original` for
+    /// the uploaded file, `converted` for the PDF created by docspell
+    /// or `archive` for the archive (like a zip or e-mail file) the
+    /// attachment was extracted from. Since often multiple files map
+    /// to a single archive, the option `--dupes skip` can be used with
+    /// `archive`. The default is `original`.
+    #[clap(long, arg_enum, group = "kind")]
+    variant: Option<Variant>,
+
+    /// Download the original file. This is the same as `--variant
+    /// original`.
     #[clap(long, group = "kind")]
     original: bool,
 
-    /// Download the original archive file to the attachment if
-    /// available. Since often multiple files map to a single archive,
-    /// the option `--dupes skip` can be used here.
+    /// Download the archive file. This is the same as `--variant
+    /// archive`, except that attachments without an archive are only
+    /// skipped.
     #[clap(long, group = "kind")]
     archive: bool,
 
-    /// Creates a single zip file containing all files (flat). If this
-    /// is enabled, the `target` option is expected to be the target
-    /// zip file and not a directory.
-    #[clap(long)]
-    zip: bool,
+    /// Creates a single zip file containing all files (flat). The
+    /// zip file can be given as `--zip=FILE`, where `-` writes it to
+    /// stdout. Otherwise the `target` option is expected to be the
+    /// target zip file and not a directory.
+    #[clap(long, require_equals = true, value_hint = ValueHint::FilePath)]
+    zip: Option<Option<PathBuf>>,
 
     /// What to do when multiple files map to the same name. Can be
     /// one of: skip, rename. For rename, the target file is renamed
@@ -65,20 +105,121 @@ pub struct Input {
     #[clap(long, arg_enum, default_value = "rename")]
     dupes: DupeMode,
 
+    /// How to name the downloaded files. The placeholders `{file}`
+    /// (the file name sent by the server), `{ext}` (its extension),
+    /// `{item}` (the item name) and `{item_id}` are replaced. The
+    /// default is `{file}`.
+    #[clap(long, default_value = "{file}")]
+    name_template: String,
+
     /// Download everything into this directory. If not given, the
-    /// current working directory is used. If `--zip` is used, this is
-    /// the zip file to create.
+    /// global `--output` or the current working directory is used. If
+    /// `--zip` is used, this is the zip file to create.
     #[clap(short, long)]
     target: Option<PathBuf>,
+
+    /// Verify each file against the checksum sent by the server, if
+    /// available. This is the default.
+    #[clap(long, overrides_with = "no-verify")]
+    verify: bool,
+
+    /// Don't verify the checksum of downloaded files.
+    #[clap(long, overrides_with = "verify")]
+    no_verify: bool,
+
+    /// Don't resume interrupted downloads, but always download the
+    /// complete file.
+    #[clap(long)]
+    no_resume: bool,
+
+    /// Download this many files in parallel. If some downloads fail,
+    /// the remaining files are still downloaded and the failed ones
+    /// are reported at the end. Not available with `--zip`.
+    #[clap(long, default_value = "1", conflicts_with = "zip")]
+    concurrency: usize,
 }
+const PLACEHOLDERS: [&str; 4] = ["{file}", "{ext}", "{item}", "{item_id}"];
+
 impl Input {
-    fn download_type(&self) -> &'static str {
-        if self.original {
-            "original"
-        } else if self.archive {
-            "archive"
-        } else {
-            "attachment"
+    /// Whether a missing file of the variant is an error. The older
+    /// `--archive` only skips attachments without an archive.
+    fn fails_on_missing(&self) -> bool {
+        self.variant.is_some()
+    }
+
+    fn variant(&self) -> Variant {
+        match self.variant {
+            Some(v) => v,
+            None if self.archive => Variant::Archive,
+            None => Variant::Original,
+        }
+    }
+
+    fn query(&self) -> String {
+        self.query_opt
+            .clone()
+            .or_else(|| self.query.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the name for the file of the given attachment.
+    fn file_name(&self, dref: &DownloadRef, file: &str) -> String {
+        let ext = Path::new(file)
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = self
+            .name_template
+            .replace("{file}", file)
+            .replace("{ext}", &ext)
+            .replace("{item}", &dref.item_name)
+            .replace("{item_id}", &dref.item_id);
+        file::safe_filename(name.trim_end_matches('.'))
+    }
+
+    fn verify(&self) -> bool {
+        !self.no_verify
+    }
+
+    fn target(&self, ctx: &Context) -> Option<PathBuf> {
+        self.target.clone().or_else(|| ctx.opts.output.clone())
+    }
+
+    fn is_zip(&self) -> bool {
+        self.zip.is_some()
+    }
+
+    /// The zip file to create, where `-` means stdout.
+    fn zip_file(&self, ctx: &Context) -> PathBuf {
+        self.zip
+            .clone()
+            .flatten()
+            .or_else(|| self.target(ctx))
+            .unwrap_or_else(|| PathBuf::from("docspell-files.zip"))
+    }
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Original,
+    Converted,
+    Archive,
+}
+
+impl Variant {
+    fn name(self) -> &'static str {
+        match self {
+            Variant::Original => "original",
+            Variant::Converted => "converted",
+            Variant::Archive => "archive",
+        }
+    }
+
+    fn fetch(self, dref: &DownloadRef, ctx: &Context) -> Result<Option<Download>, HttpError> {
+        match self {
+            Variant::Original => dref.get_original(&ctx.client, &ctx.opts.session),
+            Variant::Converted => dref.get(&ctx.client, &ctx.opts.session),
+            Variant::Archive => dref.get_archive(&ctx.client, &ctx.opts.session),
         }
     }
 }
@@ -103,62 +244,86 @@ pub enum Error {
     #[snafu(display("{}", given))]
     InvalidDupeMode { given: String },
 
+    #[snafu(display("Unknown placeholder in name template: {}", template))]
+    InvalidNameTemplate { template: String },
+
     #[snafu(display("Not a directory: {}", path.display()))]
     NotADirectory { path: PathBuf },
 
     #[snafu(display("Not a file: {}", path.display()))]
     NotAFile { path: PathBuf },
+
+    #[snafu(display("Checksum verification failed for: {}", files.join(", ")))]
+    ChecksumMismatch { files: Vec<String> },
+
+    #[snafu(display("Downloaded {} file(s), but failed: {}", downloaded, failed.join(", ")))]
+    DownloadFailures {
+        downloaded: usize,
+        failed: Vec<String>,
+    },
+
+    #[snafu(display("No {} file for: {}", variant, files.join(", ")))]
+    MissingVariant {
+        variant: &'static str,
+        files: Vec<String>,
+    },
 }
 
 impl Cmd for Input {
     type CmdError = Error;
 
     fn exec(&self, ctx: &Context) -> Result<(), Error> {
-        check_args(self)?;
+        check_args(self, ctx)?;
         let req = SearchReq {
             offset: self.offset,
             limit: self.limit,
             with_details: true,
-            query: self.query.clone(),
+            query: self.name_filter.add_to_query(&self.query()),
             search_mode: self.search_mode.to_mode(),
         };
-        let attachs = ctx
+        let mut results = ctx
             .client
-            .download_search(&ctx.opts.session, &req)
+            .search(&ctx.opts.session, &req)
             .context(HttpClient)?;
+        self.name_filter.retain(&mut results);
+        let attachs = Downloads::from_results(&results);
 
         if attachs.is_empty() {
-            println!("The search result is empty.");
+            status!("The search result is empty.");
             Ok(())
         } else {
-            match self.zip {
+            match self.is_zip() {
                 true => {
-                    let zip_file = self
-                        .target
-                        .clone()
-                        .unwrap_or_else(|| PathBuf::from("docspell-files.zip"));
+                    let zip_file = self.zip_file(ctx);
+                    if is_stdout(&zip_file) {
+                        status!(
+                            "Zipping {}",
+                            action_msg(self, attachs.len(), Path::new("stdout").display())
+                        );
+                        return download_zip_stdout(attachs, self, ctx);
+                    }
                     if let Some(parent) = zip_file.parent() {
                         if !parent.exists() {
-                            std::fs::create_dir_all(&parent).context(CreateFile)?;
+                            std::fs::create_dir_all(parent).context(CreateFile)?;
                         }
                     }
-                    println!(
+                    status!(
                         "Zipping {}",
                         action_msg(self, attachs.len(), zip_file.display())
                     );
 
-                    download_zip(attachs, self, ctx, &zip_file)
+                    download_zip(attachs, self, ctx, &zip_file, self.overwrite)
                 }
                 false => {
-                    let parent = self
-                        .target
-                        .clone()
-                        .unwrap_or(std::env::current_dir().context(CreateFile)?);
+                    let parent = match self.target(ctx) {
+                        Some(dir) => dir,
+                        None => std::env::current_dir().context(CreateFile)?,
+                    };
 
                     if !parent.exists() {
                         std::fs::create_dir_all(&parent).context(CreateFile)?;
                     }
-                    println!(
+                    status!(
                         "Downloading {}",
                         action_msg(self, attachs.len(), parent.display())
                     );
@@ -176,39 +341,148 @@ fn download_flat(
     ctx: &Context,
     parent: &Path,
 ) -> Result<(), Error> {
-    let mut dupes = Dupes::new();
-    for dref in attachs {
-        let dlopt = if opts.original {
-            dref.get_original(&ctx.client, &ctx.opts.session)
-        } else if opts.archive {
-            dref.get_archive(&ctx.client, &ctx.opts.session)
-        } else {
-            dref.get(&ctx.client, &ctx.opts.session)
+    let total = attachs.len();
+    let workers = parallel::workers(opts.concurrency, total);
+    log::debug!("Downloading {} files using {} worker(s)", total, workers);
+    // a single worker shows a progress for each file instead
+    let progress = match workers {
+        1 => Progress::hidden(),
+        _ => Progress::files(ctx.show_progress(), total as u64, 0),
+    };
+    let dupes = Mutex::new(Dupes::new());
+    let downloaded = AtomicUsize::new(0);
+    let skipped = AtomicUsize::new(0);
+    let mismatched: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    let missing: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    let failed: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    parallel::for_each(attachs, workers, |dref| {
+        match download_ref(&dref, opts, ctx, parent, &dupes, &progress) {
+            Ok(Outcome::Downloaded) => {
+                downloaded.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(Outcome::Skipped) => {
+                skipped.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(Outcome::Mismatch(name)) => mismatched.lock().unwrap().push(name),
+            Ok(Outcome::Missing(name)) => missing.lock().unwrap().push(name),
+            Err(err) => {
+                log::debug!("Error downloading {}: {:?}", dref.name, err);
+                emit_error(&dref.name, &err.to_string());
+                progress.print_error(format!("Downloading {} failed: {}", dref.name, err));
+                failed
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} ({})", dref.name, err));
+            }
         }
-        .context(HttpClient)?;
+        progress.inc_file(0);
+    });
+    progress.finish();
 
-        if let Some(mut dl) = dlopt {
-            let org_name = dl.get_filename().unwrap_or(&dref.name);
-            let (fname, duplicate) = dupes.use_name(org_name);
+    let downloaded = downloaded.into_inner();
+    let mismatched = mismatched.into_inner().unwrap();
+    let missing = missing.into_inner().unwrap();
+    let failed = failed.into_inner().unwrap();
+    if total > 1 {
+        let (skipped_missing, failed_missing) = match opts.fails_on_missing() {
+            true => (0, missing.len()),
+            false => (missing.len(), 0),
+        };
+        status!(
+            "Downloaded {}, skipped {}, failed {} of {} files",
+            downloaded,
+            skipped.into_inner() + skipped_missing,
+            mismatched.len() + failed_missing + failed.len(),
+            total
+        );
+    }
+    if !failed.is_empty() {
+        return Err(Error::DownloadFailures {
+            downloaded,
+            failed: failed
+                .into_iter()
+                .chain(mismatched)
+                .chain(missing)
+                .collect(),
+        });
+    }
+    check_failed(mismatched)?;
+    check_missing(opts, missing)
+}
+
+enum Outcome {
+    Downloaded,
+    Skipped,
+    Mismatch(String),
+    /// The attachment has no file of the requested variant.
+    Missing(String),
+}
+
+/// Downloads a single file into `parent`. The `progress` is only
+/// visible when downloading in parallel, then it is used instead of
+/// the progress of each file.
+fn download_ref(
+    dref: &DownloadRef,
+    opts: &Input,
+    ctx: &Context,
+    parent: &Path,
+    dupes: &Mutex<Dupes>,
+    progress: &Progress,
+) -> Result<Outcome, Error> {
+    let dlopt = opts.variant().fetch(dref, ctx).context(HttpClient)?;
+
+    match dlopt {
+        Some(dl) => {
+            let org_name = opts.file_name(dref, dl.get_filename().unwrap_or(&dref.name));
+            let (fname, duplicate) = dupes.lock().unwrap().use_name(&org_name);
             let path = parent.join(&fname);
             if path.exists() && !opts.overwrite {
-                println!("File exists: {}. Skipping.", path.display());
+                progress.println(format!("File exists: {}. Skipping.", path.display()));
+                emit_skipped(&fname);
+                Ok(Outcome::Skipped)
             } else if duplicate && opts.dupes == DupeMode::Skip {
-                println!("Skipping already downloaded file {}", org_name);
+                progress.println(format!("Skipping already downloaded file {}", org_name));
+                emit_skipped(&fname);
+                Ok(Outcome::Skipped)
             } else {
-                println!("Downloading {} …", &fname);
-                let file = std::fs::File::create(path).context(CreateFile)?;
-                let mut writer = std::io::BufWriter::new(file);
-                dl.copy_to(&mut writer).context(HttpClient)?;
+                progress.println(format!("Downloading {} …", &fname));
+                if download_file(dl, &path, &fname, opts, ctx, progress)? {
+                    Ok(Outcome::Downloaded)
+                } else {
+                    Ok(Outcome::Mismatch(fname))
+                }
             }
-        } else {
-            println!(
+        }
+        None => {
+            let msg = format!(
                 "No {} file for attachment {}",
-                opts.download_type(),
+                opts.variant().name(),
                 dref.name
             );
+            progress.println(&msg);
+            emit_missing(&dref.name, &msg, opts);
+            Ok(Outcome::Missing(dref.name.clone()))
         }
     }
+}
+
+/// Creates the zip in a temporary file, which is then copied to
+/// stdout, because writing a zip file requires seeking.
+fn download_zip_stdout(attachs: Downloads, opts: &Input, ctx: &Context) -> Result<(), Error> {
+    let mut tmp = tempfile::Builder::new()
+        .prefix("dsc-download-")
+        .suffix(".zip")
+        .tempfile()
+        .context(CreateFile)?;
+    let zipped = write_zip(attachs, opts, ctx, tmp.as_file_mut())?;
+    zipped.check(opts)?;
+    if !zipped.empty {
+        let file = tmp.as_file_mut();
+        file.seek(SeekFrom::Start(0)).context(CreateFile)?;
+        let mut out = std::io::stdout();
+        std::io::copy(file, &mut out).context(CreateFile)?;
+        out.flush().context(CreateFile)?;
+    }
     Ok(())
 }
 
@@ -217,78 +491,308 @@ fn download_zip(
     opts: &Input,
     ctx: &Context,
     zip_file: &Path,
+    overwrite: bool,
 ) -> Result<(), Error> {
-    if zip_file.exists() && !opts.overwrite {
-        println!("Zip file already exists! {}", zip_file.display());
+    if zip_file.exists() && !overwrite {
+        status!("Zip file already exists! {}", zip_file.display());
     } else {
         if zip_file.exists() {
             std::fs::remove_file(zip_file).context(CreateFile)?;
         }
-        let zip = std::fs::File::create(zip_file).context(CreateFile)?;
-        let mut zw = zip::ZipWriter::new(zip);
-        let mut dupes = Dupes::new();
-        for dref in attachs {
-            let dlopt = if opts.original {
-                dref.get_original(&ctx.client, &ctx.opts.session)
-            } else if opts.archive {
-                dref.get_archive(&ctx.client, &ctx.opts.session)
-            } else {
-                dref.get(&ctx.client, &ctx.opts.session)
-            }
-            .context(HttpClient)?;
-
-            if let Some(mut dl) = dlopt {
-                let org_name = dl.get_filename().unwrap_or(&dref.name);
-                let (fname, duplicate) = dupes.use_name(org_name);
-                if duplicate && opts.dupes == DupeMode::Skip {
-                    println!("Skipping already downloaded file {}", org_name);
-                } else {
-                    zw.start_file(&fname, zip::write::FileOptions::default())
-                        .context(Zip)?;
-                    println!("Downloading {} …", &fname);
-                    dl.copy_to(&mut zw).context(HttpClient)?;
-                }
-            } else {
-                println!(
-                    "No {} file for attachment {}",
-                    opts.download_type(),
-                    dref.name
-                );
-            }
-        }
-        zw.finish().context(Zip)?;
-
-        if dupes.is_empty() {
+        let mut zip = std::fs::File::create(zip_file).context(CreateFile)?;
+        let zipped = write_zip(attachs, opts, ctx, &mut zip)?;
+        if zipped.empty {
             match std::fs::remove_file(zip_file) {
                 Ok(_) => log::info!("Emtpy zip file deleted."),
                 Err(e) => log::warn!("Empty zip file could not be deleted! {}", e),
             }
         }
+        zipped.check(opts)?;
     }
     Ok(())
 }
 
-fn check_args(args: &Input) -> Result<(), Error> {
-    match &args.target {
-        Some(path) => {
-            if args.zip && path.exists() && path.is_dir() {
-                Err(Error::NotAFile { path: path.clone() })
-            } else if !args.zip && !path.is_dir() && path.exists() {
-                Err(Error::NotADirectory { path: path.clone() })
+/// What has been written into a zip file.
+struct Zipped {
+    empty: bool,
+    failed: Vec<String>,
+    missing: Vec<String>,
+}
+
+impl Zipped {
+    fn check(&self, opts: &Input) -> Result<(), Error> {
+        check_failed(self.failed.clone())?;
+        check_missing(opts, self.missing.clone())
+    }
+}
+
+fn write_zip<W: Write + Seek>(
+    attachs: Downloads,
+    opts: &Input,
+    ctx: &Context,
+    out: W,
+) -> Result<Zipped, Error> {
+    let mut zw = zip::ZipWriter::new(out);
+    let mut dupes = Dupes::new();
+    let mut failed = Vec::new();
+    let mut missing = Vec::new();
+    for dref in attachs {
+        let dlopt = opts.variant().fetch(&dref, ctx).context(HttpClient)?;
+
+        if let Some(mut dl) = dlopt {
+            let org_name = opts.file_name(&dref, dl.get_filename().unwrap_or(&dref.name));
+            let (fname, duplicate) = dupes.use_name(&org_name);
+            if duplicate && opts.dupes == DupeMode::Skip {
+                status!("Skipping already downloaded file {}", org_name);
+                emit_skipped(&fname);
             } else {
-                Ok(())
+                zw.start_file(&fname, zip::write::FileOptions::default())
+                    .context(Zip)?;
+                status!("Downloading {} …", &fname);
+                let progress = Progress::bytes(ctx.show_progress(), dl.content_length());
+                let tracked = Tracked::new(&mut zw, fname.clone(), dl.content_length());
+                let writer = DigestWriter::new(progress.wrap_write(tracked));
+                let ok = copy_verified(&mut dl, writer, &fname, opts, &progress)?;
+                progress.finish();
+                if !ok {
+                    failed.push(fname);
+                }
             }
+        } else {
+            let msg = format!(
+                "No {} file for attachment {}",
+                opts.variant().name(),
+                dref.name
+            );
+            status!("{}", msg);
+            emit_missing(&dref.name, &msg, opts);
+            missing.push(dref.name.clone());
         }
-        None => Ok(()),
     }
+    zw.finish().context(Zip)?;
+    Ok(Zipped {
+        empty: dupes.is_empty(),
+        failed,
+        missing,
+    })
 }
 
-fn action_msg(opts: &Input, len: usize, target: Display) -> String {
-    if opts.original {
-        format!("original files of {} attachments into {}", len, target)
-    } else if opts.archive {
-        format!("archives of {} attachments into {}", len, target)
+/// Downloads the file into a `.part` file next to `path` and renames
+/// it once it is complete and verified. An existing `.part` file is
+/// continued, if the server supports range requests. Returns `false`
+/// if the checksum doesn't match.
+///
+/// The bytes are counted by `shared` if it is visible, otherwise a
+/// progress for this file is shown.
+fn download_file(
+    dl: Download,
+    path: &Path,
+    name: &str,
+    opts: &Input,
+    ctx: &Context,
+    shared: &Progress,
+) -> Result<bool, Error> {
+    let part = part_file(path);
+    let existing = if part.exists() && !opts.no_resume {
+        std::fs::metadata(&part).context(CreateFile)?.len()
+    } else {
+        0
+    };
+    let resumed = if existing > 0 && dl.accepts_ranges() {
+        dl.resume_from(&ctx.client, &ctx.opts.session, existing)
+            .context(HttpClient)?
+            .filter(|rest| rest.checksum() == dl.checksum())
+    } else {
+        None
+    };
+    let (mut dl, append) = match resumed {
+        Some(rest) => {
+            shared.println(format!("Resuming {} at {} bytes", name, existing));
+            (rest, true)
+        }
+        None => (dl, false),
+    };
+
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&part)
+        .context(CreateFile)?;
+    let own = if shared.is_hidden() {
+        Progress::bytes(ctx.show_progress(), dl.content_length())
+    } else {
+        shared.inc_length(dl.content_length().unwrap_or(0));
+        Progress::hidden()
+    };
+    let progress = if shared.is_hidden() { &own } else { shared };
+    let total = dl
+        .content_length()
+        .map(|n| n + if append { existing } else { 0 });
+    let tracked = Tracked::new(std::io::BufWriter::new(file), name.to_string(), total);
+    let tracked = if append {
+        tracked.with_offset(existing)
+    } else {
+        tracked
+    };
+    let mut writer = DigestWriter::new(progress.wrap_write(tracked));
+    if append {
+        let mut existing = std::fs::File::open(&part).context(CreateFile)?;
+        writer.update_from(&mut existing).context(CreateFile)?;
+    }
+    let ok = copy_verified(&mut dl, writer, name, opts, progress)?;
+    own.finish();
+    if ok {
+        std::fs::rename(&part, path).context(CreateFile)?;
+    } else {
+        std::fs::remove_file(&part).context(CreateFile)?;
+    }
+    Ok(ok)
+}
+
+fn part_file(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+/// Copies the download into the writer and compares the checksum of
+/// the written bytes with the one sent by the server. Returns `false`
+/// if they differ.
+fn copy_verified<W: Write>(
+    dl: &mut Download,
+    mut writer: DigestWriter<W, Sha256>,
+    name: &str,
+    opts: &Input,
+    progress: &Progress,
+) -> Result<bool, Error> {
+    let expected = if opts.verify() { dl.checksum() } else { None };
+    let bytes = dl.copy_to(&mut writer).context(HttpClient)?;
+    writer.flush().context(CreateFile)?;
+    let actual = writer.finish();
+    let ok = match expected {
+        Some(exp) if exp == actual => {
+            progress.println(format!("Checksum verified: {}", name));
+            true
+        }
+        Some(exp) => {
+            let msg = format!(
+                "Checksum mismatch: {} (expected {}, got {})",
+                name, exp, actual
+            );
+            progress.print_error(&msg);
+            emit_error(name, &msg);
+            false
+        }
+        None => {
+            if opts.verify() {
+                log::info!("No checksum available for {}", name);
+            }
+            true
+        }
+    };
+    if ok {
+        events::emit(&Event::FileDone {
+            file: name,
+            outcome: events::Outcome::Downloaded,
+            bytes,
+        });
+    }
+    Ok(ok)
+}
+
+fn emit_skipped(name: &str) {
+    events::emit(&Event::FileDone {
+        file: name,
+        outcome: events::Outcome::Skipped,
+        bytes: 0,
+    });
+}
+
+fn emit_missing(name: &str, error: &str, opts: &Input) {
+    if opts.fails_on_missing() {
+        emit_error(name, error);
+    } else {
+        emit_skipped(name);
+    }
+}
+
+fn emit_error(name: &str, error: &str) {
+    events::emit(&Event::FileError { file: name, error });
+}
+
+fn check_failed(failed: Vec<String>) -> Result<(), Error> {
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::ChecksumMismatch { files: failed })
+    }
+}
+
+fn check_missing(opts: &Input, missing: Vec<String>) -> Result<(), Error> {
+    if missing.is_empty() || !opts.fails_on_missing() {
+        Ok(())
     } else {
-        format!("{} attachments into {}", len, target)
+        Err(Error::MissingVariant {
+            variant: opts.variant().name(),
+            files: missing,
+        })
+    }
+}
+
+fn check_args(args: &Input, ctx: &Context) -> Result<(), Error> {
+    let rest = PLACEHOLDERS
+        .iter()
+        .fold(args.name_template.clone(), |t, p| t.replace(p, ""));
+    if rest.contains('{') || rest.contains('}') || args.name_template.trim().is_empty() {
+        return Err(Error::InvalidNameTemplate {
+            template: args.name_template.clone(),
+        });
+    }
+    if args.is_zip() {
+        let path = args.zip_file(ctx);
+        if !is_stdout(&path) && path.is_dir() {
+            return Err(Error::NotAFile { path });
+        }
+    } else if let Some(path) = args.target(ctx) {
+        if path.exists() && !path.is_dir() {
+            return Err(Error::NotADirectory { path });
+        }
+    }
+    Ok(())
+}
+
+fn is_stdout(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+fn action_msg(opts: &Input, len: usize, target: Display) -> String {
+    match opts.variant() {
+        Variant::Original => format!("original files of {} attachments into {}", len, target),
+        Variant::Converted => format!("converted files of {} attachments into {}", len, target),
+        Variant::Archive => format!("archives of {} attachments into {}", len, target),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_file_name_template() {
+        let dref = DownloadRef {
+            id: "a1".into(),
+            name: "scan.pdf".into(),
+            item_id: "i1".into(),
+            item_name: "Invoice 03/2021".into(),
+        };
+        let input = Input::parse_from(["download", "--name-template", "{item}.{ext}", "x"]);
+        assert_eq!(input.file_name(&dref, "scan.pdf"), "Invoice 03-2021.pdf");
+        assert_eq!(input.file_name(&dref, "scan"), "Invoice 03-2021");
+
+        let input = Input::parse_from(["download", "--query", "tag:invoice"]);
+        assert_eq!(input.query(), "tag:invoice");
+        assert_eq!(input.file_name(&dref, "scan.pdf"), "scan.pdf");
     }
 }
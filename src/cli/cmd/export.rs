@@ -8,20 +8,17 @@ use crate::cli::sink::Error as SinkError;
 use crate::cli::table::format_date_by;
 use crate::http::payload::{Item, SearchMode, SearchReq};
 use crate::http::{Downloads, Error as HttpError};
+use crate::status;
 use crate::util::file;
 
-#[derive(ArgEnum, Clone, Copy, Debug)]
+#[derive(ArgEnum, Clone, Copy, Debug, Default)]
 pub enum LinkNaming {
     /// Name links to items after the items' id.
+    #[default]
     Id,
     // Name links to items after the items' sanitized name.
     Name,
 }
-impl Default for LinkNaming {
-    fn default() -> Self {
-        LinkNaming::Id
-    }
-}
 
 /// Exports data for a query.
 ///
@@ -151,7 +148,7 @@ impl Cmd for Input {
                 break;
             }
         }
-        eprintln!("Exported {} items.", counter);
+        status!("Exported {} items.", counter);
         Ok(())
     }
 }
@@ -178,7 +175,7 @@ fn export(req: &SearchReq, opts: &Input, ctx: &Context) -> Result<usize, Error>
                 make_links(&item, opts, &item_dir, &link_dir)?;
             }
             if opts.correspondent_links {
-                let corr_opt = item.corr_org.as_ref().or_else(|| item.corr_person.as_ref());
+                let corr_opt = item.corr_org.as_ref().or(item.corr_person.as_ref());
                 if let Some(corr) = corr_opt {
                     let link_dir = by_corr.join(file::safe_filename(&corr.name));
                     make_links(&item, opts, &item_dir, &link_dir)?;
@@ -208,8 +205,8 @@ fn export(req: &SearchReq, opts: &Input, ctx: &Context) -> Result<usize, Error>
 
 fn export_message(item: Item, ctx: &Context) -> Result<(), Error> {
     match ctx.format() {
-        Format::Tabular => eprintln!("Exported item: {}", item.name),
-        Format::Csv => eprintln!("Exported item: {}", item.name),
+        Format::Tabular => status!("Exported item: {}", item.name),
+        Format::Csv => status!("Exported item: {}", item.name),
         _ => ctx.write_result(item).context(WriteResult)?,
     }
 
@@ -227,7 +224,7 @@ fn export_item(item: &Item, overwrite: bool, item_dir: &Path, ctx: &Context) ->
         std::fs::remove_file(&meta_file).context(DeleteFile)?;
     }
     if !item_dir.exists() {
-        std::fs::create_dir_all(&item_dir).context(CreateFile)?;
+        std::fs::create_dir_all(item_dir).context(CreateFile)?;
     }
     if !&meta_file.exists() {
         let file = std::fs::File::create(&meta_file).context(CreateFile)?;
@@ -248,9 +245,7 @@ fn export_item(item: &Item, overwrite: bool, item_dir: &Path, ctx: &Context) ->
             .get_original(&ctx.client, &ctx.opts.session)
             .context(HttpClient)?;
         if let Some(mut orig_file) = orig {
-            let file_name = orig_file
-                .get_filename()
-                .unwrap_or_else(|| attach.name.as_str());
+            let file_name = orig_file.get_filename().unwrap_or(attach.name.as_str());
             let file_path = file_dir.join(file_name);
             if file_path.exists() && overwrite {
                 log::debug!(
@@ -278,14 +273,14 @@ fn make_links(
     link_name_path: &Path,
 ) -> Result<(), Error> {
     if !link_name_path.exists() {
-        std::fs::create_dir_all(&link_name_path).context(CreateFile)?;
+        std::fs::create_dir_all(link_name_path).context(CreateFile)?;
     }
     let link_filename = match opts.link_naming.unwrap_or_default() {
         LinkNaming::Id => item.id.clone(),
         LinkNaming::Name => file::safe_filename(&item.name),
     };
 
-    let rel_link_target = pathdiff::diff_paths(&link_target, &link_name_path).unwrap();
+    let rel_link_target = pathdiff::diff_paths(link_target, link_name_path).unwrap();
     // Append the item's id as link name on the link's path.
     let mut link_name = link_name_path.join(&link_filename);
     let mut collision_counter = 1;
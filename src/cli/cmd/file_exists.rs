@@ -1,12 +1,13 @@
 use clap::{Clap, ValueHint};
 use snafu::{ResultExt, Snafu};
+use std::io::{BufRead, Write};
 use std::path::{Path, PathBuf};
 
-use crate::cli::opts::EndpointOpts;
+use crate::cli::opts::{EndpointOpts, Format};
 use crate::cli::sink::Error as SinkError;
 use crate::http::payload::CheckFileResult;
 use crate::http::Error as HttpError;
-use crate::util::digest;
+use crate::util::{digest, events};
 
 use super::{Cmd, Context};
 
@@ -15,14 +16,33 @@ use super::{Cmd, Context};
 /// To check a file, an authenticated user is required, a source id or
 /// the secret to the integration endpoint. The latter allows to check
 /// across collectives.
+///
+/// With `--stdin`, the files are read from stdin, one per line. A line
+/// can also be the SHA-256 hash of a file, then it is not computed
+/// again. In this mode, the results are printed as they arrive: as
+/// `hash<TAB>exists<TAB>item ids` lines for the tabular format and
+/// one json object per line for `ndjson`. Lines that are neither a
+/// file nor a hash result in a `line<TAB>error<TAB>message` line or
+/// an object with an `error`, so there is a result for every line.
+///
+/// The results contain the SHA-256 of each file and the items on the
+/// server containing it.
 #[derive(Clap, Debug)]
 pub struct Input {
     #[clap(flatten)]
     pub endpoint: EndpointOpts,
 
     /// One or more files to check
-    #[clap(required = true, min_values = 1, value_hint = ValueHint::FilePath)]
+    #[clap(
+        required_unless_present = "stdin",
+        min_values = 1,
+        value_hint = ValueHint::FilePath
+    )]
     pub files: Vec<PathBuf>,
+
+    /// Read the files or hashes to check from stdin, one per line.
+    #[clap(long, conflicts_with = "files")]
+    pub stdin: bool,
 }
 
 #[derive(Debug, Snafu)]
@@ -41,12 +61,21 @@ pub enum Error {
 
     #[snafu(display("Error writing data: {}", source))]
     WriteResult { source: SinkError },
+
+    #[snafu(display("Error reading from stdin: {}", source))]
+    ReadStdin { source: std::io::Error },
+
+    #[snafu(display("Error writing result: {}", source))]
+    WriteOut { source: std::io::Error },
 }
 
 impl Cmd for Input {
     type CmdError = Error;
 
     fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        if self.stdin {
+            return check_stdin(&self.endpoint, ctx);
+        }
         let mut results = Vec::with_capacity(self.files.capacity());
         for file in &self.files {
             if file.is_file() {
@@ -66,9 +95,86 @@ pub fn check_file(
     opts: &EndpointOpts,
     ctx: &Context,
 ) -> Result<CheckFileResult, Error> {
-    let fa = opts.to_file_auth(ctx);
     let hash = digest::digest_file_sha256(file).context(DigestFail { path: file })?;
-    let mut result = ctx.client.file_exists(hash, &fa).context(HttpClient)?;
+    let mut result = check_hash(&hash, opts, ctx)?;
     result.file = file.canonicalize().ok().map(|p| p.display().to_string());
     Ok(result)
 }
+
+fn check_hash(hash: &str, opts: &EndpointOpts, ctx: &Context) -> Result<CheckFileResult, Error> {
+    let fa = opts.to_file_auth(ctx);
+    let mut result = ctx.client.file_exists(hash, &fa).context(HttpClient)?;
+    result.hash = Some(hash.to_string());
+    Ok(result)
+}
+
+/// Checks each line of stdin, which is either a file or the hash of
+/// a file.
+fn check_stdin(opts: &EndpointOpts, ctx: &Context) -> Result<(), Error> {
+    let stream = ctx.opts.template.is_none()
+        && ctx.opts.fields.is_empty()
+        && matches!(ctx.format(), Format::Tabular | Format::Ndjson);
+    let mut results = Vec::new();
+    for line in std::io::stdin().lock().lines() {
+        let line = line.context(ReadStdin)?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let path = Path::new(line);
+        let result = if is_sha256(line) && !path.exists() {
+            check_hash(&line.to_lowercase(), opts, ctx)?
+        } else if path.is_file() {
+            check_file(path, opts, ctx)?
+        } else {
+            let error = format!("Not a file or SHA-256 hash: {}", line);
+            if !stream {
+                events::warn(&error);
+            }
+            CheckFileResult {
+                exists: false,
+                items: Vec::new(),
+                file: Some(line.to_string()),
+                hash: None,
+                error: Some(error),
+            }
+        };
+        if stream {
+            let mut out = ctx.output().context(WriteResult)?;
+            match ctx.format() {
+                Format::Ndjson => {
+                    let json = serde_json::to_string(&result).map_err(std::io::Error::from);
+                    writeln!(out, "{}", json.context(WriteOut)?).context(WriteOut)?;
+                }
+                _ if result.error.is_some() => writeln!(
+                    out,
+                    "{}\terror\t{}",
+                    result.file.as_deref().unwrap_or_default(),
+                    result.error.as_deref().unwrap_or_default()
+                )
+                .context(WriteOut)?,
+                _ => {
+                    let ids: Vec<&str> = result.items.iter().map(|i| i.id.as_str()).collect();
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}",
+                        result.hash.as_deref().unwrap_or_default(),
+                        result.exists,
+                        ids.join(",")
+                    )
+                    .context(WriteOut)?
+                }
+            }
+        } else {
+            results.push(result);
+        }
+    }
+    if !stream {
+        ctx.write_result(results).context(WriteResult)?;
+    }
+    Ok(())
+}
+
+fn is_sha256(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
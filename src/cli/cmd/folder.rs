@@ -0,0 +1,65 @@
+pub mod create;
+pub mod delete;
+pub mod list;
+
+use clap::{AppSettings, Clap};
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::http::payload::FolderItem;
+use crate::http::Error as HttpError;
+
+/// List and manage folders.
+///
+/// Folders can be given by their name or id. Names are compared
+/// ignoring case.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    #[clap(subcommand)]
+    pub subcmd: FolderCommand,
+}
+
+#[derive(Clap, Debug)]
+pub enum FolderCommand {
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    List(list::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Create(create::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Delete(delete::Input),
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    List { source: list::Error },
+    Create { source: create::Error },
+    Delete { source: delete::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, args: &Context) -> Result<(), Error> {
+        match &self.subcmd {
+            FolderCommand::List(input) => input.exec(args).context(List),
+            FolderCommand::Create(input) => input.exec(args).context(Create),
+            FolderCommand::Delete(input) => input.exec(args).context(Delete),
+        }
+    }
+}
+
+/// Finds the folder with the given name. If no folder has this name,
+/// it is checked whether it is the id of a folder.
+pub fn find_folder(name: &str, ctx: &Context) -> Result<Option<FolderItem>, HttpError> {
+    let mut folders = ctx.client.list_folders(&ctx.opts.session, "")?.items;
+    let pos = folders
+        .iter()
+        .position(|f| f.name.eq_ignore_ascii_case(name))
+        .or_else(|| folders.iter().position(|f| f.id == name));
+    Ok(pos.map(|i| folders.swap_remove(i)))
+}
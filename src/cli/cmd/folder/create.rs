@@ -0,0 +1,46 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Creates a new folder, owned by the current user.
+///
+/// The id of the new folder is printed, it can be given to `upload
+/// --folder` as well as the name.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// The name of the new folder.
+    pub name: String,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Creating the folder failed: {}", message))]
+    CreateFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let result = ctx
+            .client
+            .create_folder(&ctx.opts.session, &self.name)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::CreateFailed {
+                message: result.message,
+            });
+        }
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{find_folder, Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Deletes a folder. Its items are not deleted, they are just not in
+/// a folder anymore.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// The name or id of the folder.
+    pub folder: String,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("The folder '{}' was not found", name))]
+    FolderNotFound { name: String },
+
+    #[snafu(display("Deleting the folder failed: {}", message))]
+    DeleteFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let folder = find_folder(&self.folder, ctx)
+            .context(HttpClient)?
+            .ok_or_else(|| Error::FolderNotFound {
+                name: self.folder.clone(),
+            })?;
+        let result = ctx
+            .client
+            .delete_folder(&ctx.opts.session, &folder.id)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::DeleteFailed {
+                message: result.message,
+            });
+        }
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
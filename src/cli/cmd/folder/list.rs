@@ -0,0 +1,38 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Lists all folders with their owner and number of members.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// Only list folders matching this name. It may contain the `*`
+    /// wildcard at the beginning or end.
+    #[clap(long)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let query = self.name.as_deref().unwrap_or("");
+        let folders = ctx
+            .client
+            .list_folders(&ctx.opts.session, query)
+            .context(HttpClient)?;
+        ctx.write_result(folders.items).context(WriteResult)?;
+        Ok(())
+    }
+}
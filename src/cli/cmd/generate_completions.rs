@@ -4,6 +4,9 @@ use clap_generate::{generate, Generator};
 
 /// Generates completions for some shells.
 ///
+/// For bash, fish and zsh the values of `--tag`, `--folder` and
+/// `--source` are completed, too. The names are asked from the
+/// server each time, using the stored session.
 #[derive(Clap, std::fmt::Debug)]
 pub struct Input {
     #[clap(long, arg_enum)]
@@ -20,18 +23,165 @@ pub enum GeneratorChoice {
     Zsh,
 }
 
+/// The options whose values are completed dynamically, with the
+/// argument to `dsc __complete`.
+const DYNAMIC: [(&str, &str); 3] = [
+    ("tag", "tags"),
+    ("folder", "folders"),
+    ("source", "sources"),
+];
+
 impl Input {
     pub fn print_completions(&self, app: &mut App) {
-        match &self.shell {
-            GeneratorChoice::Bash => generate_completions::<Bash>(app),
+        let script = match &self.shell {
+            GeneratorChoice::Bash => dynamic_bash(&generate_completions::<Bash>(app)),
             GeneratorChoice::Elvish => generate_completions::<Elvish>(app),
-            GeneratorChoice::Fish => generate_completions::<Fish>(app),
+            GeneratorChoice::Fish => dynamic_fish(&generate_completions::<Fish>(app)),
             GeneratorChoice::PowerShell => generate_completions::<PowerShell>(app),
-            GeneratorChoice::Zsh => generate_completions::<Zsh>(app),
+            GeneratorChoice::Zsh => dynamic_zsh(&generate_completions::<Zsh>(app)),
+        };
+        print!("{}", script);
+    }
+}
+
+fn generate_completions<G: Generator>(app: &mut App) -> String {
+    let mut buf = Vec::new();
+    generate::<G, _>(app, "dsc", &mut buf);
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// Defines a function completing the values of `dsc __complete`,
+/// which are split at newlines only, as names may contain spaces.
+const BASH_VALUES: &str = r#"_dsc_values() {
+    local IFS=$'\n'
+    local value
+    COMPREPLY=()
+    for value in $(compgen -W "$(dsc __complete "$1" 2>/dev/null)" -- "$2"); do
+        COMPREPLY+=("$(printf '%q' "$value")")
+    done
+}
+
+"#;
+
+/// Replaces the file completion of the options.
+fn dynamic_bash(script: &str) -> String {
+    let mut out = script.to_string();
+    for (opt, values) in DYNAMIC.iter() {
+        out = out.replace(
+            &format!(
+                "--{})\n                    COMPREPLY=($(compgen -f \"${{cur}}\"))",
+                opt
+            ),
+            &format!(
+                "--{})\n                    _dsc_values {} \"${{cur}}\"",
+                opt, values
+            ),
+        );
+    }
+    format!("{}{}", BASH_VALUES, out)
+}
+
+/// Adds the candidates to the options, which take any value.
+fn dynamic_fish(script: &str) -> String {
+    script
+        .lines()
+        .map(|line| {
+            let dynamic = DYNAMIC
+                .iter()
+                .find(|(opt, _)| line.contains(&format!(" -l {} ", opt)) && line.ends_with(" -r"));
+            match dynamic {
+                Some((_, values)) => format!(
+                    "{} -x -a '(dsc __complete {} 2>/dev/null)'\n",
+                    line.trim_end_matches(" -r"),
+                    values
+                ),
+                None => format!("{}\n", line),
+            }
+        })
+        .collect()
+}
+
+/// Adds an action to the options and defines the functions calling
+/// `dsc __complete` after the first line.
+fn dynamic_zsh(script: &str) -> String {
+    let mut out = String::new();
+    for (n, line) in script.lines().enumerate() {
+        let dynamic = DYNAMIC.iter().find(|(opt, _)| {
+            let spec = line.replacen("'*--", "'--", 1);
+            spec.starts_with(&format!("'--{}=[", opt)) && spec.ends_with("]' \\")
+        });
+        match dynamic {
+            Some((opt, values)) => {
+                let spec = line.trim_end_matches("' \\");
+                out.push_str(&format!("{}:{}:_dsc_{}' \\\n", spec, opt, values));
+            }
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        if n == 0 {
+            for (_, values) in DYNAMIC.iter() {
+                out.push_str(&format!(
+                    "\n_dsc_{0}() {{\n    local -a values\n    values=(${{(f)\"$(dsc __complete {0} 2>/dev/null)\"}})\n    compadd -a values\n}}\n",
+                    values
+                ));
+            }
         }
     }
+    out
 }
 
-fn generate_completions<G: Generator>(app: &mut App) {
-    generate::<G, _>(app, "dsc", &mut std::io::stdout());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::opts::MainOpts;
+    use clap::IntoApp;
+
+    fn script<G: Generator>() -> String {
+        generate_completions::<G>(&mut MainOpts::into_app())
+    }
+
+    #[test]
+    fn unit_dynamic_bash() {
+        let out = dynamic_bash(&script::<Bash>());
+        assert!(out.starts_with(BASH_VALUES));
+        for (opt, values) in DYNAMIC.iter() {
+            assert!(out.contains(&format!(
+                "--{})\n                    _dsc_values {} \"${{cur}}\"",
+                opt, values
+            )));
+        }
+        assert!(!out.contains("--tag)\n                    COMPREPLY=($(compgen -f"));
+        assert!(out.contains("--name)\n                    COMPREPLY=($(compgen -f"));
+    }
+
+    #[test]
+    fn unit_dynamic_fish() {
+        let out = dynamic_fish(&script::<Fish>());
+        let tag_lines: Vec<&str> = out.lines().filter(|l| l.contains(" -l tag ")).collect();
+        assert!(!tag_lines.is_empty());
+        for line in tag_lines {
+            assert!(
+                line.ends_with(" -x -a '(dsc __complete tags 2>/dev/null)'"),
+                "{}",
+                line
+            );
+        }
+        assert!(out.contains(" -l folder "));
+        assert!(!out
+            .lines()
+            .any(|l| l.contains(" -l folder ") && l.ends_with(" -r")));
+    }
+
+    #[test]
+    fn unit_dynamic_zsh() {
+        let out = dynamic_zsh(&script::<Zsh>());
+        assert!(out.starts_with("#compdef dsc\n\n_dsc_tags() {"));
+        for (_, values) in DYNAMIC.iter() {
+            assert!(out.contains(&format!("dsc __complete {} 2>/dev/null", values)));
+        }
+        assert!(out.contains("]:tag:_dsc_tags' \\\n"));
+        assert!(out.contains("]:folder:_dsc_folders' \\\n"));
+    }
 }
@@ -1,10 +1,14 @@
-use clap::Clap;
+use clap::{Clap, ValueHint};
 use snafu::{ResultExt, Snafu};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use super::{Cmd, Context};
 use crate::cli::sink::Error as SinkError;
-use crate::http::payload::GenInvite;
+use crate::http::payload::{GenInvite, InviteResult};
 use crate::http::Error as HttpError;
+use crate::status;
+use crate::util::file::AtomicFile;
 
 /// Generates a new invitation key.
 ///
@@ -14,6 +18,17 @@ use crate::http::Error as HttpError;
 pub struct Input {
     #[clap(long, short)]
     password: String,
+
+    /// Generate this many invitation keys. If generating one fails,
+    /// the keys generated before are still written or printed.
+    #[clap(long, default_value = "1", parse(try_from_str = parse_count))]
+    count: usize,
+
+    /// Write the keys to this file instead of printing the result,
+    /// one key per line. If the file name ends in `.json`, the
+    /// results are written as a json array instead.
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    out: Option<PathBuf>,
 }
 
 impl Cmd for Input {
@@ -23,8 +38,47 @@ impl Cmd for Input {
         let req = GenInvite {
             password: self.password.clone(),
         };
-        let result = ctx.client.gen_invite(&req).context(HttpClient)?;
-        ctx.write_result(result).context(WriteResult)?;
+        let mut results = Vec::with_capacity(self.count);
+        let mut failure = None;
+        while results.len() < self.count && failure.is_none() {
+            match ctx.client.gen_invite(&req) {
+                Ok(result) if result.success => results.push(result),
+                Ok(result) => {
+                    failure = Some(Error::GenInviteFailed {
+                        generated: results.len(),
+                        message: result.message,
+                    })
+                }
+                Err(source) => failure = Some(Error::HttpClient { source }),
+            }
+        }
+        if !results.is_empty() {
+            self.output(results, ctx)?;
+        }
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Input {
+    /// Writes the keys to the `--out` file or prints them.
+    fn output(&self, mut results: Vec<InviteResult>, ctx: &Context) -> Result<(), Error> {
+        match &self.out {
+            Some(file) => {
+                write_keys(file, &results).context(WriteFile { path: file })?;
+                status!(
+                    "Wrote {} invitation key(s) to {}",
+                    results.len(),
+                    file.display()
+                );
+            }
+            None if self.count == 1 => {
+                ctx.write_result(results.remove(0)).context(WriteResult)?;
+            }
+            None => ctx.write_result(results).context(WriteResult)?,
+        }
         Ok(())
     }
 }
@@ -36,4 +90,42 @@ pub enum Error {
 
     #[snafu(display("Error writing data: {}", source))]
     WriteResult { source: SinkError },
+
+    #[snafu(display(
+        "Generating an invitation key failed after {} key(s): {}",
+        generated,
+        message
+    ))]
+    GenInviteFailed { generated: usize, message: String },
+
+    #[snafu(display("Error writing the keys to {}: {}", path.display(), source))]
+    WriteFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+fn parse_count(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("The count must be at least 1".into()),
+        Ok(n) => Ok(n),
+        Err(err) => Err(format!("Invalid number '{}': {}", s, err)),
+    }
+}
+
+fn write_keys(file: &Path, results: &[InviteResult]) -> std::io::Result<()> {
+    let mut out = AtomicFile::create(file)?;
+    let is_json = file
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    if is_json {
+        serde_json::to_writer_pretty(&mut out, results)?;
+        writeln!(out)?;
+    } else {
+        for key in results.iter().filter_map(|r| r.key.as_ref()) {
+            writeln!(out, "{}", key)?;
+        }
+    }
+    out.commit()
 }
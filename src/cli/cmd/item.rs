@@ -1,6 +1,16 @@
+pub mod attachment;
+pub mod delete;
+pub mod empty_trash;
+pub mod export;
 pub mod fields;
 pub mod get;
+pub mod ids;
+pub mod restore;
+pub mod set;
+pub mod set_folder;
+pub mod tag;
 pub mod tags;
+pub mod url;
 
 use clap::{AppSettings, Clap};
 use snafu::{ResultExt, Snafu};
@@ -24,16 +34,61 @@ pub enum ItemCommand {
     #[clap(version)]
     Tags(tags::Input),
 
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Tag(tag::Input),
+
     #[clap(setting = AppSettings::ColoredHelp)]
     #[clap(version)]
     Fields(fields::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Set(set::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    SetFolder(set_folder::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Delete(delete::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Restore(restore::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    EmptyTrash(empty_trash::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Export(export::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Url(url::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Attachment(attachment::Input),
 }
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     Get { source: get::Error },
     Tags { source: tags::Error },
+    Tag { source: tag::Error },
     Fields { source: fields::Error },
+    Set { source: set::Error },
+    SetFolder { source: set_folder::Error },
+    Delete { source: delete::Error },
+    Restore { source: restore::Error },
+    EmptyTrash { source: empty_trash::Error },
+    Export { source: export::Error },
+    Url { source: url::Error },
+    Attachment { source: attachment::Error },
 }
 
 impl Cmd for Input {
@@ -43,7 +98,16 @@ impl Cmd for Input {
         match &self.subcmd {
             ItemCommand::Get(input) => input.exec(ctx).context(Get),
             ItemCommand::Tags(input) => input.exec(ctx).context(Tags),
+            ItemCommand::Tag(input) => input.exec(ctx).context(Tag),
             ItemCommand::Fields(input) => input.exec(ctx).context(Fields),
+            ItemCommand::Set(input) => input.exec(ctx).context(Set),
+            ItemCommand::SetFolder(input) => input.exec(ctx).context(SetFolder),
+            ItemCommand::Delete(input) => input.exec(ctx).context(Delete),
+            ItemCommand::Restore(input) => input.exec(ctx).context(Restore),
+            ItemCommand::EmptyTrash(input) => input.exec(ctx).context(EmptyTrash),
+            ItemCommand::Export(input) => input.exec(ctx).context(Export),
+            ItemCommand::Url(input) => input.exec(ctx).context(Url),
+            ItemCommand::Attachment(input) => input.exec(ctx).context(Attachment),
         }
     }
 }
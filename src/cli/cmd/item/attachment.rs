@@ -0,0 +1,127 @@
+pub mod move_to;
+pub mod rename;
+
+use clap::{AppSettings, Clap};
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::http::payload::{Attachment, ItemDetail, SearchMode, SearchReq};
+use crate::http::Error as HttpError;
+
+/// Manage the attachments of an item.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    #[clap(subcommand)]
+    pub subcmd: AttachmentCommand,
+}
+
+#[derive(Clap, Debug)]
+pub enum AttachmentCommand {
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Rename(rename::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    #[clap(name = "move")]
+    MoveTo(move_to::Input),
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    Rename { source: rename::Error },
+    MoveTo { source: move_to::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        match &self.subcmd {
+            AttachmentCommand::Rename(input) => input.exec(ctx).context(Rename),
+            AttachmentCommand::MoveTo(input) => input.exec(ctx).context(MoveTo),
+        }
+    }
+}
+
+/// Errors when looking up an attachment.
+#[derive(Debug, Snafu)]
+pub enum FindError {
+    #[snafu(display("An http error occurred: {}!", source))]
+    FindHttp { source: HttpError },
+
+    #[snafu(display("The item {} was not found", id))]
+    ItemNotFound { id: String },
+
+    #[snafu(display("The attachment {} was not found", id))]
+    AttachmentNotFound { id: String },
+
+    #[snafu(display("The attachment {} does not belong to item {}", id, item))]
+    NotInItem { id: String, item: String },
+
+    #[snafu(display("The id '{}' matches more than one attachment", id))]
+    AmbiguousId { id: String },
+}
+
+/// Finds the attachment with the given id and the item containing
+/// it. The id may be a prefix, if it matches only one attachment.
+///
+/// If the item is given, the attachment must belong to it. Otherwise
+/// the item is searched by the attachment id.
+pub fn find_attachment(
+    id: &str,
+    item_id: Option<&str>,
+    ctx: &Context,
+) -> Result<(ItemDetail, Attachment), FindError> {
+    let token = &ctx.opts.session;
+    let item_id = match item_id {
+        Some(item_id) => item_id.to_string(),
+        None => {
+            let req = SearchReq {
+                offset: 0,
+                limit: 2,
+                with_details: false,
+                query: format!("attach.id:{}*", id),
+                search_mode: SearchMode::All,
+            };
+            let result = ctx.client.search(token, &req).context(FindHttp)?;
+            let mut ids: Vec<String> = result
+                .groups
+                .into_iter()
+                .flat_map(|g| g.items.into_iter())
+                .map(|i| i.id)
+                .collect();
+            if ids.len() > 1 {
+                return Err(FindError::AmbiguousId { id: id.to_string() });
+            }
+            ids.pop()
+                .ok_or_else(|| FindError::AttachmentNotFound { id: id.to_string() })?
+        }
+    };
+    let mut item = ctx
+        .client
+        .get_item(token, &item_id)
+        .context(FindHttp)?
+        .ok_or_else(|| FindError::ItemNotFound {
+            id: item_id.clone(),
+        })?;
+    let mut matches: Vec<usize> = match item.attachments.iter().position(|a| a.id == id) {
+        Some(pos) => vec![pos],
+        None => (0..item.attachments.len())
+            .filter(|n| item.attachments[*n].id.starts_with(id))
+            .collect(),
+    };
+    if matches.len() > 1 {
+        return Err(FindError::AmbiguousId { id: id.to_string() });
+    }
+    match matches.pop() {
+        Some(pos) => {
+            let attachment = item.attachments.remove(pos);
+            Ok((item, attachment))
+        }
+        None => Err(FindError::NotInItem {
+            id: id.to_string(),
+            item: item.id,
+        }),
+    }
+}
@@ -0,0 +1,69 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{find_attachment, Cmd, Context, FindError};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Moves an attachment to another position within its item.
+///
+/// The attachment is placed right before the `--before` attachment,
+/// which must belong to the same item. All attachments of the item
+/// are printed afterwards, in their new order.
+#[derive(Clap, Debug)]
+pub struct Input {
+    /// The id of the attachment to move (can be abbreviated to a
+    /// prefix)
+    #[clap(long)]
+    pub id: String,
+
+    /// The id of the attachment to place it before (can be
+    /// abbreviated to a prefix)
+    #[clap(long)]
+    pub before: String,
+
+    /// The item id the attachments belong to (can be abbreviated to
+    /// a prefix)
+    #[clap(long)]
+    pub item: Option<String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("{}", source))]
+    Find { source: FindError },
+
+    #[snafu(display("Moving the attachment failed: {}", message))]
+    MoveFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let (item, source) = find_attachment(&self.id, self.item.as_deref(), ctx).context(Find)?;
+        let (_, target) = find_attachment(&self.before, Some(&item.id), ctx).context(Find)?;
+        let result = ctx
+            .client
+            .move_attachment(&ctx.opts.session, &item.id, &source.id, &target.id)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::MoveFailed {
+                message: result.message,
+            });
+        }
+        let item = ctx
+            .client
+            .get_item(&ctx.opts.session, &item.id)
+            .context(HttpClient)?;
+        let attachments = item.map(|i| i.attachments).unwrap_or_default();
+        ctx.write_result(attachments).context(WriteResult)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{find_attachment, Cmd, Context, FindError};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Renames an attachment.
+///
+/// The renamed attachment is printed afterwards. When the item is
+/// given, too, the attachment must belong to it.
+#[derive(Clap, Debug)]
+pub struct Input {
+    /// The attachment id (can be abbreviated to a prefix)
+    #[clap(long)]
+    pub id: String,
+
+    /// The new name of the attachment.
+    #[clap(long)]
+    pub name: String,
+
+    /// The item id the attachment belongs to (can be abbreviated to
+    /// a prefix)
+    #[clap(long)]
+    pub item: Option<String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("{}", source))]
+    Find { source: FindError },
+
+    #[snafu(display("Renaming the attachment failed: {}", message))]
+    RenameFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let (item, attachment) =
+            find_attachment(&self.id, self.item.as_deref(), ctx).context(Find)?;
+        let result = ctx
+            .client
+            .rename_attachment(&ctx.opts.session, &attachment.id, &self.name)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::RenameFailed {
+                message: result.message,
+            });
+        }
+        let (_, renamed) = find_attachment(&attachment.id, Some(&item.id), ctx).context(Find)?;
+        ctx.write_result(vec![renamed]).context(WriteResult)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+use clap::{ArgGroup, Clap};
+use dialoguer::Confirm;
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+
+use super::{empty_trash, ids, Cmd, Context};
+use crate::cli::cmd::{login, search};
+use crate::cli::opts::SearchMode;
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{self, IdName};
+use crate::http::Error as HttpError;
+use crate::status;
+
+/// Moves items given by their ids or by a query into the trash.
+///
+/// Items in the trash can be brought back via `item restore`, until
+/// the trash is emptied. With `--purge`, the items are deleted
+/// permanently instead, by emptying the trash right after. Docspell
+/// can only empty the whole trash, so this is refused if the trash
+/// contains other items.
+///
+/// Before deleting, the number of items is shown and must be
+/// confirmed. Use `--yes` to skip this, for example in scripts.
+///
+/// With `--ids-stdin`, the ids are read from stdin, one per line.
+/// Empty input deletes nothing. Since stdin is not a terminal then,
+/// `--yes` is required.
+#[derive(Clap, Debug)]
+#[clap(group = ArgGroup::new("target").required(true))]
+pub struct Input {
+    /// The ids of the items to delete (can be abbreviated to a
+    /// prefix).
+    #[clap(long, multiple_occurrences = true, min_values = 1, group = "target")]
+    pub id: Vec<String>,
+
+    /// Delete all items matching this query. See
+    /// https://docspell.org/docs/query/
+    #[clap(long, short, group = "target")]
+    pub query: Option<String>,
+
+    /// Read the ids of the items from stdin, one per line, like the
+    /// output of `search --ids-only`.
+    #[clap(long, group = "target")]
+    pub ids_stdin: bool,
+
+    #[clap(flatten)]
+    pub search_mode: SearchMode,
+
+    /// Don't ask for confirmation.
+    #[clap(long, short)]
+    pub yes: bool,
+
+    /// Only list the items that would be deleted.
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Delete the items permanently. This fails if the trash contains
+    /// other items, because docspell can only empty the whole trash.
+    #[clap(long)]
+    pub purge: bool,
+}
+
+/// The outcome of deleting one item.
+#[derive(Serialize, Debug)]
+pub struct DeletedItem {
+    pub id: String,
+    pub name: String,
+    pub deleted: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Error reading the confirmation: {}", source))]
+    Prompt { source: std::io::Error },
+
+    #[snafu(display(
+        "Deleting requires a confirmation, use --yes when not running interactively"
+    ))]
+    ConfirmRequired,
+
+    #[snafu(display("Deleting was cancelled"))]
+    Cancelled,
+
+    #[snafu(display("{} of {} item(s) could not be deleted", failed, total))]
+    DeleteFailed { failed: usize, total: usize },
+
+    #[snafu(display(
+        "The trash contains {} other item(s). Docspell can only empty the whole trash, \
+         so --purge would delete them, too. Restore or purge them first.",
+        count
+    ))]
+    TrashNotEmpty { count: usize },
+
+    #[snafu(display("Emptying the trash failed: {}", message))]
+    PurgeFailed { message: String },
+
+    #[snafu(display("{}", source))]
+    Ids { source: ids::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let (items, mut results) = self.find_targets(ctx)?;
+        if self.purge {
+            check_trash(&items, ctx)?;
+        }
+        if self.dry_run {
+            let planned: Vec<DeletedItem> = items
+                .into_iter()
+                .map(|item| DeletedItem {
+                    id: item.id,
+                    name: item.name,
+                    deleted: false,
+                    message: if self.purge {
+                        "Would be deleted permanently".into()
+                    } else {
+                        "Would be moved to the trash".into()
+                    },
+                })
+                .chain(results)
+                .collect();
+            ctx.write_result(planned).context(WriteResult)?;
+            return Ok(());
+        }
+
+        if !items.is_empty() {
+            confirm(self, items.len())?;
+        }
+        for item in items {
+            results.push(delete_item(item, ctx));
+        }
+        let failed = results.iter().filter(|r| !r.deleted).count();
+        let total = results.len();
+        if self.purge && failed < total {
+            purge(ctx)?;
+        }
+        ctx.write_result(results).context(WriteResult)?;
+        if failed > 0 {
+            Err(Error::DeleteFailed { failed, total })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Input {
+    /// Returns the items to delete. For ids given with `--id` that
+    /// don't exist, a failed result is returned instead; ids from
+    /// stdin must all exist.
+    fn find_targets(&self, ctx: &Context) -> Result<(Vec<IdName>, Vec<DeletedItem>), Error> {
+        match &self.query {
+            Some(q) => {
+                let items = search::find_items(ctx, q, self.search_mode.to_mode())
+                    .context(HttpClient)?
+                    .into_iter()
+                    .map(|item| IdName {
+                        id: item.id,
+                        name: item.name,
+                    })
+                    .collect();
+                Ok((items, vec![]))
+            }
+            None if self.ids_stdin => {
+                let ids = ids::read_stdin().context(Ids)?;
+                let items = ids::find_items(&ids, self.search_mode.to_mode(), ctx)
+                    .context(Ids)?
+                    .into_iter()
+                    .map(|item| IdName {
+                        id: item.id,
+                        name: item.name,
+                    })
+                    .collect();
+                Ok((items, vec![]))
+            }
+            None => {
+                let mut items = Vec::new();
+                let mut missing = Vec::new();
+                for id in &self.id {
+                    match ctx.client.get_item(&ctx.opts.session, id) {
+                        Ok(Some(item)) => items.push(IdName {
+                            id: item.id,
+                            name: item.name,
+                        }),
+                        Ok(None) => missing.push(failed(id.clone(), "", "Item not found")),
+                        Err(err) => missing.push(failed(id.clone(), "", err.to_string())),
+                    }
+                }
+                Ok((items, missing))
+            }
+        }
+    }
+}
+
+fn confirm(opts: &Input, count: usize) -> Result<(), Error> {
+    if opts.yes {
+        return Ok(());
+    }
+    if !login::is_interactive() {
+        return Err(Error::ConfirmRequired);
+    }
+    let prompt = if opts.purge {
+        format!("Permanently delete {} item(s)?", count)
+    } else {
+        format!("Move {} item(s) to the trash?", count)
+    };
+    let ok = Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .context(Prompt)?;
+    if ok {
+        Ok(())
+    } else {
+        Err(Error::Cancelled)
+    }
+}
+
+/// Makes sure that the trash contains no other items than the given
+/// ones, which would be deleted when emptying it.
+fn check_trash(items: &[IdName], ctx: &Context) -> Result<(), Error> {
+    let trashed = search::find_items(ctx, "", payload::SearchMode::Trashed).context(HttpClient)?;
+    let count = trashed
+        .iter()
+        .filter(|t| !items.iter().any(|i| i.id == t.id))
+        .count();
+    if count > 0 {
+        Err(Error::TrashNotEmpty { count })
+    } else {
+        Ok(())
+    }
+}
+
+/// Empties the trash, which deletes the items moved there before.
+fn purge(ctx: &Context) -> Result<(), Error> {
+    let result = empty_trash::start(0, ctx).context(HttpClient)?;
+    if result.success {
+        status!("{}", result.message);
+        Ok(())
+    } else {
+        Err(Error::PurgeFailed {
+            message: result.message,
+        })
+    }
+}
+
+/// Trashes a single item. Errors are returned as a failed result so
+/// that the remaining items are still processed.
+fn delete_item(item: IdName, ctx: &Context) -> DeletedItem {
+    match ctx.client.delete_item(&ctx.opts.session, &item.id) {
+        Ok(result) => DeletedItem {
+            id: item.id,
+            name: item.name,
+            deleted: result.success,
+            message: result.message,
+        },
+        Err(err) => {
+            log::debug!("Error deleting item {}: {:?}", item.id, err);
+            failed(item.id, item.name, err.to_string())
+        }
+    }
+}
+
+fn failed<S: Into<String>, M: Into<String>>(id: String, name: S, message: M) -> DeletedItem {
+    DeletedItem {
+        id,
+        name: name.into(),
+        deleted: false,
+        message: message.into(),
+    }
+}
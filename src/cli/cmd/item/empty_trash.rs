@@ -0,0 +1,98 @@
+use clap::Clap;
+use dialoguer::Confirm;
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::cli::cmd::login;
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{BasicResult, EmptyTrashSetting};
+use crate::http::Error as HttpError;
+
+/// Permanently deletes the items in the trash.
+///
+/// This submits a job to the server, which deletes the items in the
+/// background. Deleted items cannot be restored anymore. Before, it
+/// must be confirmed, unless `--yes` is given.
+#[derive(Clap, Debug)]
+pub struct Input {
+    /// Only delete items that are in the trash for at least this
+    /// many days.
+    #[clap(long, default_value = "0")]
+    pub older_than: u32,
+
+    /// Don't ask for confirmation.
+    #[clap(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Error reading the confirmation: {}", source))]
+    Prompt { source: std::io::Error },
+
+    #[snafu(display(
+        "Emptying the trash requires a confirmation, use --yes when not running interactively"
+    ))]
+    ConfirmRequired,
+
+    #[snafu(display("Emptying the trash was cancelled"))]
+    Cancelled,
+
+    #[snafu(display("Emptying the trash failed: {}", message))]
+    EmptyTrashFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        confirm(self)?;
+        let min_age = i64::from(self.older_than) * MILLIS_PER_DAY;
+        let result = start(min_age, ctx).context(HttpClient)?;
+        if !result.success {
+            return Err(Error::EmptyTrashFailed {
+                message: result.message,
+            });
+        }
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
+
+/// Submits the job deleting all items that are in the trash for at
+/// least `min_age` milliseconds.
+pub fn start(min_age: i64, ctx: &Context) -> Result<BasicResult, HttpError> {
+    let setting = EmptyTrashSetting {
+        // only used for periodic runs, but must be valid
+        schedule: "*-*-* 01:00:00".into(),
+        min_age,
+    };
+    ctx.client.empty_trash(&ctx.opts.session, &setting)
+}
+
+fn confirm(opts: &Input) -> Result<(), Error> {
+    if opts.yes {
+        return Ok(());
+    }
+    if !login::is_interactive() {
+        return Err(Error::ConfirmRequired);
+    }
+    let ok = Confirm::new()
+        .with_prompt("Permanently delete the items in the trash?")
+        .default(false)
+        .interact()
+        .context(Prompt)?;
+    if ok {
+        Ok(())
+    } else {
+        Err(Error::Cancelled)
+    }
+}
+
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
@@ -0,0 +1,136 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+use std::path::{Path, PathBuf};
+
+use super::{Cmd, Context};
+use crate::cli::cmd::search;
+use crate::cli::opts::SearchMode;
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{BasicResult, ItemDetail};
+use crate::http::{Downloads, Error as HttpError};
+use crate::status;
+use crate::util::file;
+
+/// Exports the metadata of all items matching a query.
+///
+/// For each item, a file `<id>.json` is written to the `--out`
+/// directory. It contains the complete item details as returned by
+/// the server, like tags, custom fields, correspondents and dates.
+/// Existing files are replaced, so running it again updates the
+/// snapshot.
+///
+/// With `--with-attachments` the original files are downloaded into
+/// a directory `<id>` next to the json file.
+#[derive(Clap, Debug)]
+pub struct Input {
+    /// The query selecting the items. See
+    /// https://docspell.org/docs/query/
+    #[clap(long, short)]
+    pub query: String,
+
+    #[clap(flatten)]
+    pub search_mode: SearchMode,
+
+    /// Write the files into this directory.
+    #[clap(long, parse(from_os_str))]
+    pub out: PathBuf,
+
+    /// Also download the original files of each item.
+    #[clap(long)]
+    pub with_attachments: bool,
+
+    /// Overwrite already downloaded attachments. By default they are
+    /// skipped if a file with the same name exists.
+    #[clap(long)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Error creating json: {}", source))]
+    Json { source: serde_json::Error },
+
+    #[snafu(display("Error writing file {}: {}", path.display(), source))]
+    CreateFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("The item {} was not found", id))]
+    ItemNotFound { id: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let items =
+            search::find_items(ctx, &self.query, self.search_mode.to_mode()).context(HttpClient)?;
+        std::fs::create_dir_all(&self.out).context(CreateFile { path: &self.out })?;
+        for item in &items {
+            export_item(&item.id, self, ctx)?;
+            status!("Exported item: {}", item.name);
+        }
+        ctx.write_result(BasicResult {
+            success: true,
+            message: format!("Exported {} item(s) to {}", items.len(), self.out.display()),
+        })
+        .context(WriteResult)?;
+        Ok(())
+    }
+}
+
+fn export_item(id: &str, opts: &Input, ctx: &Context) -> Result<(), Error> {
+    let value = ctx
+        .client
+        .get_item_json(&ctx.opts.session, id)
+        .context(HttpClient)?
+        .ok_or_else(|| Error::ItemNotFound { id: id.to_string() })?;
+
+    let meta_file = opts.out.join(format!("{}.json", id));
+    log::debug!("Writing item metadata to {}", meta_file.display());
+    let mut out = file::AtomicFile::create(&meta_file).context(CreateFile { path: &meta_file })?;
+    serde_json::to_writer_pretty(&mut out, &value).context(Json)?;
+    out.commit().context(CreateFile { path: &meta_file })?;
+
+    if opts.with_attachments {
+        let item: ItemDetail = serde_json::from_value(value).context(Json)?;
+        export_attachments(&item, &opts.out.join(id), opts.overwrite, ctx)?;
+    }
+    Ok(())
+}
+
+fn export_attachments(
+    item: &ItemDetail,
+    dir: &Path,
+    overwrite: bool,
+    ctx: &Context,
+) -> Result<(), Error> {
+    std::fs::create_dir_all(dir).context(CreateFile { path: dir })?;
+    for attach in Downloads::from_item_detail(item) {
+        let orig = attach
+            .get_original(&ctx.client, &ctx.opts.session)
+            .context(HttpClient)?;
+        if let Some(mut dl) = orig {
+            let name = file::safe_filename(dl.get_filename().unwrap_or(attach.name.as_str()));
+            let path = dir.join(name);
+            if path.exists() && !overwrite {
+                log::debug!("Skipping existing file {}", path.display());
+                continue;
+            }
+            log::debug!("Saving attachment {} to {}", attach.id, path.display());
+            // written to a temporary file first, so an aborted download
+            // doesn't leave a partial file that is skipped next time
+            let mut out = file::AtomicFile::create(&path).context(CreateFile { path: &path })?;
+            dl.copy_to(&mut out).context(HttpClient)?;
+            out.commit().context(CreateFile { path: &path })?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! Reading item ids from stdin, for `--ids-stdin`.
+//!
+//! Each line contains one id, which may be abbreviated to a prefix
+//! like with `--id`. Empty lines are ignored, so empty input results
+//! in no ids at all. Problems are reported with the line they occur
+//! at.
+
+use snafu::{ResultExt, Snafu};
+use std::io::BufRead;
+
+use super::Context;
+use crate::cli::cmd::search;
+use crate::http::payload::{Item, SearchMode};
+use crate::http::Error as HttpError;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error reading item ids from stdin: {}", source))]
+    ReadStdin { source: std::io::Error },
+
+    #[snafu(display("Invalid item id '{}' in line {}", id, line))]
+    InvalidId { id: String, line: usize },
+
+    #[snafu(display("No item found for id '{}' in line {}", id, line))]
+    IdNotFound { id: String, line: usize },
+
+    #[snafu(display("The id '{}' in line {} matches more than one item", id, line))]
+    IdNotUnique { id: String, line: usize },
+
+    #[snafu(display("An http error occurred: {}!", source))]
+    FindItems { source: HttpError },
+}
+
+/// An item id together with the line it was read from.
+#[derive(Debug, PartialEq)]
+pub struct LineId {
+    pub line: usize,
+    pub id: String,
+}
+
+/// Reads the item ids from stdin.
+pub fn read_stdin() -> Result<Vec<LineId>, Error> {
+    read_ids(std::io::stdin().lock())
+}
+
+/// Reads one item id per line, ignoring empty lines.
+pub fn read_ids<R: BufRead>(input: R) -> Result<Vec<LineId>, Error> {
+    let mut ids = Vec::new();
+    for (n, line) in input.lines().enumerate() {
+        let line = line.context(ReadStdin)?;
+        let id = line.trim();
+        if id.is_empty() {
+            continue;
+        }
+        if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(Error::InvalidId {
+                id: id.to_string(),
+                line: n + 1,
+            });
+        }
+        ids.push(LineId {
+            line: n + 1,
+            id: id.to_string(),
+        });
+    }
+    Ok(ids)
+}
+
+/// Finds the items of the given ids, where each id must match
+/// exactly one item.
+pub fn find_items(
+    ids: &[LineId],
+    search_mode: SearchMode,
+    ctx: &Context,
+) -> Result<Vec<Item>, Error> {
+    let mut items: Vec<Item> = Vec::with_capacity(ids.len());
+    for chunk in ids.chunks(CHUNK_SIZE) {
+        let terms: Vec<String> = chunk.iter().map(|i| format!("id:{}*", i.id)).collect();
+        let query = format!("(| {})", terms.join(" "));
+        let mut found = search::find_items(ctx, &query, search_mode.clone()).context(FindItems)?;
+        for lid in chunk {
+            let mut matching = found.iter().filter(|item| item.id.starts_with(&lid.id));
+            match (matching.next(), matching.next()) {
+                (None, _) => {
+                    return Err(Error::IdNotFound {
+                        id: lid.id.clone(),
+                        line: lid.line,
+                    })
+                }
+                (Some(_), Some(_)) => {
+                    return Err(Error::IdNotUnique {
+                        id: lid.id.clone(),
+                        line: lid.line,
+                    })
+                }
+                (Some(_), None) => {}
+            }
+        }
+        found.retain(|item| !items.iter().any(|i| i.id == item.id));
+        items.append(&mut found);
+    }
+    Ok(items)
+}
+
+/// How many ids are looked up with one query.
+const CHUNK_SIZE: usize = 50;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_read_ids() {
+        let input = "2wKt\n\n  J4wA-x  \n";
+        let ids = read_ids(input.as_bytes()).unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                LineId {
+                    line: 1,
+                    id: "2wKt".into()
+                },
+                LineId {
+                    line: 3,
+                    id: "J4wA-x".into()
+                }
+            ]
+        );
+        assert!(read_ids("".as_bytes()).unwrap().is_empty());
+        match read_ids("2wKt\nnot an id\n".as_bytes()) {
+            Err(Error::InvalidId { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+}
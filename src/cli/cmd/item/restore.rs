@@ -0,0 +1,56 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::BasicResult;
+use crate::http::Error as HttpError;
+
+/// Restores items from the trash.
+///
+/// Items that have been deleted via `item delete` are in the trash
+/// until it is emptied. Use `search --trashed-only` to find them.
+#[derive(Clap, Debug)]
+pub struct Input {
+    /// The ids of the items to restore (can be abbreviated to a
+    /// prefix).
+    #[clap(long, required = true, multiple_occurrences = true, min_values = 1)]
+    pub id: Vec<String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Restoring item {} failed: {}", id, message))]
+    RestoreFailed { id: String, message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        for id in &self.id {
+            let result = ctx
+                .client
+                .restore_item(&ctx.opts.session, id)
+                .context(HttpClient)?;
+            if !result.success {
+                return Err(Error::RestoreFailed {
+                    id: id.clone(),
+                    message: result.message,
+                });
+            }
+        }
+        ctx.write_result(BasicResult {
+            success: true,
+            message: format!("Restored {} item(s)", self.id.len()),
+        })
+        .context(WriteResult)?;
+        Ok(())
+    }
+}
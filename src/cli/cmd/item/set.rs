@@ -0,0 +1,154 @@
+use chrono::NaiveDate;
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::cli::cmd::folder;
+use crate::cli::opts::Direction;
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{BasicResult, ItemDetail};
+use crate::http::Error as HttpError;
+
+/// Changes the name, date, direction or folder of an item.
+///
+/// Only the given values are changed, all others are left as is. The
+/// updated item is printed afterwards.
+#[derive(Clap, Debug)]
+pub struct Input {
+    /// The item id (can be abbreviated to a prefix)
+    #[clap(long)]
+    pub id: String,
+
+    /// Set the name of the item.
+    #[clap(long)]
+    pub name: Option<String>,
+
+    /// Set the date of the item, given as `yyyy-mm-dd`.
+    #[clap(long, parse(try_from_str = parse_date))]
+    pub date: Option<i64>,
+
+    /// Set the direction of the item.
+    #[clap(long, arg_enum)]
+    pub direction: Option<Direction>,
+
+    /// Move the item into this folder, given by its name or id.
+    #[clap(long)]
+    pub folder: Option<String>,
+}
+
+impl Input {
+    fn has_changes(&self) -> bool {
+        self.name.is_some()
+            || self.date.is_some()
+            || self.direction.is_some()
+            || self.folder.is_some()
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Nothing to change, specify at least one value!"))]
+    NoChanges,
+
+    #[snafu(display("The item was not found"))]
+    ItemNotFound,
+
+    #[snafu(display("The folder '{}' was not found", name))]
+    FolderNotFound { name: String },
+
+    #[snafu(display("Setting the {} failed: {}", field, message))]
+    UpdateFailed { field: String, message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        if !self.has_changes() {
+            return Err(Error::NoChanges);
+        }
+        let folder_id = match &self.folder {
+            Some(name) => Some(find_folder(name, ctx)?),
+            None => None,
+        };
+        let id = get_item(&self.id, ctx)?.id;
+        let token = &ctx.opts.session;
+
+        if let Some(name) = &self.name {
+            let result = ctx.client.set_item_name(token, &id, name);
+            check_result("name", result)?;
+        }
+        if let Some(date) = self.date {
+            let result = ctx.client.set_item_date(token, &id, date);
+            check_result("date", result)?;
+        }
+        if let Some(dir) = &self.direction {
+            let result = ctx.client.set_item_direction(token, &id, dir.to_value());
+            check_result("direction", result)?;
+        }
+        if let Some(folder) = &folder_id {
+            let result = ctx.client.set_item_folder(token, &id, folder);
+            check_result("folder", result)?;
+        }
+
+        let item = get_item(&id, ctx)?;
+        ctx.write_result(item).context(WriteResult)?;
+        Ok(())
+    }
+}
+
+fn get_item(id: &str, ctx: &Context) -> Result<ItemDetail, Error> {
+    ctx.client
+        .get_item(&ctx.opts.session, id)
+        .context(HttpClient)?
+        .ok_or(Error::ItemNotFound)
+}
+
+/// Finds the id of the folder with the given name. If no folder has
+/// this name, it is checked whether it is the id of a folder.
+fn find_folder(name: &str, ctx: &Context) -> Result<String, Error> {
+    folder::find_folder(name, ctx)
+        .context(HttpClient)?
+        .map(|f| f.id)
+        .ok_or_else(|| Error::FolderNotFound {
+            name: name.to_string(),
+        })
+}
+
+fn check_result(field: &str, result: Result<BasicResult, HttpError>) -> Result<(), Error> {
+    let result = result.context(HttpClient)?;
+    if result.success {
+        Ok(())
+    } else {
+        Err(Error::UpdateFailed {
+            field: field.to_string(),
+            message: result.message,
+        })
+    }
+}
+
+/// Parses a date in the form `yyyy-mm-dd` into milliseconds since the
+/// epoch at the start of that day (UTC).
+fn parse_date(s: &str) -> Result<i64, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|d| d.and_hms(0, 0, 0).timestamp_millis())
+        .map_err(|err| format!("Invalid date '{}', expected yyyy-mm-dd: {}", s, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_parse_date() {
+        assert_eq!(parse_date("2020-09-08"), Ok(1599523200000));
+        assert!(parse_date("2020-13-01").is_err());
+        assert!(parse_date("08.09.2020").is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use clap::{ArgGroup, Clap};
+use snafu::{ResultExt, Snafu};
+
+use super::{ids, Cmd, Context};
+use crate::cli::cmd::{folder, search};
+use crate::cli::opts::SearchMode;
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{BasicResult, Group, Item, ItemsAndRef, SearchResult};
+use crate::http::Error as HttpError;
+
+/// Move all items matching a query into a folder.
+///
+/// The query is resolved to a list of items first, then all of them
+/// are moved into the folder given via `--folder`. The folder can be
+/// given by name or id and must exist.
+///
+/// Instead of a query, the item ids can be read from stdin with
+/// `--ids-stdin`, one per line. Empty input changes nothing.
+#[derive(Clap, Debug)]
+#[clap(group = ArgGroup::new("target").required(true))]
+pub struct Input {
+    /// The query selecting the items. See
+    /// https://docspell.org/docs/query/
+    #[clap(long, short, group = "target")]
+    pub query: Option<String>,
+
+    /// Read the ids of the items from stdin, one per line, like the
+    /// output of `search --ids-only`.
+    #[clap(long, group = "target")]
+    pub ids_stdin: bool,
+
+    #[clap(flatten)]
+    pub search_mode: SearchMode,
+
+    /// The folder to move the items into, given by its name or id.
+    #[clap(long)]
+    pub folder: String,
+
+    /// Only list the items that would be changed.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("The folder '{}' was not found", name))]
+    FolderNotFound { name: String },
+
+    #[snafu(display("Setting the folder failed: {}", message))]
+    FolderFailed { message: String },
+
+    #[snafu(display("{}", source))]
+    Ids { source: ids::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let folder_id = folder::find_folder(&self.folder, ctx)
+            .context(HttpClient)?
+            .map(|f| f.id)
+            .ok_or_else(|| Error::FolderNotFound {
+                name: self.folder.clone(),
+            })?;
+        let items = match &self.query {
+            Some(q) => {
+                search::find_items(ctx, q, self.search_mode.to_mode()).context(HttpClient)?
+            }
+            None => {
+                let ids = ids::read_stdin().context(Ids)?;
+                ids::find_items(&ids, self.search_mode.to_mode(), ctx).context(Ids)?
+            }
+        };
+        if self.dry_run {
+            let result = SearchResult {
+                groups: vec![Group {
+                    name: "affected items".into(),
+                    items,
+                }],
+            };
+            ctx.write_result(result).context(WriteResult)?;
+        } else {
+            let count = set_folder(&folder_id, &items, ctx)?;
+            ctx.write_result(BasicResult {
+                success: true,
+                message: format!("Modified folder of {} item(s)", count),
+            })
+            .context(WriteResult)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sets the folder in chunks of items.
+fn set_folder(folder_id: &str, items: &[Item], ctx: &Context) -> Result<usize, Error> {
+    for chunk in items.chunks(CHUNK_SIZE) {
+        let req = ItemsAndRef {
+            items: chunk.iter().map(|i| i.id.clone()).collect(),
+            item_ref: Some(folder_id.to_string()),
+        };
+        let result = ctx
+            .client
+            .set_folder_multiple(&ctx.opts.session, &req)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::FolderFailed {
+                message: result.message,
+            });
+        }
+    }
+    Ok(items.len())
+}
+
+const CHUNK_SIZE: usize = 100;
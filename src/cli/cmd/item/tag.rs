@@ -0,0 +1,141 @@
+use clap::{ArgGroup, Clap};
+use snafu::{ResultExt, Snafu};
+
+use super::{ids, Cmd, Context};
+use crate::cli::cmd::search;
+use crate::cli::opts::SearchMode;
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{BasicResult, Group, Item, ItemsAndRefs, SearchResult};
+use crate::http::Error as HttpError;
+
+/// Add or remove tags for all items matching a query.
+///
+/// The query is resolved to a list of items first. Then the tags
+/// given via `--add` are added to all of them and the tags given via
+/// `--remove` are removed. Tags can be given by name or id.
+///
+/// Instead of a query, the item ids can be read from stdin with
+/// `--ids-stdin`, one per line. Empty input changes nothing.
+#[derive(Clap, Debug)]
+#[clap(group = ArgGroup::new("target").required(true))]
+pub struct Input {
+    /// The query selecting the items. See
+    /// https://docspell.org/docs/query/
+    #[clap(long, short, group = "target")]
+    pub query: Option<String>,
+
+    /// Read the ids of the items from stdin, one per line, like the
+    /// output of `search --ids-only`.
+    #[clap(long, group = "target")]
+    pub ids_stdin: bool,
+
+    #[clap(flatten)]
+    pub search_mode: SearchMode,
+
+    /// A tag to add to each item. Can be given multiple times.
+    #[clap(
+        long,
+        multiple_occurrences = true,
+        number_of_values = 1,
+        required_unless_present = "remove"
+    )]
+    pub add: Vec<String>,
+
+    /// A tag to remove from each item. Can be given multiple times.
+    #[clap(long, multiple_occurrences = true, number_of_values = 1)]
+    pub remove: Vec<String>,
+
+    /// Only list the items that would be changed.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Changing tags failed: {}", message))]
+    TagsFailed { message: String },
+
+    #[snafu(display("{}", source))]
+    Ids { source: ids::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let items = match &self.query {
+            Some(q) => {
+                search::find_items(ctx, q, self.search_mode.to_mode()).context(HttpClient)?
+            }
+            None => {
+                let ids = ids::read_stdin().context(Ids)?;
+                ids::find_items(&ids, self.search_mode.to_mode(), ctx).context(Ids)?
+            }
+        };
+        if self.dry_run {
+            let result = SearchResult {
+                groups: vec![Group {
+                    name: "affected items".into(),
+                    items,
+                }],
+            };
+            ctx.write_result(result).context(WriteResult)?;
+        } else {
+            let count = update_tags(self, &items, ctx)?;
+            ctx.write_result(BasicResult {
+                success: true,
+                message: format!("Modified tags of {} item(s)", count),
+            })
+            .context(WriteResult)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds and removes the tags in chunks of items.
+fn update_tags(opts: &Input, items: &[Item], ctx: &Context) -> Result<usize, Error> {
+    for chunk in items.chunks(CHUNK_SIZE) {
+        let ids: Vec<String> = chunk.iter().map(|i| i.id.clone()).collect();
+        if !opts.add.is_empty() {
+            let req = ItemsAndRefs {
+                items: ids.clone(),
+                refs: opts.add.clone(),
+            };
+            let result = ctx
+                .client
+                .link_tags_multiple(&ctx.opts.session, &req)
+                .context(HttpClient)?;
+            check_result(result)?;
+        }
+        if !opts.remove.is_empty() {
+            let req = ItemsAndRefs {
+                items: ids,
+                refs: opts.remove.clone(),
+            };
+            let result = ctx
+                .client
+                .remove_tags_multiple(&ctx.opts.session, &req)
+                .context(HttpClient)?;
+            check_result(result)?;
+        }
+    }
+    Ok(items.len())
+}
+
+fn check_result(result: BasicResult) -> Result<(), Error> {
+    if result.success {
+        Ok(())
+    } else {
+        Err(Error::TagsFailed {
+            message: result.message,
+        })
+    }
+}
+
+const CHUNK_SIZE: usize = 100;
@@ -0,0 +1,110 @@
+use clap::Clap;
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use std::process::Command;
+
+use super::{Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Prints the link to items in the docspell web application.
+///
+/// The link is created from the docspell url and the item id. With
+/// `--open` it is opened in the default browser, too.
+#[derive(Clap, Debug)]
+pub struct Input {
+    /// The item ids (can be abbreviated to a prefix)
+    #[clap(required = true, min_values = 1)]
+    pub ids: Vec<String>,
+
+    /// Open the links in the default browser.
+    #[clap(long)]
+    pub open: bool,
+}
+
+/// The link to an item.
+#[derive(Serialize, Debug)]
+pub struct ItemUrl {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("The item {} was not found", id))]
+    ItemNotFound { id: String },
+
+    #[snafu(display("Unable to open the browser: {}", source))]
+    OpenBrowser { source: std::io::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let mut urls = Vec::with_capacity(self.ids.len());
+        for id in &self.ids {
+            let item = ctx
+                .client
+                .get_item(&ctx.opts.session, id)
+                .context(HttpClient)?
+                .ok_or_else(|| Error::ItemNotFound { id: id.clone() })?;
+            urls.push(ItemUrl {
+                url: item_url(&ctx.base_url(), &item.id),
+                id: item.id,
+                name: item.name,
+            });
+        }
+        if self.open {
+            for item in &urls {
+                log::debug!("Opening {}", item.url);
+                open_browser(&item.url)?;
+            }
+        }
+        ctx.write_result(urls).context(WriteResult)?;
+        Ok(())
+    }
+}
+
+/// Returns the link to the item in the web application.
+pub fn item_url(base_url: &str, id: &str) -> String {
+    format!("{}/app/item/{}", base_url.trim_end_matches('/'), id)
+}
+
+fn open_browser(url: &str) -> Result<(), Error> {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    cmd.arg(url).spawn().context(OpenBrowser)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_item_url() {
+        assert_eq!(
+            item_url("http://localhost:7880/", "abc"),
+            "http://localhost:7880/app/item/abc"
+        );
+        assert_eq!(
+            item_url("https://docs.example.com/docspell", "abc"),
+            "https://docs.example.com/docspell/app/item/abc"
+        );
+    }
+}
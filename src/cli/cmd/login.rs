@@ -6,7 +6,9 @@ use crate::http::Error as HttpError;
 use crate::util::pass;
 
 use clap::{ArgGroup, Clap, ValueHint};
+use dialoguer::{Input as TextInput, Password};
 use snafu::{ResultExt, Snafu};
+use std::io::{BufRead, IsTerminal};
 
 /// Performs a login given user credentials.
 ///
@@ -17,19 +19,29 @@ use snafu::{ResultExt, Snafu};
 /// It is also possible to specfiy a session token instead. When a
 /// session token is given via options or env variable, the session
 /// file is not updated (no filesystem access occurs).
+///
+/// If the account or password is not given, it is asked for
+/// interactively when running in a terminal.
 #[derive(Clap, Debug, PartialEq)]
 #[clap(group = ArgGroup::new("pass"))]
 pub struct Input {
     /// The account name. If not given here, it is looked up in the
-    /// config file.
+    /// config file or asked for interactively.
     #[clap(long, short, value_hint = ValueHint::Username)]
     user: Option<String>,
 
     /// The password used for authentication in plain text. An
-    /// environment variable DSC_PASSWORD can also be used.
+    /// environment variable DSC_PASSWORD can also be used. Note that
+    /// the password may end up in the shell history this way, it is
+    /// asked for interactively if not given.
     #[clap(long, group = "pass")]
     password: Option<String>,
 
+    /// Read the password from the first line of stdin, for example
+    /// `echo $PASS | dsc login --password-stdin`.
+    #[clap(long, group = "pass")]
+    password_stdin: bool,
+
     /// An entry for the pass password manager. If this is given, the
     /// `password` option is ignored.
     #[clap(long, group = "pass")]
@@ -61,6 +73,12 @@ pub enum Error {
 
     #[snafu(display("Error writing data: {}", source))]
     WriteResult { source: SinkError },
+
+    #[snafu(display("Error reading the password from stdin: {}", source))]
+    ReadStdin { source: std::io::Error },
+
+    #[snafu(display("Error reading input: {}", source))]
+    Prompt { source: std::io::Error },
 }
 
 impl Cmd for Input {
@@ -82,6 +100,9 @@ pub fn login(opts: &Input, ctx: &Context) -> Result<AuthResp, Error> {
 }
 
 fn get_password(opts: &Input, ctx: &Context) -> Result<String, Error> {
+    if opts.password_stdin {
+        return read_stdin_line();
+    }
     match ctx.pass_entry(&opts.pass_entry) {
         Some(pe) => pass::pass_password(&pe).context(PassEntry),
         None => match std::env::var_os(DSC_PASSWORD) {
@@ -89,18 +110,54 @@ fn get_password(opts: &Input, ctx: &Context) -> Result<String, Error> {
                 log::debug!("Using password from environment variable");
                 pw.into_string().map_err(|_os| Error::InvalidPasswordEnv)
             }
-            None => opts.password.clone().ok_or(Error::NoPassword),
+            None => match &opts.password {
+                Some(pw) => Ok(pw.clone()),
+                None if is_interactive() => Password::new()
+                    .with_prompt("Password")
+                    .interact()
+                    .context(Prompt),
+                None => Err(Error::NoPassword),
+            },
         },
     }
 }
 
 fn get_account(opts: &Input, ctx: &Context) -> Result<String, Error> {
-    let acc = match &opts.user {
-        Some(u) => Ok(u.clone()),
-        None => ctx.cfg.default_account.clone().ok_or(Error::NoAccount),
+    let acc = match opts
+        .user
+        .clone()
+        .or_else(|| ctx.cfg.default_account.clone())
+    {
+        Some(u) => Ok(u),
+        None if is_interactive() => TextInput::<String>::new()
+            .with_prompt("Account")
+            .interact_text()
+            .context(Prompt),
+        None => Err(Error::NoAccount),
     };
     log::debug!("Using account: {:?}", &acc);
     acc
 }
 
-const DSC_PASSWORD: &str = "DSC_PASSWORD";
+/// Reads exactly one line from stdin, without the line ending.
+fn read_stdin_line() -> Result<String, Error> {
+    let mut line = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .context(ReadStdin)?;
+    let pw = line.trim_end_matches(&['\r', '\n'][..]);
+    if pw.is_empty() {
+        Err(Error::NoPassword)
+    } else {
+        Ok(pw.to_string())
+    }
+}
+
+/// Prompts are only shown if stdin and stderr are connected to a
+/// terminal.
+pub(crate) fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && console::Term::stderr().is_term()
+}
+
+pub(crate) const DSC_PASSWORD: &str = "DSC_PASSWORD";
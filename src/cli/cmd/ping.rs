@@ -0,0 +1,69 @@
+use clap::Clap;
+use prettytable::{row, Table};
+use schemars::JsonSchema;
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use std::time::Instant;
+
+use super::version::Compatibility;
+use super::{Cmd, Context};
+use crate::cli::sink::{Error as SinkError, Sink};
+use crate::cli::table::AsTable;
+use crate::http::Error as HttpError;
+
+/// Checks whether the server is reachable.
+///
+/// Asks the server for its version, which doesn't require to be
+/// logged in, and prints how long it took to answer. If the server
+/// can't be reached, the command fails with exit code 5, so it can
+/// be used as a precondition in scripts.
+#[derive(Clap, Debug)]
+pub struct Input {}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("The server is not reachable: {}", source))]
+    Unreachable { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+}
+
+/// The answer of the server.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Ping {
+    pub docspell_url: String,
+    pub latency_ms: u64,
+    pub server_version: String,
+    pub compatibility: Compatibility,
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let started = Instant::now();
+        let version = ctx.client.version().context(Unreachable)?;
+        let result = Ping {
+            docspell_url: ctx.base_url(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            compatibility: Compatibility::of(&version.version),
+            server_version: version.version,
+        };
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
+
+impl AsTable for Ping {
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+        table.add_row(row!["docspell url", self.docspell_url]);
+        table.add_row(row!["latency", format!("{} ms", self.latency_ms)]);
+        table.add_row(row!["server version", self.server_version]);
+        table.add_row(row!["compatibility", self.compatibility.name()]);
+        table
+    }
+}
+impl Sink for Ping {}
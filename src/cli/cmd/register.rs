@@ -1,5 +1,7 @@
-use clap::{Clap, ValueHint};
+use clap::{ArgGroup, Clap, ValueHint};
 use snafu::{ResultExt, Snafu};
+use std::io::Read;
+use std::path::PathBuf;
 
 use super::{Cmd, Context};
 use crate::cli::sink::Error as SinkError;
@@ -7,7 +9,12 @@ use crate::http::payload::Registration;
 use crate::http::Error as HttpError;
 
 /// Register a new account at Docspell.
+///
+/// If signup requires an invitation key, it can be given via
+/// `--invite`, read from a file with `--invite-key-file` or from the
+/// env variable `DSC_INVITE_KEY`. Only one of them may be used.
 #[derive(Clap, Debug)]
+#[clap(group = ArgGroup::new("invite-key"))]
 pub struct Input {
     /// The collective name to use. If unsure, use the same as login.
     #[clap(long, short, value_hint = ValueHint::Username)]
@@ -23,9 +30,14 @@ pub struct Input {
     pub password: String,
 
     /// If signup requires an invitation key, it can be specified
-    /// here.
-    #[clap(long, short)]
+    /// here. Use `-` to read it from stdin, which keeps it out of the
+    /// shell history.
+    #[clap(long, short, alias = "invite-key", group = "invite-key")]
     pub invite: Option<String>,
+
+    /// Read the invitation key from this file.
+    #[clap(long, group = "invite-key", value_hint = ValueHint::FilePath)]
+    pub invite_key_file: Option<PathBuf>,
 }
 
 impl Cmd for Input {
@@ -36,7 +48,7 @@ impl Cmd for Input {
             collective_name: self.collective_name.clone(),
             login: self.login.clone(),
             password: self.password.clone(),
-            invite: self.invite.clone(),
+            invite: invite_key(self)?,
         };
 
         let result = ctx.client.register(&body).context(HttpClient)?;
@@ -52,4 +64,62 @@ pub enum Error {
 
     #[snafu(display("Error writing data: {}", source))]
     WriteResult { source: SinkError },
+
+    #[snafu(display("Error reading the invitation key from {}: {}", path.display(), source))]
+    ReadInviteFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Error reading the invitation key from stdin: {}", source))]
+    ReadStdin { source: std::io::Error },
+
+    #[snafu(display("Invalid invitation key (non-unicode) in environment variable"))]
+    InvalidInviteEnv,
+
+    #[snafu(display("The invitation key is empty"))]
+    EmptyInviteKey,
+
+    #[snafu(display(
+        "The invitation key is given as option and in {}, use only one of them",
+        DSC_INVITE_KEY
+    ))]
+    MultipleInviteKeys,
+}
+
+const DSC_INVITE_KEY: &str = "DSC_INVITE_KEY";
+
+/// Returns the invitation key from the one source that is given, if
+/// any.
+fn invite_key(opts: &Input) -> Result<Option<String>, Error> {
+    let env = match std::env::var_os(DSC_INVITE_KEY) {
+        Some(v) => Some(v.into_string().map_err(|_| Error::InvalidInviteEnv)?),
+        None => None,
+    };
+    let given = opts.invite.is_some() || opts.invite_key_file.is_some();
+    let key = match (&opts.invite, &opts.invite_key_file, env) {
+        (_, _, Some(_)) if given => return Err(Error::MultipleInviteKeys),
+        (Some(key), _, _) if key == "-" => {
+            let mut data = String::new();
+            std::io::stdin()
+                .read_to_string(&mut data)
+                .context(ReadStdin)?;
+            data
+        }
+        (Some(key), _, _) => key.clone(),
+        (None, Some(file), _) => {
+            std::fs::read_to_string(file).context(ReadInviteFile { path: file })?
+        }
+        (None, None, Some(key)) => {
+            log::debug!("Using invitation key from environment variable");
+            key
+        }
+        (None, None, None) => return Ok(None),
+    };
+    let key = key.trim();
+    if key.is_empty() {
+        Err(Error::EmptyInviteKey)
+    } else {
+        Ok(Some(key.to_string()))
+    }
 }
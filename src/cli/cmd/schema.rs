@@ -0,0 +1,99 @@
+use clap::{ArgEnum, Clap};
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use snafu::{ResultExt, Snafu};
+use std::io::Write;
+
+use super::ping::Ping;
+use super::version::AllVersion;
+use super::whoami::WhoAmI;
+use super::{Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{
+    AuthResp, BasicResult, CheckFileResult, FolderItem, InviteResult, ItemDetail, SearchResult,
+    SourceAndTags, Summary, TagCount,
+};
+
+/// Prints the json schema of the output of a command.
+///
+/// The schema describes the result of the command when using the
+/// json format, which can be used to generate types in other
+/// languages. Use `basic` for the commands that change something and
+/// only report whether they succeeded.
+#[derive(Clap, Debug)]
+pub struct Input {
+    #[clap(arg_enum)]
+    pub command: SchemaCommand,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum SchemaCommand {
+    Version,
+    Login,
+    Whoami,
+    Ping,
+    Search,
+    SearchSummary,
+    FileExists,
+    TagList,
+    FolderList,
+    SourceList,
+    ItemGet,
+    GenInvite,
+    Basic,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Error serializing the schema: {}", source))]
+    SerializeSchema { source: serde_json::Error },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Error writing the schema: {}", source))]
+    WriteOut { source: std::io::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let schema = schema_of(self.command);
+        let json = serde_json::to_string_pretty(&schema).context(SerializeSchema)?;
+        writeln!(ctx.output().context(WriteResult)?, "{}", json).context(WriteOut)?;
+        Ok(())
+    }
+}
+
+fn schema_of(cmd: SchemaCommand) -> RootSchema {
+    match cmd {
+        SchemaCommand::Version => schema_for!(AllVersion),
+        SchemaCommand::Login => schema_for!(AuthResp),
+        SchemaCommand::Whoami => schema_for!(WhoAmI),
+        SchemaCommand::Ping => schema_for!(Ping),
+        SchemaCommand::Search => schema_for!(SearchResult),
+        SchemaCommand::SearchSummary => schema_for!(Summary),
+        SchemaCommand::FileExists => schema_for!(Vec<CheckFileResult>),
+        SchemaCommand::TagList => schema_for!(Vec<TagCount>),
+        SchemaCommand::FolderList => schema_for!(Vec<FolderItem>),
+        SchemaCommand::SourceList => schema_for!(Vec<SourceAndTags>),
+        SchemaCommand::ItemGet => schema_for!(ItemDetail),
+        SchemaCommand::GenInvite => schema_for!(InviteResult),
+        SchemaCommand::Basic => schema_for!(BasicResult),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_schema_of_all_commands() {
+        for name in SchemaCommand::VARIANTS {
+            let cmd = SchemaCommand::from_str(name, false).unwrap();
+            let schema = schema_of(cmd);
+            assert!(schema.schema.metadata.is_some(), "no title for {}", name);
+        }
+    }
+}
@@ -1,47 +1,323 @@
-use clap::Clap;
+pub mod saved;
+
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use clap::{ArgEnum, Clap, ValueHint};
 use snafu::{ResultExt, Snafu};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+use std::path::PathBuf;
 
+use self::saved::{SavedSearch, SavedSearches};
 use super::{Cmd, Context};
-use crate::cli::opts::SearchMode;
-use crate::cli::sink::Error as SinkError;
-use crate::http::payload::{SearchReq, SearchResult};
+use crate::cli::fields::Projection;
+use crate::cli::opts::{parse_assignment, Format, NameFilter, NameVal, SearchMode};
+use crate::cli::sink::{Error as SinkError, Sink};
+use crate::cli::table::project_group;
+use crate::http::payload::{self, Group, Item, SearchReq, SearchResult};
 use crate::http::Error as HttpError;
+use crate::status;
 
 /// Searches for documents and prints the results.
 ///
 /// Documents are searched via a query. The query syntax is described
 /// here: https://docspell.org/docs/query/
+///
+/// Queries can be saved under a name using `--save-as` and run again
+/// later with `--saved`.
+///
+/// Terms like `@name` are replaced by the query alias `name` from the
+/// `aliases` table of the config file.
+///
+/// The `--since` and `--until` options restrict the item date and are
+/// combined with the query, so they can also be given without one.
+/// They are not saved with `--save-as`, which allows to run a saved
+/// search for different time ranges. The same applies to
+/// `--name-like`.
 #[derive(Clap, std::fmt::Debug)]
 pub struct Input {
     /// The query string. See https://docspell.org/docs/query/
-    pub query: String,
+    ///
+    /// Use `-` to read the query from stdin.
+    #[clap(required_unless_present_any = &["saved", "list-saved", "delete-saved", "query-file", "since", "until", "name-like"])]
+    pub query: Option<String>,
+
+    /// Read the query from this file. Lines are joined with a space,
+    /// because docspell treats all whitespace the same way. Leading
+    /// and trailing whitespace of each line is removed.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath, conflicts_with_all = &["query", "saved"])]
+    pub query_file: Option<PathBuf>,
+
+    /// Save the query under this name, before running it. If given,
+    /// the format and columns are saved as well. An existing search
+    /// with the same name is replaced.
+    #[clap(long, conflicts_with_all = &["saved", "list-saved", "delete-saved"])]
+    pub save_as: Option<String>,
+
+    /// Run the saved search with this name. The format and columns
+    /// saved with it are used, unless given explicitly.
+    #[clap(long, conflicts_with_all = &["query", "list-saved", "delete-saved"])]
+    pub saved: Option<String>,
+
+    /// List all saved searches.
+    #[clap(long, conflicts_with = "delete-saved")]
+    pub list_saved: bool,
+
+    /// Delete the saved search with this name.
+    #[clap(long)]
+    pub delete_saved: Option<String>,
+
+    /// Only find items dated on or after this day. It is either a
+    /// date `yyyy-mm-dd` or a time span back from today, like `30d`,
+    /// `2w`, `6m` or `1y`.
+    #[clap(long, parse(try_from_str = parse_date_arg))]
+    pub since: Option<NaiveDate>,
+
+    /// Only find items dated on or before this day. It accepts the
+    /// same values as `--since`.
+    #[clap(long, parse(try_from_str = parse_date_arg))]
+    pub until: Option<NaiveDate>,
 
     #[clap(flatten)]
     pub search_mode: SearchMode,
 
+    #[clap(flatten)]
+    pub name_filter: NameFilter,
+
     /// Do not fetch details to each item in the result
     #[clap(long = "no-details", parse(from_flag = std::ops::Not::not))]
     pub with_details: bool,
 
-    /// Limit the number of results.
+    /// Limit the number of results. With `--all-pages` this is the
+    /// number of results fetched with each request.
     #[clap(short, long, default_value = "20")]
     pub limit: u32,
 
     /// Skip the first n results.
     #[clap(short, long, default_value = "0")]
     pub offset: u32,
+
+    /// Fetch all results by requesting one page after another, until
+    /// no more results are returned. With json and ndjson, each page
+    /// is printed as soon as it arrives. (Note that `--all` is used to
+    /// include trashed items.)
+    #[clap(long)]
+    pub all_pages: bool,
+
+    /// Sort the results by this field. The docspell server doesn't
+    /// support sorting, so results are sorted after fetching them.
+    /// This applies only to the fetched page, use `--all-pages` to
+    /// sort all results. All items are then put into a single group.
+    /// Sorting by `created` requires to fetch the details of every
+    /// item.
+    #[clap(long, arg_enum)]
+    pub sort: Option<SortField>,
+
+    /// The sort order when using `--sort`, the default is `asc`.
+    #[clap(long, arg_enum, requires = "sort")]
+    pub order: Option<SortOrder>,
+
+    /// Add a `name=value` parameter to the search request, for
+    /// parameters of the api that are not available as options. It
+    /// replaces a parameter of the same name set by other options,
+    /// like `withDetails`. Can be given multiple times.
+    #[clap(long, multiple_occurrences = true, number_of_values = 1, parse(try_from_str = parse_assignment))]
+    pub param: Vec<NameVal>,
+
+    /// Print only the ids of the found items, one per line, or as a
+    /// json array with `--format json`. This is meant for passing
+    /// them to other commands, like `item tag --ids-stdin`.
+    #[clap(long)]
+    pub ids_only: bool,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Date,
+    Name,
+    Created,
+    Id,
+    Due,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
 }
 
 impl Cmd for Input {
     type CmdError = Error;
 
     fn exec(&self, ctx: &Context) -> Result<(), Error> {
-        let result = search(self, ctx)?;
-        ctx.write_result(result).context(WriteResult)?;
-        Ok(())
+        if self.list_saved {
+            let searches = SavedSearches::load().context(Saved)?;
+            return ctx.write_result(searches.to_list()).context(WriteResult);
+        }
+        if let Some(name) = &self.delete_saved {
+            let mut searches = SavedSearches::load().context(Saved)?;
+            searches.remove(name).context(Saved)?;
+            searches.store().context(Saved)?;
+            status!("Deleted saved search '{}'", name);
+            return Ok(());
+        }
+
+        let search = match (&self.saved, self.read_query()?) {
+            (Some(name), _) => SavedSearches::load()
+                .and_then(|s| s.get(name).cloned())
+                .context(Saved)?,
+            (None, Some(query)) => SavedSearch {
+                query,
+                format: ctx.opts.format,
+                columns: ctx.opts.columns.clone(),
+            },
+            (None, None)
+                if self.since.is_some()
+                    || self.until.is_some()
+                    || self.name_filter.name_like.is_some() =>
+            {
+                SavedSearch {
+                    query: String::new(),
+                    format: ctx.opts.format,
+                    columns: ctx.opts.columns.clone(),
+                }
+            }
+            (None, None) => return Err(Error::NoQuery),
+        };
+        if let Some(name) = &self.save_as {
+            let mut searches = SavedSearches::load().context(Saved)?;
+            searches.search.insert(name.clone(), search.clone());
+            searches.store().context(Saved)?;
+        }
+
+        let format = ctx
+            .opts
+            .format
+            .or(search.format)
+            .unwrap_or(ctx.default_format);
+        let columns = if ctx.opts.columns.is_empty() {
+            &search.columns
+        } else {
+            &ctx.opts.columns
+        };
+        let query = self.with_dates(&search.query)?;
+        let query = self.name_filter.add_to_query(&query);
+        if self.all_pages {
+            search_all(self, &query, format, columns, ctx)
+        } else {
+            let mut result = self::search(self, &query, self.offset, ctx)?;
+            self.name_filter.retain(&mut result);
+            let result = sort_result(self, result, ctx)?;
+            if self.ids_only {
+                write_ids(&result, format, ctx)
+            } else {
+                ctx.write_result_as(result, Some(format), columns)
+                    .context(WriteResult)
+            }
+        }
+    }
+}
+
+impl Input {
+    /// Returns the query given as argument, read from stdin or from
+    /// the `--query-file`.
+    fn read_query(&self) -> Result<Option<String>, Error> {
+        let cnt = match (&self.query_file, self.query.as_deref()) {
+            (Some(file), _) => std::fs::read_to_string(file).context(ReadQuery { path: file })?,
+            (None, Some("-")) => {
+                let mut cnt = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut cnt)
+                    .context(ReadQuery { path: "-" })?;
+                cnt
+            }
+            (None, Some(query)) => return Ok(Some(query.to_string())),
+            (None, None) => return Ok(None),
+        };
+        match join_lines(&cnt) {
+            query if query.is_empty() => Err(Error::NoQuery),
+            query => Ok(Some(query)),
+        }
+    }
+}
+
+impl Input {
+    /// Adds the `--since` and `--until` constraints to the query.
+    fn with_dates(&self, query: &str) -> Result<String, Error> {
+        if let (Some(since), Some(until)) = (self.since, self.until) {
+            if since > until {
+                return Err(Error::InvalidDateRange { since, until });
+            }
+        }
+        let mut parts: Vec<String> = Vec::new();
+        if !query.trim().is_empty() {
+            parts.push(query.trim().to_string());
+        }
+        if let Some(since) = self.since {
+            parts.push(format!("date>={}", since.format("%Y-%m-%d")));
+        }
+        if let Some(until) = self.until {
+            parts.push(format!("date<={}", until.format("%Y-%m-%d")));
+        }
+        let query = parts.join(" ");
+        if self.since.is_some() || self.until.is_some() {
+            log::debug!("Query with dates: {}", query);
+        }
+        Ok(query)
     }
 }
 
+/// Parses a date `yyyy-mm-dd` or a time span back from today.
+fn parse_date_arg(s: &str) -> Result<NaiveDate, String> {
+    parse_date_from(s, Local::today().naive_local())
+}
+
+fn parse_date_from(s: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date);
+    }
+    let err = || {
+        format!(
+            "Invalid date '{}', expected yyyy-mm-dd or a number followed by d, w, m or y",
+            s
+        )
+    };
+    let unit = s.chars().last().ok_or_else(err)?;
+    let n: i64 = s[..s.len() - unit.len_utf8()].parse().map_err(|_| err())?;
+    // chrono panics on durations that are too large
+    if !(0..=MAX_SPAN).contains(&n) {
+        return Err(err());
+    }
+    match unit {
+        'd' => today.checked_sub_signed(Duration::days(n)),
+        'w' => today.checked_sub_signed(Duration::weeks(n)),
+        'm' => months_before(today, n),
+        'y' => months_before(today, n.saturating_mul(12)),
+        _ => None,
+    }
+    .ok_or_else(err)
+}
+
+const MAX_SPAN: i64 = 1_000_000;
+
+/// Goes back `n` months, using the last day of the month if the day
+/// doesn't exist there.
+fn months_before(date: NaiveDate, n: i64) -> Option<NaiveDate> {
+    let months = date.year() as i64 * 12 + date.month0() as i64 - n;
+    let year = i32::try_from(months.div_euclid(12)).ok()?;
+    let month = months.rem_euclid(12) as u32 + 1;
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+}
+
+fn join_lines(cnt: &str) -> String {
+    cnt.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("An http error occurred: {}!", source))]
@@ -49,18 +325,285 @@ pub enum Error {
 
     #[snafu(display("Error writing data: {}", source))]
     WriteResult { source: SinkError },
+
+    #[snafu(display("Saved searches: {}", source))]
+    Saved { source: saved::Error },
+
+    #[snafu(display("No query given"))]
+    NoQuery,
+
+    #[snafu(display("Error reading the query from {}: {}", path.display(), source))]
+    ReadQuery {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("The limit must be greater than 0 to fetch all pages"))]
+    ZeroLimit,
+
+    #[snafu(display("The date given to --since ({}) is after --until ({})", since, until))]
+    InvalidDateRange { since: NaiveDate, until: NaiveDate },
 }
 
-fn search(opts: &Input, ctx: &Context) -> Result<SearchResult, Error> {
+fn search(opts: &Input, query: &str, offset: u32, ctx: &Context) -> Result<SearchResult, Error> {
     let req = SearchReq {
         limit: opts.limit,
-        offset: opts.offset,
-        with_details: opts.with_details,
-        query: opts.query.clone(),
+        offset,
+        with_details: opts.with_details && !opts.ids_only,
+        query: query.to_string(),
         search_mode: opts.search_mode.to_mode(),
     };
 
+    let params: Vec<(String, String)> = opts
+        .param
+        .iter()
+        .map(|p| (p.name.clone(), p.value.clone()))
+        .collect();
     ctx.client
-        .search(&ctx.opts.session, &req)
+        .search_with_params(&ctx.opts.session, &req, &params)
         .context(HttpClient)
 }
+
+/// Fetches all pages of results, `limit` items at a time. For json,
+/// ndjson and templates each page is written as it arrives, for all
+/// other formats the results are collected first.
+///
+/// Paging stops when a page contains less than `limit` items. It
+/// also stops if the server returns the same page again, in case it
+/// doesn't support paging for this query.
+fn search_all(
+    opts: &Input,
+    query: &str,
+    format: Format,
+    columns: &[String],
+    ctx: &Context,
+) -> Result<(), Error> {
+    if opts.limit == 0 {
+        return Err(Error::ZeroLimit);
+    }
+    let mut offset = opts.offset;
+    let mut collected = SearchResult { groups: vec![] };
+    let mut prev_first: Option<String> = None;
+    let mut group_count = 0;
+    let template = ctx.opts.template.as_ref();
+    let stream = opts.sort.is_none()
+        && !opts.ids_only
+        && (template.is_some() || matches!(format, Format::Json | Format::Ndjson));
+    let mut proj = match template {
+        None if stream && !ctx.opts.fields.is_empty() => Some(Projection::new(&ctx.opts.fields)),
+        _ => None,
+    };
+    if stream && template.is_none() && matches!(format, Format::Json) {
+        write!(ctx.output().context(WriteResult)?, "{{\"groups\":[")
+            .map_err(SinkError::from)
+            .context(WriteResult)?;
+    }
+    loop {
+        let mut page = search(opts, query, offset, ctx)?;
+        let count: usize = page.groups.iter().map(|g| g.items.len()).sum();
+        let first = first_id(&page);
+        opts.name_filter.retain(&mut page);
+        if first.is_some() && first == prev_first {
+            log::warn!("The server returned the same page again, stop paging");
+            break;
+        }
+        log::debug!("Got {} results at offset {}", count, offset);
+        match (template, format) {
+            (Some(tpl), _) if stream => {
+                let mut out = ctx.output().context(WriteResult)?;
+                SearchResult::write_template(&mut out, &page, tpl, ctx.opts.strict)
+                    .context(WriteResult)?
+            }
+            (None, Format::Json) if stream => {
+                let mut out = ctx.output().context(WriteResult)?;
+                for group in page.groups {
+                    if group_count > 0 {
+                        write!(out, ",")
+                            .map_err(SinkError::from)
+                            .context(WriteResult)?;
+                    }
+                    let json = match &mut proj {
+                        Some(proj) => project_group(&group, proj)
+                            .and_then(|v| serde_json::to_string(&v).map_err(SinkError::from)),
+                        None => serde_json::to_string(&group).map_err(SinkError::from),
+                    };
+                    write!(out, "{}", json.context(WriteResult)?)
+                        .map_err(SinkError::from)
+                        .context(WriteResult)?;
+                    group_count += 1;
+                }
+            }
+            (None, Format::Ndjson) if stream => {
+                let mut out = ctx.output().context(WriteResult)?;
+                match &mut proj {
+                    Some(proj) => {
+                        SearchResult::write_projected(&mut out, Format::Ndjson, &page, proj)
+                    }
+                    None => SearchResult::write_ndjson(&mut out, &page),
+                }
+                .context(WriteResult)?
+            }
+            _ => append_groups(&mut collected, page),
+        }
+        if count < opts.limit as usize {
+            break;
+        }
+        offset += opts.limit;
+        prev_first = first;
+    }
+    if let Some(proj) = &proj {
+        proj.warn_unknown();
+    }
+    match (template, format) {
+        (Some(_), _) if stream => Ok(()),
+        (None, Format::Json) if stream => writeln!(ctx.output().context(WriteResult)?, "]}}")
+            .map_err(SinkError::from)
+            .context(WriteResult),
+        (None, Format::Ndjson) if stream => Ok(()),
+        _ if opts.ids_only => write_ids(&sort_result(opts, collected, ctx)?, format, ctx),
+        _ => {
+            let result = sort_result(opts, collected, ctx)?;
+            ctx.write_result_as(result, Some(format), columns)
+                .context(WriteResult)
+        }
+    }
+}
+
+/// Writes the ids of all items, as a json array for the json format
+/// and one per line otherwise.
+fn write_ids(result: &SearchResult, format: Format, ctx: &Context) -> Result<(), Error> {
+    let ids: Vec<&str> = result
+        .groups
+        .iter()
+        .flat_map(|g| g.items.iter())
+        .map(|item| item.id.as_str())
+        .collect();
+    let mut out = ctx.output().context(WriteResult)?;
+    match format {
+        Format::Json => {
+            let json = serde_json::to_string(&ids).map_err(SinkError::from);
+            writeln!(out, "{}", json.context(WriteResult)?)
+        }
+        _ => ids.iter().try_for_each(|id| writeln!(out, "{}", id)),
+    }
+    .map_err(SinkError::from)
+    .context(WriteResult)
+}
+
+/// Returns all items matching the query, by fetching one page after
+/// another. Item details are not fetched.
+pub fn find_items(
+    ctx: &Context,
+    query: &str,
+    search_mode: payload::SearchMode,
+) -> Result<Vec<Item>, HttpError> {
+    let mut req = SearchReq {
+        offset: 0,
+        limit: FIND_PAGE_SIZE,
+        with_details: false,
+        query: query.to_string(),
+        search_mode,
+    };
+    let mut items: Vec<Item> = Vec::new();
+    loop {
+        let page = ctx.client.search(&ctx.opts.session, &req)?;
+        let first = first_id(&page);
+        let page_items: Vec<Item> = page.groups.into_iter().flat_map(|g| g.items).collect();
+        let count = page_items.len();
+        if first.is_some() && items.iter().any(|i| Some(&i.id) == first.as_ref()) {
+            log::warn!("The server returned the same page again, stop paging");
+            break;
+        }
+        items.extend(page_items);
+        if count < req.limit as usize {
+            break;
+        }
+        req.offset += req.limit;
+    }
+    log::debug!("Found {} items for query: {}", items.len(), query);
+    Ok(items)
+}
+
+const FIND_PAGE_SIZE: u32 = 100;
+
+fn first_id(result: &SearchResult) -> Option<String> {
+    result
+        .groups
+        .iter()
+        .flat_map(|g| g.items.iter())
+        .next()
+        .map(|item| item.id.clone())
+}
+
+/// Appends the groups of a page to the result. A group continuing
+/// from the previous page is merged into the last group.
+fn append_groups(result: &mut SearchResult, page: SearchResult) {
+    for group in page.groups {
+        match result.groups.last_mut() {
+            Some(last) if last.name == group.name => last.items.extend(group.items),
+            _ => result.groups.push(group),
+        }
+    }
+}
+
+/// Sorts all items of the result, if requested, and puts them into a
+/// single group.
+fn sort_result(opts: &Input, result: SearchResult, ctx: &Context) -> Result<SearchResult, Error> {
+    let field = match opts.sort {
+        Some(f) => f,
+        None => return Ok(result),
+    };
+    let mut items: Vec<Item> = result.groups.into_iter().flat_map(|g| g.items).collect();
+    match field {
+        SortField::Date => items.sort_by_key(|i| i.date),
+        SortField::Name => items.sort_by_key(|i| i.name.to_lowercase()),
+        SortField::Id => items.sort_by(|a, b| a.id.cmp(&b.id)),
+        SortField::Due => items.sort_by_key(|i| i.due_date),
+        SortField::Created => {
+            let mut keyed = Vec::with_capacity(items.len());
+            for item in items {
+                let created = ctx
+                    .client
+                    .get_item(&ctx.opts.session, &item.id)
+                    .context(HttpClient)?
+                    .map(|d| d.created);
+                keyed.push((created, item));
+            }
+            keyed.sort_by_key(|(created, _)| *created);
+            items = keyed.into_iter().map(|(_, item)| item).collect();
+        }
+    }
+    if opts.order == Some(SortOrder::Desc) {
+        items.reverse();
+    }
+    Ok(SearchResult {
+        groups: vec![Group {
+            name: format!("sorted by {:?}", field).to_lowercase(),
+            items,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_parse_date_arg() {
+        let today = NaiveDate::from_ymd(2023, 3, 31);
+        let date = |s| parse_date_from(s, today).map(|d| d.to_string());
+        assert_eq!(date("2023-01-01"), Ok("2023-01-01".into()));
+        assert_eq!(date("30d"), Ok("2023-03-01".into()));
+        assert_eq!(date("1w"), Ok("2023-03-24".into()));
+        assert_eq!(date("1m"), Ok("2023-02-28".into()));
+        assert_eq!(date("3m"), Ok("2022-12-31".into()));
+        assert_eq!(date("2y"), Ok("2021-03-31".into()));
+        assert!(date("").is_err());
+        assert!(date("d").is_err());
+        assert!(date("-3d").is_err());
+        assert!(date("3h").is_err());
+        assert!(date("3ä").is_err());
+        assert!(date("99999999999w").is_err());
+        assert!(date("2023-02-30").is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Storing named search queries.
+//!
+//! Saved searches are kept in the file `searches.toml` next to the
+//! default config file.
+
+use crate::cli::opts::Format;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A saved query, optionally with the format and columns to use for
+/// presenting the results.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedSearch {
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+}
+
+/// A saved search together with its name, used for listing.
+#[derive(Serialize, Debug)]
+pub struct NamedSearch {
+    pub name: String,
+    #[serde(flatten)]
+    pub search: SavedSearch,
+}
+
+/// All saved searches by their name.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SavedSearches {
+    #[serde(default)]
+    pub search: BTreeMap<String, SavedSearch>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("The config directory could not be found"))]
+    NoConfigDir,
+
+    #[snafu(display("Unable to read saved searches from {}: {}", path.display(), source))]
+    ReadFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Unable to parse saved searches in {}: {}", path.display(), source))]
+    ParseFile {
+        source: toml::de::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Unable to serialize saved searches: {}", source))]
+    WriteToml { source: toml::ser::Error },
+
+    #[snafu(display("Unable to write saved searches to {}: {}", path.display(), source))]
+    WriteFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("There is no saved search named '{}'", name))]
+    UnknownSearch { name: String },
+}
+
+impl SavedSearches {
+    /// Reads the saved searches. If the file doesn't exist, no
+    /// searches are returned.
+    pub fn load() -> Result<SavedSearches, Error> {
+        let file = searches_file()?;
+        if file.exists() {
+            log::debug!("Reading saved searches from {}", file.display());
+            let cnt = std::fs::read_to_string(&file).context(ReadFile { path: &file })?;
+            toml::from_str(&cnt).context(ParseFile { path: &file })
+        } else {
+            Ok(SavedSearches::default())
+        }
+    }
+
+    /// Writes all searches back to the file.
+    pub fn store(&self) -> Result<(), Error> {
+        let file = searches_file()?;
+        log::debug!("Writing saved searches to {}", file.display());
+        let cnt = toml::to_string(self).context(WriteToml)?;
+        write_file(&file, &cnt)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&SavedSearch, Error> {
+        self.search.get(name).ok_or_else(|| Error::UnknownSearch {
+            name: name.to_string(),
+        })
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<SavedSearch, Error> {
+        self.search
+            .remove(name)
+            .ok_or_else(|| Error::UnknownSearch {
+                name: name.to_string(),
+            })
+    }
+
+    pub fn to_list(&self) -> Vec<NamedSearch> {
+        self.search
+            .iter()
+            .map(|(name, search)| NamedSearch {
+                name: name.clone(),
+                search: search.clone(),
+            })
+            .collect()
+    }
+}
+
+fn write_file(file: &Path, cnt: &str) -> Result<(), Error> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir).context(WriteFile { path: file })?;
+    }
+    std::fs::write(file, cnt).context(WriteFile { path: file })
+}
+
+fn searches_file() -> Result<PathBuf, Error> {
+    match dirs::config_dir() {
+        Some(dir) => Ok(dir.join("dsc").join("searches.toml")),
+        None => Err(Error::NoConfigDir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_saved_searches_roundtrip() {
+        let mut searches = SavedSearches::default();
+        searches.search.insert(
+            "invoices".into(),
+            SavedSearch {
+                query: "tag:invoice".into(),
+                format: Some(Format::Csv),
+                columns: vec!["id".into(), "name".into()],
+            },
+        );
+        let cnt = toml::to_string(&searches).unwrap();
+        let read: SavedSearches = toml::from_str(&cnt).unwrap();
+        let s = read.get("invoices").unwrap();
+        assert_eq!(s.query, "tag:invoice");
+        assert!(matches!(s.format, Some(Format::Csv)));
+        assert_eq!(s.columns, vec!["id", "name"]);
+        assert!(read.get("other").is_err());
+    }
+}
@@ -1,30 +1,146 @@
-use clap::Clap;
+use clap::{ArgEnum, Clap, ValueHint};
+use serde::Serialize;
 use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 use super::{Cmd, Context};
+use crate::cli::cmd::search;
+use crate::cli::cmd::search::saved::{self, SavedSearches};
 use crate::cli::sink::Error as SinkError;
+use crate::cli::table::combine;
+use crate::http::payload::{SearchMode, Summary};
 use crate::http::Error as HttpError;
+use crate::util::parallel;
 
 /// Performs a search and prints a summary of the results.
+///
+/// With `--group-by` the number of matching items is shown per tag,
+/// correspondent or folder instead, sorted by the count. Tags and
+/// folders are counted by the server, for correspondents all
+/// matching items are fetched and counted locally.
+///
+/// To count the items of many queries at once, give them via
+/// `--query`, `--query-file` or `--saved`, each can be repeated. Their
+/// summaries are fetched concurrently and the number of items is
+/// printed per query, in the order they were given. Queries that
+/// fail show the error instead of a count and the command fails at
+/// the end.
 #[derive(Clap, std::fmt::Debug)]
 pub struct Input {
     /// The query string. See https://docspell.org/docs/query/
-    query: String,
+    #[clap(required_unless_present_any = &["queries", "query-file", "saved"])]
+    query: Option<String>,
+
+    /// Count the items per tag, correspondent or folder.
+    #[clap(long, arg_enum, conflicts_with_all = &["queries", "query-file", "saved"])]
+    group_by: Option<GroupBy>,
+
+    /// Another query to count the items of. Can be given multiple
+    /// times.
+    #[clap(
+        long = "query",
+        short,
+        multiple_occurrences = true,
+        number_of_values = 1
+    )]
+    queries: Vec<String>,
+
+    /// A file with one query per line to count the items of. Empty
+    /// lines and lines starting with `#` are ignored.
+    #[clap(long, parse(from_os_str), value_hint = ValueHint::FilePath)]
+    query_file: Option<PathBuf>,
+
+    /// The name of a saved search to count the items of. Can be given
+    /// multiple times.
+    #[clap(long, multiple_occurrences = true, number_of_values = 1)]
+    saved: Vec<String>,
+
+    /// How many summaries to fetch in parallel when counting multiple
+    /// queries.
+    #[clap(long, default_value = "4")]
+    concurrency: usize,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Tag,
+    Correspondent,
+    Folder,
+}
+
+/// The number of items in a group, like a tag.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct GroupCount {
+    pub name: String,
+    pub count: u32,
+}
+
+/// The number of items matching a query. The name is the name of the
+/// saved search or the query itself. If counting failed, there is an
+/// error instead of the count.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct QueryCount {
+    pub name: String,
+    pub query: String,
+    pub count: Option<u32>,
+    pub error: Option<String>,
 }
 
 impl Cmd for Input {
     type CmdError = Error;
 
     fn exec(&self, ctx: &Context) -> Result<(), Error> {
-        let result = ctx
-            .client
-            .summary(&ctx.opts.session, &self.query)
-            .context(HttpClient)?;
-        ctx.write_result(result).context(WriteResult)?;
+        if self.is_multiple() {
+            let counts = query_counts(&self.named_queries()?, self.concurrency, ctx);
+            let failed = counts.iter().filter(|c| c.error.is_some()).count();
+            let total = counts.len();
+            ctx.write_result(counts).context(WriteResult)?;
+            return match failed {
+                0 => Ok(()),
+                _ => Err(Error::QueriesFailed { failed, total }),
+            };
+        }
+        let query = self.query.as_deref().unwrap_or_default();
+        match self.group_by {
+            Some(group_by) => {
+                let groups = group_counts(group_by, query, ctx)?;
+                ctx.write_result(groups).context(WriteResult)?;
+            }
+            None => {
+                let result = summary(query, ctx)?;
+                ctx.write_result(result).context(WriteResult)?;
+            }
+        }
         Ok(())
     }
 }
 
+impl Input {
+    fn is_multiple(&self) -> bool {
+        !self.queries.is_empty() || self.query_file.is_some() || !self.saved.is_empty()
+    }
+
+    /// Returns all queries to count together with their names.
+    fn named_queries(&self) -> Result<Vec<(String, String)>, Error> {
+        let mut queries: Vec<String> = self.query.iter().chain(&self.queries).cloned().collect();
+        if let Some(file) = &self.query_file {
+            let cnt = std::fs::read_to_string(file).context(ReadQueryFile { path: file })?;
+            queries.extend(query_lines(&cnt));
+        }
+        let mut named: Vec<(String, String)> =
+            queries.into_iter().map(|q| (q.clone(), q)).collect();
+        if !self.saved.is_empty() {
+            let searches = SavedSearches::load().context(Saved)?;
+            for name in &self.saved {
+                let search = searches.get(name).context(Saved)?;
+                named.push((name.clone(), search.query.clone()));
+            }
+        }
+        Ok(named)
+    }
+}
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("An http error occurred: {}!", source))]
@@ -32,4 +148,123 @@ pub enum Error {
 
     #[snafu(display("Error writing data: {}", source))]
     WriteResult { source: SinkError },
+
+    #[snafu(display("Error reading queries from {}: {}", path.display(), source))]
+    ReadQueryFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Saved searches: {}", source))]
+    Saved { source: saved::Error },
+
+    #[snafu(display("Counting the items failed for {} of {} queries", failed, total))]
+    QueriesFailed { failed: usize, total: usize },
+}
+
+fn summary(query: &str, ctx: &Context) -> Result<Summary, Error> {
+    ctx.client
+        .summary(&ctx.opts.session, query)
+        .context(HttpClient)
+}
+
+/// Fetches the summaries of all queries using the given number of
+/// threads. The counts are returned in the order of the queries.
+fn query_counts(
+    queries: &[(String, String)],
+    concurrency: usize,
+    ctx: &Context,
+) -> Vec<QueryCount> {
+    let workers = parallel::workers(concurrency, queries.len());
+    log::debug!(
+        "Counting {} queries using {} worker(s)",
+        queries.len(),
+        workers
+    );
+    parallel::map(queries.iter(), workers, |(name, query)| {
+        let result = ctx.client.summary(&ctx.opts.session, query);
+        if let Err(err) = &result {
+            log::debug!("Counting the items of '{}' failed: {:?}", name, err);
+        }
+        QueryCount {
+            name: name.clone(),
+            query: query.clone(),
+            count: result.as_ref().ok().map(|s| s.count),
+            error: result.err().map(|e| e.to_string()),
+        }
+    })
+}
+
+/// Returns the queries of a query file, skipping empty lines and
+/// comments.
+fn query_lines(cnt: &str) -> impl Iterator<Item = String> + '_ {
+    cnt.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+}
+
+fn group_counts(group_by: GroupBy, query: &str, ctx: &Context) -> Result<Vec<GroupCount>, Error> {
+    let counts = match group_by {
+        GroupBy::Tag => summary(query, ctx)?
+            .tag_cloud
+            .items
+            .into_iter()
+            .map(|tc| (tc.tag.name, tc.count))
+            .collect(),
+        GroupBy::Folder => summary(query, ctx)?
+            .folder_stats
+            .into_iter()
+            .map(|fs| (fs.name, fs.count))
+            .collect(),
+        GroupBy::Correspondent => {
+            let items = search::find_items(ctx, query, SearchMode::Normal).context(HttpClient)?;
+            let names = items
+                .iter()
+                .map(|item| combine(&item.corr_org, &item.corr_person, "/"));
+            tally(names)
+        }
+    };
+    Ok(sorted(counts))
+}
+
+/// Counts how often each name occurs, ignoring empty names.
+fn tally<I: Iterator<Item = String>>(names: I) -> Vec<(String, u32)> {
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for name in names.filter(|n| !n.is_empty()) {
+        *counts.entry(name).or_default() += 1;
+    }
+    counts.into_iter().collect()
+}
+
+/// Sorts by count, highest first, and then by name. Groups without
+/// items are removed.
+fn sorted(counts: Vec<(String, u32)>) -> Vec<GroupCount> {
+    let mut groups: Vec<GroupCount> = counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, count)| GroupCount { name, count })
+        .collect();
+    groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_group_counts() {
+        let names = vec!["Acme", "", "Bob", "Acme", "Bob", "Acme", "Ann"];
+        let groups = sorted(tally(names.into_iter().map(String::from)));
+        let found: Vec<(&str, u32)> = groups.iter().map(|g| (g.name.as_str(), g.count)).collect();
+        assert_eq!(found, vec![("Acme", 3), ("Bob", 2), ("Ann", 1)]);
+    }
+
+    #[test]
+    fn unit_query_lines() {
+        let cnt = "tag:todo\n\n# overdue items\n  due<today  \n";
+        let queries: Vec<String> = query_lines(cnt).collect();
+        assert_eq!(queries, vec!["tag:todo", "due<today"]);
+    }
 }
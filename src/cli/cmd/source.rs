@@ -1,11 +1,15 @@
+pub mod create;
+pub mod delete;
 pub mod list;
 
 use clap::{AppSettings, Clap};
 use snafu::{ResultExt, Snafu};
 
 use super::{Cmd, Context};
+use crate::http::payload::SourceAndTags;
+use crate::http::Error as HttpError;
 
-/// Manage source urls for uploading files.
+/// List and manage source urls for uploading files.
 #[derive(Clap, std::fmt::Debug)]
 pub struct Input {
     #[clap(subcommand)]
@@ -17,11 +21,21 @@ pub enum SourceCommand {
     #[clap(setting = AppSettings::ColoredHelp)]
     #[clap(version)]
     List(list::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Create(create::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Delete(delete::Input),
 }
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     List { source: list::Error },
+    Create { source: create::Error },
+    Delete { source: delete::Error },
 }
 
 impl Cmd for Input {
@@ -30,6 +44,20 @@ impl Cmd for Input {
     fn exec(&self, args: &Context) -> Result<(), Error> {
         match &self.subcmd {
             SourceCommand::List(input) => input.exec(args).context(List),
+            SourceCommand::Create(input) => input.exec(args).context(Create),
+            SourceCommand::Delete(input) => input.exec(args).context(Delete),
         }
     }
 }
+
+/// Finds the sources whose id is or starts with the given id.
+pub fn find_source(id: &str, ctx: &Context) -> Result<Vec<SourceAndTags>, HttpError> {
+    let sources = ctx.client.list_sources(&ctx.opts.session)?.items;
+    match sources.iter().position(|s| s.source.id == id) {
+        Some(pos) => Ok(sources.into_iter().skip(pos).take(1).collect()),
+        None => Ok(sources
+            .into_iter()
+            .filter(|s| s.source.id.starts_with(id))
+            .collect()),
+    }
+}
@@ -0,0 +1,132 @@
+use clap::{ArgEnum, Clap};
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::cli::cmd::folder::find_folder;
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::{IdResult, NewSource, SourceTagIn};
+use crate::http::Error as HttpError;
+
+/// Creates a new source for uploading files.
+///
+/// The id of the new source is printed, it can be given to `upload
+/// --source` or used as `default_source_id` in the config file.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// The name of the new source.
+    #[clap(long)]
+    pub name: String,
+
+    /// The priority for processing files uploaded via this source.
+    #[clap(long, arg_enum, default_value = "low")]
+    pub priority: Priority,
+
+    /// Put all items uploaded via this source into this folder. It
+    /// can be given by its name or id.
+    #[clap(long)]
+    pub folder: Option<String>,
+
+    /// A description of the source.
+    #[clap(long)]
+    pub description: Option<String>,
+
+    /// Create the source disabled, so that uploads are not possible
+    /// until it is enabled in the web ui.
+    #[clap(long)]
+    pub disabled: bool,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Low,
+    High,
+}
+
+impl Priority {
+    fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::High => "high",
+        }
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("The folder '{}' was not found", name))]
+    FolderNotFound { name: String },
+
+    #[snafu(display("Creating the source failed: {}", message))]
+    CreateFailed { message: String },
+
+    #[snafu(display("The source '{}' was created, but cannot be found", name))]
+    SourceNotFound { name: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let folder = match &self.folder {
+            Some(name) => {
+                let folder = find_folder(name, ctx)
+                    .context(HttpClient)?
+                    .ok_or_else(|| Error::FolderNotFound { name: name.clone() })?;
+                Some(folder.id)
+            }
+            None => None,
+        };
+        let source = SourceTagIn {
+            source: NewSource {
+                id: String::new(),
+                abbrev: self.name.clone(),
+                description: self.description.clone(),
+                counter: 0,
+                enabled: !self.disabled,
+                priority: self.priority.name().into(),
+                folder,
+                file_filter: None,
+                language: None,
+                created: 0,
+                attachments_only: false,
+            },
+            tags: Vec::new(),
+        };
+        let result = ctx
+            .client
+            .create_source(&ctx.opts.session, &source)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::CreateFailed {
+                message: result.message,
+            });
+        }
+
+        // The newest source with this name is the one just created.
+        let id = ctx
+            .client
+            .list_sources(&ctx.opts.session)
+            .context(HttpClient)?
+            .items
+            .into_iter()
+            .filter(|s| s.source.abbrev == self.name)
+            .max_by_key(|s| s.source.created)
+            .map(|s| s.source.id)
+            .ok_or_else(|| Error::SourceNotFound {
+                name: self.name.clone(),
+            })?;
+        ctx.write_result(IdResult {
+            success: true,
+            message: result.message,
+            id,
+        })
+        .context(WriteResult)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{find_source, Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Deletes a source. Uploading via this source is not possible
+/// anymore, the items uploaded before are kept.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// The id of the source. A prefix is enough, if it matches only
+    /// one source.
+    #[clap(long)]
+    pub id: String,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("The source '{}' was not found", id))]
+    SourceNotFound { id: String },
+
+    #[snafu(display("The id '{}' matches more than one source", id))]
+    AmbiguousId { id: String },
+
+    #[snafu(display("Deleting the source failed: {}", message))]
+    DeleteFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let mut sources = find_source(&self.id, ctx).context(HttpClient)?;
+        if sources.len() > 1 {
+            return Err(Error::AmbiguousId {
+                id: self.id.clone(),
+            });
+        }
+        let source = sources.pop().ok_or_else(|| Error::SourceNotFound {
+            id: self.id.clone(),
+        })?;
+        let result = ctx
+            .client
+            .delete_source(&ctx.opts.session, &source.source.id)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::DeleteFailed {
+                message: result.message,
+            });
+        }
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+pub mod create;
+pub mod delete;
+pub mod list;
+pub mod rename;
+
+use clap::{AppSettings, Clap};
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::http::payload::Tag;
+use crate::http::Error as HttpError;
+
+/// List and manage the tags of your collective.
+///
+/// Tags can be given by their name or id. Names are compared
+/// ignoring case.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    #[clap(subcommand)]
+    pub subcmd: TagCommand,
+}
+
+#[derive(Clap, Debug)]
+pub enum TagCommand {
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    List(list::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Create(create::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Rename(rename::Input),
+
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(version)]
+    Delete(delete::Input),
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    List { source: list::Error },
+    Create { source: create::Error },
+    Rename { source: rename::Error },
+    Delete { source: delete::Error },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, args: &Context) -> Result<(), Error> {
+        match &self.subcmd {
+            TagCommand::List(input) => input.exec(args).context(List),
+            TagCommand::Create(input) => input.exec(args).context(Create),
+            TagCommand::Rename(input) => input.exec(args).context(Rename),
+            TagCommand::Delete(input) => input.exec(args).context(Delete),
+        }
+    }
+}
+
+/// Finds the tag with the given name or id.
+pub fn find_tag(name: &str, ctx: &Context) -> Result<Option<Tag>, HttpError> {
+    let mut tags = ctx.client.list_tags(&ctx.opts.session, "")?.items;
+    let pos = tags
+        .iter()
+        .position(|t| t.name.eq_ignore_ascii_case(name))
+        .or_else(|| tags.iter().position(|t| t.id == name));
+    Ok(pos.map(|i| tags.swap_remove(i)))
+}
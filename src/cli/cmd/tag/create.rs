@@ -0,0 +1,54 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::Tag;
+use crate::http::Error as HttpError;
+
+/// Creates a new tag.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// The name of the new tag.
+    pub name: String,
+
+    /// The category of the tag, like `doctype`.
+    #[clap(long)]
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("Creating the tag failed: {}", message))]
+    CreateFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let tag = Tag {
+            id: String::new(),
+            name: self.name.clone(),
+            category: self.category.clone(),
+            created: 0,
+        };
+        let result = ctx
+            .client
+            .create_tag(&ctx.opts.session, &tag)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::CreateFailed {
+                message: result.message,
+            });
+        }
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{find_tag, Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Deletes a tag. It is removed from all items.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// The name or id of the tag.
+    pub tag: String,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("The tag '{}' was not found", name))]
+    TagNotFound { name: String },
+
+    #[snafu(display("Deleting the tag failed: {}", message))]
+    DeleteFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let tag = find_tag(&self.tag, ctx)
+            .context(HttpClient)?
+            .ok_or_else(|| Error::TagNotFound {
+                name: self.tag.clone(),
+            })?;
+        let result = ctx
+            .client
+            .delete_tag(&ctx.opts.session, &tag.id)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::DeleteFailed {
+                message: result.message,
+            });
+        }
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
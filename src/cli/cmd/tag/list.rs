@@ -0,0 +1,55 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+
+use super::{Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::payload::TagCount;
+use crate::http::Error as HttpError;
+
+/// Lists all tags with the number of items that have them.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// Only list tags matching this name. It may contain the `*`
+    /// wildcard at the beginning or end.
+    #[clap(long)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let token = &ctx.opts.session;
+        let query = self.name.as_deref().unwrap_or("");
+        let tags = ctx.client.list_tags(token, query).context(HttpClient)?;
+        let counts: HashMap<String, u32> = ctx
+            .client
+            .summary(token, "")
+            .context(HttpClient)?
+            .tag_cloud
+            .items
+            .into_iter()
+            .map(|tc| (tc.tag.id, tc.count))
+            .collect();
+        let result: Vec<TagCount> = tags
+            .items
+            .into_iter()
+            .map(|tag| TagCount {
+                count: counts.get(&tag.id).copied().unwrap_or(0),
+                tag,
+            })
+            .collect();
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
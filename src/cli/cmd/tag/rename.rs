@@ -0,0 +1,55 @@
+use clap::Clap;
+use snafu::{ResultExt, Snafu};
+
+use super::{find_tag, Cmd, Context};
+use crate::cli::sink::Error as SinkError;
+use crate::http::Error as HttpError;
+
+/// Renames a tag. Its category is kept.
+#[derive(Clap, std::fmt::Debug)]
+pub struct Input {
+    /// The name or id of the tag.
+    pub tag: String,
+
+    /// The new name.
+    pub new_name: String,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+
+    #[snafu(display("The tag '{}' was not found", name))]
+    TagNotFound { name: String },
+
+    #[snafu(display("Renaming the tag failed: {}", message))]
+    RenameFailed { message: String },
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let mut tag = find_tag(&self.tag, ctx)
+            .context(HttpClient)?
+            .ok_or_else(|| Error::TagNotFound {
+                name: self.tag.clone(),
+            })?;
+        tag.name = self.new_name.clone();
+        let result = ctx
+            .client
+            .update_tag(&ctx.opts.session, &tag)
+            .context(HttpClient)?;
+        if !result.success {
+            return Err(Error::RenameFailed {
+                message: result.message,
+            });
+        }
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
@@ -1,14 +1,32 @@
 use clap::{ArgGroup, Clap, ValueHint};
+use sha2::Sha256;
 use snafu::{ResultExt, Snafu};
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use super::{Cmd, Context};
-use crate::cli::opts::{EndpointOpts, FileAction, UploadMeta};
+use crate::cli::dry_run::{DryRun, PlannedFile};
+use crate::cli::opts::{CacheOpts, EndpointOpts, FileAction, UploadMeta};
 use crate::cli::sink::Error as SinkError;
 use crate::http::payload::{BasicResult, StringList, UploadMeta as MetaRequest};
-use crate::http::Error as HttpError;
+use crate::http::{Error as HttpError, FileAuth};
+use crate::status;
 use crate::util::digest;
+use crate::util::events;
 use crate::util::file::FileActionResult;
+use crate::util::parallel;
+use crate::util::progress::Progress;
+use cache::UploadCache;
+use patch::{ItemPatch, Pending};
+use sidecar::Sidecar;
+use summary::Summary;
+
+pub mod cache;
+mod patch;
+mod sidecar;
+mod summary;
 
 /// Uploads files to docspell.
 ///
@@ -30,6 +48,48 @@ use crate::util::file::FileActionResult;
 /// So using `upload --traverse --delete` will upload all files that
 /// are not yet in Docspell and then deletes them.
 ///
+/// With `--stdin`, the file is read from standard input and uploaded
+/// using the name given via `--name`, for example `generate-report |
+/// dsc upload --stdin --name report.pdf`.
+///
+/// The item is named after the uploaded file, unless `--item-name` is
+/// given. For `--stdin`, `--name` is the file name and `--item-name`
+/// the name of the item, where `{file}` in it refers to `--name`.
+///
+/// Additionally, `--include` and `--exclude` can be given multiple
+/// times to filter files. These patterns are matched against the
+/// path relative to the directory being traversed, or against the
+/// path as given for files given directly. This applies to
+/// `--single-item` as well, where only files can be given. They are
+/// case insensitive unless `--case-sensitive` is specified.
+///
+/// With `--cache`, files that have been uploaded (or were found in
+/// Docspell) are remembered in a local file. Unchanged files from the
+/// cache are skipped without hashing them or asking the server. The
+/// server's duplicate check still decides for all other files, use
+/// `--no-cache` to check every file with the server.
+///
+/// With `--sidecar`, metadata for a file is read from a json file
+/// next to it, named like the file with `.meta.json` or `.json`
+/// appended. It may set `tags`, `folder`, `direction`, `language` and
+/// `customFields`. Values from the sidecar take precedence over the
+/// options: tags are added to the ones given via `--tag`, custom
+/// fields replace the ones of the same name and all other values
+/// replace the options. Sidecar files are not uploaded themselves and
+/// are deleted or moved together with their file.
+///
+/// When more than one file is given, a summary is printed to stderr
+/// at the end: the number of uploaded, skipped and failed files, the
+/// uploaded bytes and the elapsed time, followed by each failed file
+/// and why it failed. With `--format json` it is a json object.
+///
+/// With `--compress`, the request body is gzip compressed if the
+/// server advertises that it accepts compressed requests. This helps
+/// with text based documents over a slow link, but requires to read
+/// the files into memory. Requests with only pdf, image or archive
+/// files are not compressed. Use `--force-compress` to compress
+/// without asking the server, which fails if it doesn't support it.
+///
 /// For glob patterns, see https://docs.rs/glob/0.3.0/glob/struct.Pattern.html
 #[derive(Clap, Debug)]
 #[clap(group = ArgGroup::new("g_multiple"))]
@@ -53,11 +113,37 @@ pub struct Input {
     #[clap(flatten)]
     pub action: FileAction,
 
+    #[clap(flatten)]
+    pub cache: CacheOpts,
+
+    /// Read metadata for each file from a sidecar file next to it,
+    /// like `invoice.pdf.meta.json` or `invoice.pdf.json`. Its values
+    /// take precedence over the options and sidecars are not uploaded.
+    #[clap(long, conflicts_with_all = &["multiple", "stdin"])]
+    pub sidecar: bool,
+
     /// A glob pattern that excludes files to upload. If `--matches`
     /// is also specified, both must evaluate to true.
     #[clap(long, short)]
     pub not_matches: Option<String>,
 
+    /// A glob pattern that files must match to be uploaded, for
+    /// example `**/*.pdf`. It can be given multiple times and a file
+    /// is included if it matches any of the patterns.
+    #[clap(long, multiple_occurrences = true, number_of_values = 1)]
+    pub include: Vec<String>,
+
+    /// A glob pattern to skip files, for example `**/tmp/**`. It can
+    /// be given multiple times and a file is excluded if it matches
+    /// any of the patterns.
+    #[clap(long, multiple_occurrences = true, number_of_values = 1)]
+    pub exclude: Vec<String>,
+
+    /// Match the `--include` and `--exclude` patterns case
+    /// sensitive. By default, `**/*.pdf` also matches `A.PDF`.
+    #[clap(long)]
+    pub case_sensitive: bool,
+
     /// Traverses directories and uploads all files that match the
     /// glob patterns if specfified. This means it cannot be used with
     /// `--multiple`, because each file is uploaded in a separate
@@ -74,13 +160,41 @@ pub struct Input {
     pub poll: Option<u64>,
 
     /// Doesn't submit the request, but prints which files would be
-    /// uploaded instead. This might be useful when using `--traverse`
-    /// and glob patterns.
+    /// uploaded instead, together with the endpoint, the metadata and
+    /// what would happen to each file. This might be useful when
+    /// using `--traverse` and glob patterns. No files are changed and
+    /// only requests for checking file existence are made.
     #[clap(long)]
     pub dry_run: bool,
 
+    /// Can be used with `--traverse` to upload this many files in
+    /// parallel. If some uploads fail, the remaining files are still
+    /// uploaded and the failed ones are reported at the end.
+    #[clap(long, default_value = "1")]
+    pub concurrency: usize,
+
+    /// Read the file to upload from stdin instead. The data is
+    /// uploaded as a single item and the `--name` option is required
+    /// to give it a file name.
+    #[clap(long, conflicts_with_all = &["traverse", "poll"])]
+    pub stdin: bool,
+
+    /// The file name to use when uploading from stdin. Its extension
+    /// is used to determine the content type.
+    #[clap(long, requires = "stdin")]
+    pub name: Option<String>,
+
+    /// Gzip compress the request body if the server supports it.
+    #[clap(long)]
+    pub compress: bool,
+
+    /// Gzip compress the request body without checking whether the
+    /// server supports it.
+    #[clap(long)]
+    pub force_compress: bool,
+
     /// One or more files to upload
-    #[clap(required = true, min_values = 1, value_hint = ValueHint::FilePath)]
+    #[clap(required_unless_present = "stdin", min_values = 1, value_hint = ValueHint::FilePath)]
     pub files: Vec<PathBuf>,
 }
 
@@ -113,6 +227,17 @@ pub enum Error {
         source: std::io::Error,
     },
 
+    #[snafu(display("Unable to read from stdin: {}", source))]
+    ReadStdin { source: std::io::Error },
+
+    #[snafu(display(
+        "The `--item-name` must contain {{file}} or {{stem}} when creating an item per file"
+    ))]
+    ItemNameWithoutTemplate,
+
+    #[snafu(display("The `--name` option is required when uploading from stdin"))]
+    StdinWithoutName,
+
     #[snafu(display("An http error occurred: {}!", source))]
     HttpClient { source: HttpError },
 
@@ -125,6 +250,24 @@ pub enum Error {
     #[snafu(display("The `--poll` option requires `--traverse`"))]
     PollWithoutTraverse,
 
+    #[snafu(display("The server did not accept the upload: {}", message))]
+    UploadRejected { message: String },
+
+    #[snafu(display("Uploaded {} file(s), but {} failed: {}", uploaded, failed.len(), path_list(failed)))]
+    UploadFailures {
+        uploaded: usize,
+        failed: Vec<PathBuf>,
+    },
+
+    #[snafu(display("{}", source))]
+    Patch { source: patch::Error },
+
+    #[snafu(display("{}", source))]
+    Cache { source: cache::Error },
+
+    #[snafu(display("{}", source))]
+    SidecarFile { source: sidecar::Error },
+
     #[snafu(display("The glob pattern '{}' is invalid: {}", pattern, source))]
     BadGlobPattern {
         source: glob::PatternError,
@@ -136,34 +279,35 @@ impl Cmd for Input {
     type CmdError = Error;
 
     fn exec(&self, ctx: &Context) -> Result<(), Error> {
-        let result = upload_files(self, ctx)?;
-        ctx.write_result(result).context(WriteResult)?;
+        if self.dry_run {
+            let plan = dry_run(self, ctx)?;
+            ctx.write_result(plan).context(WriteResult)?;
+        } else {
+            let result = run_upload(self, ctx, true)?;
+            ctx.write_result(result).context(WriteResult)?;
+        }
         Ok(())
     }
 }
 
+/// Uploads the files without printing a summary at the end.
 pub fn upload_files(args: &Input, ctx: &Context) -> Result<BasicResult, Error> {
+    run_upload(args, ctx, false)
+}
+
+fn run_upload(args: &Input, ctx: &Context, summary: bool) -> Result<BasicResult, Error> {
     check_flags(args)?;
     let matcher = matching::Matcher::new(args)?;
-
-    let meta = MetaRequest {
-        multiple: args.multiple,
-        direction: args
-            .upload
-            .direction
-            .clone()
-            .map(|d| d.to_value().to_string()),
-        folder: args.upload.folder.clone(),
-        skip_duplicates: args.upload.skip_duplicates,
-        tags: StringList {
-            items: args.upload.tag.clone(),
-        },
-        file_filter: args.upload.file_filter.clone(),
-        language: args.upload.language.clone(),
-        attachments_only: args.upload.attachments_only,
+    let prep = Prepared {
+        meta: Meta::resolve(&args.upload.with_defaults(ctx.cfg), args, ctx)?,
+        cache: open_cache(args, ctx)?,
+        summary: Summary::default(),
+        print_summary: summary,
+        compress: use_compression(args, ctx),
     };
-    log::debug!("Send file metadata: {:?}", serde_json::to_string(&meta));
-    if args.traverse {
+    if args.stdin {
+        upload_stdin(&prep, args, ctx)
+    } else if args.traverse {
         if let Some(delay) = args.poll {
             let delay_dur = std::time::Duration::from_secs(delay);
             let dir_list = args
@@ -173,33 +317,233 @@ pub fn upload_files(args: &Input, ctx: &Context) -> Result<BasicResult, Error> {
                 .collect::<Vec<String>>()
                 .join(", ");
             loop {
-                eprintln!(
+                status!(
                     "Traversing to upload '{}' (every {:?}) …",
-                    dir_list, delay_dur
+                    dir_list,
+                    delay_dur
                 );
-                upload_traverse(&meta, args, ctx, &matcher)?;
+                let result = upload_traverse(&prep, args, ctx, &matcher);
+                finish_run(&prep, ctx)?;
+                result?;
                 std::thread::sleep(delay_dur);
             }
         } else {
-            upload_traverse(&meta, args, ctx, &matcher)
+            let result = upload_traverse(&prep, args, ctx, &matcher);
+            finish_run(&prep, ctx)?;
+            result
         }
     } else {
-        upload_single(&meta, args, ctx, matcher)
+        let result = upload_single(&prep, args, ctx, matcher);
+        finish_run(&prep, ctx)?;
+        result
     }
 }
 
-fn apply_file_action(path: &Path, root: Option<&PathBuf>, opts: &Input) -> Result<(), Error> {
+/// The data that is the same for all files of an upload.
+struct Prepared {
+    meta: Meta,
+    cache: Option<UploadCache>,
+    summary: Summary,
+    print_summary: bool,
+    compress: bool,
+}
+
+/// Decides whether request bodies are compressed, asking the server
+/// only if `--compress` is given.
+fn use_compression(args: &Input, ctx: &Context) -> bool {
+    if args.force_compress {
+        true
+    } else if args.compress && !args.dry_run {
+        let accepted = ctx.client.accepts_gzip(&args.endpoint.to_upload_auth(ctx));
+        if !accepted {
+            events::warn(
+                "The server doesn't advertise support for compressed uploads, sending them \
+                 uncompressed. Use --force-compress to compress anyway.",
+            );
+        }
+        accepted
+    } else {
+        false
+    }
+}
+
+/// The metadata to send with the files and the values to set after
+/// the items have been created.
+struct Meta {
+    request: MetaRequest,
+    patch: Option<ItemPatch>,
+}
+
+impl Meta {
+    fn resolve(upload: &UploadMeta, args: &Input, ctx: &Context) -> Result<Meta, Error> {
+        let request = upload_meta(upload, args.multiple);
+        log::debug!("Send file metadata: {:?}", serde_json::to_string(&request));
+        let fauth = args.endpoint.to_file_auth(ctx);
+        let patch = ItemPatch::resolve(upload, &fauth, ctx).context(Patch)?;
+        Ok(Meta { request, patch })
+    }
+
+    /// Returns the metadata of the file's sidecar, if `--sidecar` is
+    /// given and it exists.
+    fn of_sidecar(file: &Path, args: &Input, ctx: &Context) -> Result<Option<Meta>, Error> {
+        if !args.sidecar {
+            return Ok(None);
+        }
+        match Sidecar::find(file) {
+            Some(path) => {
+                log::debug!("Using sidecar {} for {}", path.display(), file.display());
+                let sidecar = Sidecar::read(&path).context(SidecarFile)?;
+                let upload = sidecar
+                    .apply(&args.upload.with_defaults(ctx.cfg), &path)
+                    .context(SidecarFile)?;
+                Meta::resolve(&upload, args, ctx).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Whether the file is skipped, because it is the sidecar of another
+/// file.
+fn is_sidecar(path: &Path, args: &Input) -> bool {
+    let skip = args.sidecar && Sidecar::is_sidecar(path);
+    if skip {
+        log::debug!("Skip sidecar file {}", path.display());
+    }
+    skip
+}
+
+/// Loads the upload cache, if it is enabled. It is only used when
+/// duplicates are skipped and files are neither moved nor deleted,
+/// because otherwise no file is seen twice.
+fn open_cache(args: &Input, ctx: &Context) -> Result<Option<UploadCache>, Error> {
+    let keep_files = !args.action.delete && args.action.move_to.is_none();
+    if args.cache.cache.is_some() && !keep_files {
+        events::warn("The upload cache is not used when files are moved or deleted");
+    }
+    match args.cache.cache_file(ctx.cfg) {
+        Some(file) if args.upload.skip_duplicates && keep_files => {
+            let cache = UploadCache::load(&file).context(Cache)?;
+            Ok(Some(cache.with_scope(cache_scope(args, ctx)?)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns the scope of the upload cache, which is the docspell url
+/// and the collective or source the files are checked with.
+fn cache_scope(args: &Input, ctx: &Context) -> Result<String, Error> {
+    let target = match args.endpoint.to_file_auth(ctx) {
+        FileAuth::Source { id } => format!("source:{}", id),
+        FileAuth::Integration(data) => format!("collective:{}", data.collective),
+        FileAuth::Session { token } => {
+            let info = ctx.client.session_info(&token).context(HttpClient)?;
+            format!("collective:{}", info.collective)
+        }
+    };
+    Ok(format!("{} {}", ctx.base_url(), target))
+}
+
+/// Stores the cache and prints the summary, if there was more than
+/// one file, after each upload run.
+fn finish_run(prep: &Prepared, ctx: &Context) -> Result<(), Error> {
+    let report = prep.summary.take_report();
+    if prep.print_summary && report.files() > 1 && !ctx.opts.is_quiet() {
+        report.print(ctx.format());
+    }
+    match &prep.cache {
+        Some(cache) => cache.store().context(Cache),
+        None => Ok(()),
+    }
+}
+
+/// Determines which files would be uploaded and what would happen to
+/// them, without uploading or changing any file.
+fn dry_run(args: &Input, ctx: &Context) -> Result<DryRun, Error> {
+    check_flags(args)?;
+    let matcher = matching::Matcher::new(args)?;
+    let fauth = args.endpoint.to_file_auth(ctx);
+    let meta = Meta::resolve(&args.upload.with_defaults(ctx.cfg), args, ctx)?;
+    let cache = open_cache(args, ctx)?;
+    let mut files = Vec::new();
+    if args.stdin {
+        let name = args.name.as_ref().ok_or(Error::StdinWithoutName)?;
+        files.push(PlannedFile::new(
+            PathBuf::from(name),
+            false,
+            true,
+            FileActionResult::Nothing,
+        ));
+    } else {
+        let jobs = if args.traverse {
+            traverse_jobs(args, &matcher)?
+        } else {
+            args.files
+                .iter()
+                .filter(|p| matcher.is_included(p) && !is_sidecar(p, args))
+                .map(|p| (p.clone(), None))
+                .collect()
+        };
+        for (file, root) in jobs {
+            let exists = check_existence(&file, cache.as_ref(), args, ctx)?.exists;
+            let action = args.action.plan(&file, root);
+            let mut planned = PlannedFile::new(file, exists, !exists, action);
+            if args.sidecar {
+                planned.sidecar = Sidecar::find(&planned.file);
+                planned.meta = Meta::of_sidecar(&planned.file, args, ctx)?.map(|m| m.request);
+            }
+            files.push(planned);
+        }
+    }
+    Ok(DryRun {
+        endpoint: ctx.client.upload_url(&fauth),
+        meta: Some(meta.request),
+        files,
+    })
+}
+
+fn upload_meta(upload: &UploadMeta, multiple: bool) -> MetaRequest {
+    MetaRequest {
+        multiple,
+        direction: upload.direction.clone().map(|d| d.to_value().to_string()),
+        folder: upload.folder.clone(),
+        skip_duplicates: upload.skip_duplicates,
+        tags: StringList {
+            items: upload.tag.clone(),
+        },
+        file_filter: upload.file_filter.clone(),
+        language: upload.language.clone(),
+        attachments_only: upload.attachments_only,
+    }
+}
+
+fn apply_file_action(
+    path: &Path,
+    root: Option<&PathBuf>,
+    opts: &Input,
+    progress: &Progress,
+) -> Result<(), Error> {
+    let sidecar = if opts.sidecar {
+        Sidecar::find(path)
+    } else {
+        None
+    };
     let res = opts
         .action
         .execute(path, root)
         .context(FileActionError { path })?;
+    if let Some(file) = sidecar {
+        opts.action
+            .execute(&file, root)
+            .context(FileActionError { path: &file })?;
+    }
     match res {
         FileActionResult::Deleted(_p) => {
-            eprintln!("Deleted file");
+            progress.println("Deleted file");
             Ok(())
         }
         FileActionResult::Moved(p) => {
-            eprintln!("Moved file to: {}", p.display());
+            progress.println(format!("Moved file to: {}", p.display()));
             Ok(())
         }
         FileActionResult::Nothing => Ok(()),
@@ -207,112 +551,192 @@ fn apply_file_action(path: &Path, root: Option<&PathBuf>, opts: &Input) -> Resul
 }
 
 fn upload_traverse(
-    meta: &MetaRequest,
+    prep: &Prepared,
     opts: &Input,
     ctx: &Context,
     matcher: &matching::Matcher,
 ) -> Result<BasicResult, Error> {
     log::debug!("Upload by traversing directory");
-    let mut counter = 0;
-    let fauth = opts.endpoint.to_file_auth(ctx);
+    let jobs = traverse_jobs(opts, matcher)?;
+    let total = jobs.len();
+    let total_bytes = jobs.iter().map(|(f, _)| file_size(f)).sum();
+    let progress = Progress::files(ctx.show_progress(), total as u64, total_bytes);
+    let counter = AtomicUsize::new(0);
+    let failed: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    let workers = parallel::workers(opts.concurrency, total);
+    log::debug!("Uploading {} files using {} worker(s)", total, workers);
+    parallel::for_each(jobs.into_iter(), workers, |(file, root)| {
+        match upload_traversed(&file, root, prep, opts, ctx, &progress) {
+            Ok(true) => {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(false) => prep.summary.skipped(&file),
+            Err(err) => {
+                prep.summary.failed(&file, err.to_string());
+                log::debug!("Error uploading {}: {:?}", file.display(), err);
+                progress.print_error(format!("Uploading {} failed: {}", file.display(), err));
+                failed.lock().unwrap().push(file.clone());
+            }
+        }
+        progress.inc_file(file_size(&file));
+    });
+    progress.finish();
+
+    let failed = failed.into_inner().unwrap();
+    if !failed.is_empty() {
+        return Err(Error::UploadFailures {
+            uploaded: counter.into_inner(),
+            failed,
+        });
+    }
+    Ok(BasicResult {
+        success: true,
+        message: format!("Uploaded {}", counter.into_inner()),
+    })
+}
+
+/// Collects all files to upload from the given files and directories,
+/// together with the directory they were found in.
+fn traverse_jobs<'a>(
+    opts: &'a Input,
+    matcher: &matching::Matcher,
+) -> Result<Vec<(PathBuf, Option<&'a PathBuf>)>, Error> {
+    let mut jobs = Vec::new();
     for path in &opts.files {
         if path.is_dir() {
             for child in matcher.traverse(path)? {
-                let exists = check_existence(&child, opts, ctx)?;
-                if !exists {
-                    eprintln!("Uploading {}", child.display());
-                    counter += 1;
-                    if !opts.dry_run {
-                        let res = ctx
-                            .client
-                            .upload_files(&fauth, meta, &[child.as_path()])
-                            .context(HttpClient)?;
-                        if res.success {
-                            apply_file_action(&child, Some(path), opts)?;
-                        }
-                    }
-                } else {
-                    file_exists_message(&child);
-                    apply_file_action(&child, Some(path), opts)?;
+                if !is_sidecar(&child, opts) {
+                    jobs.push((child, Some(path)));
                 }
             }
-        } else if matcher.is_included(path) {
-            let exists = check_existence(path, opts, ctx)?;
-            if !exists {
-                eprintln!("Uploading file {}", path.display());
-                counter += 1;
-                if !opts.dry_run {
-                    let res = ctx
-                        .client
-                        .upload_files(&fauth, meta, &[path.as_path()])
-                        .context(HttpClient)?;
-                    if res.success {
-                        apply_file_action(path, None, opts)?;
-                    }
-                }
-            } else {
-                file_exists_message(path);
-                apply_file_action(path, None, opts)?;
+        } else if matcher.is_included(path) && !is_sidecar(path, opts) {
+            jobs.push((path.clone(), None));
+        }
+    }
+    Ok(jobs)
+}
+
+/// Uploads a single file found while traversing, if it doesn't exist
+/// yet. Returns whether the file was (or would be) uploaded.
+fn upload_traversed(
+    file: &Path,
+    root: Option<&PathBuf>,
+    prep: &Prepared,
+    opts: &Input,
+    ctx: &Context,
+    progress: &Progress,
+) -> Result<bool, Error> {
+    let existence = check_existence(file, prep.cache.as_ref(), opts, ctx)?;
+    if !existence.exists {
+        progress.println(format!("Uploading {}", file.display()));
+        if !opts.dry_run {
+            let own_meta = Meta::of_sidecar(file, opts, ctx)?;
+            let meta = own_meta.as_ref().unwrap_or(&prep.meta);
+            let pending = match &meta.patch {
+                Some(p) => Some(p.prepare_files(&[file], ctx).context(Patch)?),
+                None => None,
+            };
+            let fauth = opts.endpoint.to_upload_auth(ctx);
+            let res = ctx
+                .client
+                .upload_files(&fauth, &meta.request, &[file], prep.compress)
+                .context(HttpClient)?;
+            if !res.success {
+                return Err(Error::UploadRejected {
+                    message: res.message,
+                });
             }
+            remember_upload(file, existence.hash, prep);
+            prep.summary.uploaded(file);
+            apply_file_action(file, root, opts, progress)?;
+            if let Some(pending) = pending {
+                progress.println(format!("Waiting for the item of {}", file.display()));
+                pending.apply(ctx).context(Patch)?;
+            }
+        }
+        Ok(true)
+    } else {
+        file_exists_message(file, progress);
+        if !opts.dry_run {
+            apply_file_action(file, root, opts, progress)?;
         }
+        Ok(false)
     }
+}
 
-    Ok(BasicResult {
-        success: true,
-        message: format!("Uploaded {}", counter),
-    })
+fn file_exists_message(path: &Path, progress: &Progress) {
+    progress.println(format!("File already in Docspell: {}", path.display()));
 }
 
-fn file_exists_message(path: &Path) {
-    eprintln!("File already in Docspell: {}", path.display());
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
 }
 
-/// Uploads all files in a single request.
+/// Uploads all files in a single request. Files with a sidecar are
+/// sent in a request of their own, because their metadata differs.
 fn upload_single(
-    meta: &MetaRequest,
+    prep: &Prepared,
     opts: &Input,
     ctx: &Context,
     matcher: matching::Matcher,
 ) -> Result<BasicResult, Error> {
     log::debug!("Upload using a single request");
-    let fauth = opts.endpoint.to_file_auth(ctx);
-    let mut files: Vec<&Path> = Vec::new();
+    let progress = Progress::hidden();
+    let mut files: Vec<(&Path, Option<String>)> = Vec::new();
+    let mut own_requests: Vec<(Meta, &Path, Option<String>)> = Vec::new();
     for path in &opts.files {
+        if is_sidecar(path, opts) {
+            continue;
+        }
         if matcher.is_included(path) {
-            let exists = check_existence(path, opts, ctx)?;
-            if !exists {
-                eprintln!("Adding to single request: {}", path.display());
-                if !opts.dry_run {
-                    files.push(path);
+            let existence = check_existence(path, prep.cache.as_ref(), opts, ctx)?;
+            if !existence.exists {
+                if opts.dry_run {
+                    status!("Adding to single request: {}", path.display());
+                    files.push((path, existence.hash));
+                } else if let Some(meta) = Meta::of_sidecar(path, opts, ctx)? {
+                    status!("Adding to own request: {}", path.display());
+                    own_requests.push((meta, path, existence.hash));
+                } else {
+                    status!("Adding to single request: {}", path.display());
+                    files.push((path, existence.hash));
                 }
             } else {
-                file_exists_message(path);
-                apply_file_action(path, None, opts)?;
+                file_exists_message(path, &progress);
+                prep.summary.skipped(path);
+                if !opts.dry_run {
+                    apply_file_action(path, None, opts, &progress)?;
+                }
             }
         } else {
-            eprintln!("Skip '{}', doesn't match given pattern(s)", path.display());
+            status!("Skip '{}', doesn't match given pattern(s)", path.display());
         }
     }
 
     if !opts.dry_run {
-        if !files.is_empty() {
-            eprintln!("Sending request …");
-            let result = ctx
-                .client
-                .upload_files(&fauth, meta, &files)
-                .context(HttpClient)?;
-            if result.success {
-                for path in &files {
-                    apply_file_action(path, None, opts)?;
-                }
-            }
-            Ok(result)
-        } else {
-            Ok(BasicResult {
+        if files.is_empty() && own_requests.is_empty() {
+            return Ok(BasicResult {
                 success: true,
                 message: "No files to upload".into(),
-            })
+            });
+        }
+        let mut results = Vec::new();
+        if !files.is_empty() {
+            results.push(send_files(files, &prep.meta, prep, opts, ctx)?);
+        }
+        for (meta, path, hash) in own_requests {
+            results.push(send_files(vec![(path, hash)], &meta, prep, opts, ctx)?);
         }
+        let mut messages: Vec<String> = Vec::new();
+        for result in &results {
+            if !messages.contains(&result.message) {
+                messages.push(result.message.clone());
+            }
+        }
+        Ok(BasicResult {
+            success: results.iter().all(|r| r.success),
+            message: messages.join(" "),
+        })
     } else {
         Ok(BasicResult {
             success: true,
@@ -321,17 +745,177 @@ fn upload_single(
     }
 }
 
-fn check_existence(path: &Path, opts: &Input, ctx: &Context) -> Result<bool, Error> {
+/// Uploads the files in one request and applies the file action to
+/// them if the server accepted it. The files come with their hash,
+/// if it is known already.
+fn send_files(
+    hashed: Vec<(&Path, Option<String>)>,
+    meta: &Meta,
+    prep: &Prepared,
+    opts: &Input,
+    ctx: &Context,
+) -> Result<BasicResult, Error> {
+    let progress = Progress::hidden();
+    let files: Vec<&Path> = hashed.iter().map(|(path, _)| *path).collect();
+    let files = files.as_slice();
+    let fauth = opts.endpoint.to_upload_auth(ctx);
+    let pending = match &meta.patch {
+        Some(p) => Some(p.prepare_files(files, ctx).context(Patch)?),
+        None => None,
+    };
+    status!("Sending request …");
+    let result = match ctx
+        .client
+        .upload_files(&fauth, &meta.request, files, prep.compress)
+    {
+        Ok(r) => r,
+        Err(err) => {
+            for path in files {
+                prep.summary.failed(path, err.to_string());
+            }
+            return Err(Error::HttpClient { source: err });
+        }
+    };
+    if result.success {
+        for (path, hash) in hashed {
+            remember_upload(path, hash, prep);
+            prep.summary.uploaded(path);
+            apply_file_action(path, None, opts, &progress)?;
+        }
+        if let Some(pending) = pending {
+            wait_and_patch(pending, ctx)?;
+        }
+    } else {
+        for path in files {
+            prep.summary.failed(path, result.message.clone());
+        }
+    }
+    Ok(result)
+}
+
+/// Uploads the data read from stdin as a single file.
+fn upload_stdin(prep: &Prepared, opts: &Input, ctx: &Context) -> Result<BasicResult, Error> {
+    let name = opts.name.as_ref().ok_or(Error::StdinWithoutName)?;
+    let mut data = Vec::new();
+    std::io::stdin().read_to_end(&mut data).context(ReadStdin)?;
+
     if opts.upload.skip_duplicates {
+        let hash = digest::digest::<Sha256, _>(&mut data.as_slice()).context(ReadStdin)?;
+        let fauth = opts.endpoint.to_file_auth(ctx);
+        if ctx
+            .client
+            .file_exists(hash, &fauth)
+            .context(HttpClient)?
+            .exists
+        {
+            return Ok(BasicResult {
+                success: true,
+                message: format!("File already in Docspell: {}", name),
+            });
+        }
+    }
+
+    if opts.dry_run {
+        Ok(BasicResult {
+            success: true,
+            message: format!("Would upload {} bytes as '{}'", data.len(), name),
+        })
+    } else {
+        let pending = match &prep.meta.patch {
+            Some(p) => {
+                let hash = digest::digest::<Sha256, _>(&mut data.as_slice()).context(ReadStdin)?;
+                Some(p.prepare(vec![(hash, name.clone())], ctx).context(Patch)?)
+            }
+            None => None,
+        };
+        status!("Uploading {} bytes from stdin as '{}'", data.len(), name);
+        let fauth = opts.endpoint.to_upload_auth(ctx);
+        let result = ctx
+            .client
+            .upload_data(&fauth, &prep.meta.request, name, &data, prep.compress)
+            .context(HttpClient)?;
+        if let (true, Some(pending)) = (result.success, pending) {
+            wait_and_patch(pending, ctx)?;
+        }
+        Ok(result)
+    }
+}
+
+fn wait_and_patch(pending: Pending, ctx: &Context) -> Result<(), Error> {
+    status!("Waiting for docspell to create the item …");
+    pending.apply(ctx).context(Patch)
+}
+
+/// Whether a file is in Docspell already, with its hash if it was
+/// computed for the check.
+struct Existence {
+    exists: bool,
+    hash: Option<String>,
+}
+
+fn check_existence(
+    path: &Path,
+    cache: Option<&UploadCache>,
+    opts: &Input,
+    ctx: &Context,
+) -> Result<Existence, Error> {
+    if opts.upload.skip_duplicates {
+        if cache.is_some_and(|c| c.contains(path)) {
+            log::debug!("File {} is in the upload cache", path.display());
+            return Ok(Existence {
+                exists: true,
+                hash: None,
+            });
+        }
         let fauth = opts.endpoint.to_file_auth(ctx);
         let hash = digest::digest_file_sha256(path).context(DigestFile { path })?;
-        let exists = ctx.client.file_exists(hash, &fauth).context(HttpClient)?;
-        Ok(exists.exists)
+        if let Some(cache) = cache.filter(|c| c.contains_hash(path, &hash)) {
+            log::debug!(
+                "File {} is in the upload cache with the same hash",
+                path.display()
+            );
+            cache.insert(path, hash.clone());
+            return Ok(Existence {
+                exists: true,
+                hash: Some(hash),
+            });
+        }
+        let exists = ctx
+            .client
+            .file_exists(hash.clone(), &fauth)
+            .context(HttpClient)?;
+        if let (true, Some(cache)) = (exists.exists, cache) {
+            cache.insert(path, hash.clone());
+        }
+        Ok(Existence {
+            exists: exists.exists,
+            hash: Some(hash),
+        })
     } else {
-        Ok(false)
+        Ok(Existence {
+            exists: false,
+            hash: None,
+        })
     }
 }
 
+/// Adds an uploaded file to the cache, using the hash computed when
+/// checking for duplicates. The cache is only open when duplicates
+/// are checked, so the hash is always known then.
+fn remember_upload(path: &Path, hash: Option<String>, prep: &Prepared) {
+    if let (Some(cache), Some(hash)) = (&prep.cache, hash) {
+        cache.insert(path, hash);
+    }
+}
+
+fn path_list(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 // TODO use clap to solve this!
 fn check_flags(args: &Input) -> Result<(), Error> {
     if args.traverse && !args.multiple {
@@ -340,22 +924,83 @@ fn check_flags(args: &Input) -> Result<(), Error> {
     if args.poll.is_some() && !args.traverse {
         return Err(Error::PollWithoutTraverse);
     }
+    if args.stdin && args.name.is_none() {
+        return Err(Error::StdinWithoutName);
+    }
+    check_item_name(
+        &args.upload,
+        args.multiple && (args.traverse || args.files.len() > 1),
+    )
+}
 
-    Ok(())
+/// Checks that `--item-name` can be used, if many items are created.
+pub fn check_item_name(meta: &UploadMeta, many_items: bool) -> Result<(), Error> {
+    match &meta.item_name {
+        Some(name) if many_items && !patch::is_name_template(name) => {
+            Err(Error::ItemNameWithoutTemplate)
+        }
+        _ => Ok(()),
+    }
 }
 
 //////////////////////////////////////////////////////////////////////////////
-/// Helper types
+// Helper types
 
-mod matching {
+pub(super) mod matching {
     use super::*;
-    use glob::{GlobResult, Paths, Pattern};
+    use glob::{GlobResult, MatchOptions, Paths, Pattern};
 
     pub struct Matcher {
         include: glob::Pattern,
         include_glob: String,
         exclude: Option<glob::Pattern>,
         exclude_glob: Option<String>,
+        filter: Filter,
+    }
+
+    /// The patterns given via `--include` and `--exclude`.
+    #[derive(Clone)]
+    pub struct Filter {
+        include: Vec<Pattern>,
+        exclude: Vec<Pattern>,
+        options: MatchOptions,
+    }
+
+    impl Filter {
+        pub fn new(
+            include: &[String],
+            exclude: &[String],
+            case_sensitive: bool,
+        ) -> Result<Filter, Error> {
+            Ok(Filter {
+                include: parse_patterns(include)?,
+                exclude: parse_patterns(exclude)?,
+                options: MatchOptions {
+                    case_sensitive,
+                    ..MatchOptions::new()
+                },
+            })
+        }
+
+        pub fn accepts(&self, path: &Path) -> bool {
+            let included = self.include.is_empty()
+                || self
+                    .include
+                    .iter()
+                    .any(|p| p.matches_path_with(path, self.options));
+            included
+                && !self
+                    .exclude
+                    .iter()
+                    .any(|p| p.matches_path_with(path, self.options))
+        }
+    }
+
+    fn parse_patterns(globs: &[String]) -> Result<Vec<Pattern>, Error> {
+        globs
+            .iter()
+            .map(|g| Pattern::new(g).context(BadGlobPattern { pattern: g.clone() }))
+            .collect()
     }
 
     impl Matcher {
@@ -375,12 +1020,13 @@ mod matching {
                 include_glob: args.matches.clone(),
                 exclude,
                 exclude_glob: args.not_matches.clone(),
+                filter: Filter::new(&args.include, &args.exclude, args.case_sensitive)?,
             })
         }
 
         pub fn is_included(&self, path: &Path) -> bool {
             let bi = self.include.matches_path(path);
-            let result = bi && !check_exclude(&self.exclude, path);
+            let result = bi && !check_exclude(&self.exclude, path) && self.filter.accepts(path);
             log::debug!("Including '{}': {}", path.display(), result);
             result
         }
@@ -395,6 +1041,8 @@ mod matching {
             Ok(Matches {
                 paths,
                 excl: self.exclude.clone(),
+                filter: self.filter.clone(),
+                root: start.to_path_buf(),
             })
         }
     }
@@ -402,6 +1050,8 @@ mod matching {
     pub struct Matches {
         paths: Paths,
         excl: Option<Pattern>,
+        filter: Filter,
+        root: PathBuf,
     }
 
     impl Iterator for Matches {
@@ -410,7 +1060,9 @@ mod matching {
         fn next(&mut self) -> Option<Self::Item> {
             loop {
                 let next = self.paths.next()?;
-                let filtered = match_result(next, &self.excl).filter(|p| p.is_file());
+                let filtered = match_result(next, &self.excl)
+                    .filter(|p| p.is_file())
+                    .filter(|p| self.filter.accepts(p.strip_prefix(&self.root).unwrap_or(p)));
                 if filtered.is_some() {
                     return filtered;
                 } else {
@@ -433,7 +1085,10 @@ mod matching {
             }
             Err(err) => {
                 log::error!("Cannot read file while traversing: {}", err);
-                eprintln!("Skipping some entry while traversing due to error: {}", err);
+                events::warn(&format!(
+                    "Skipping some entry while traversing due to error: {}",
+                    err
+                ));
                 None
             }
         }
@@ -445,4 +1100,31 @@ mod matching {
             None => false,
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn unit_filter_include_exclude() {
+            let filter = Filter::new(&["**/*.pdf".into()], &["**/tmp/**".into()], false).unwrap();
+            assert!(filter.accepts(Path::new("a.pdf")));
+            assert!(filter.accepts(Path::new("x/y/A.PDF")));
+            assert!(!filter.accepts(Path::new("x/a.txt")));
+            assert!(!filter.accepts(Path::new("x/tmp/a.pdf")));
+        }
+
+        #[test]
+        fn unit_filter_case_sensitive() {
+            let filter = Filter::new(&["**/*.pdf".into()], &[], true).unwrap();
+            assert!(filter.accepts(Path::new("a.pdf")));
+            assert!(!filter.accepts(Path::new("A.PDF")));
+        }
+
+        #[test]
+        fn unit_filter_empty() {
+            let filter = Filter::new(&[], &[], false).unwrap();
+            assert!(filter.accepts(Path::new("any/file.txt")));
+        }
+    }
 }
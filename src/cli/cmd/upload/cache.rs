@@ -0,0 +1,289 @@
+//! A local cache of files that are known to be in docspell.
+//!
+//! Each file is stored with its absolute path, size, modification
+//! time and sha256 hash. A file that is in the cache and has not
+//! changed since can be skipped without hashing it and asking the
+//! server. A file that was only touched is recognized by its hash.
+//! This is only a shortcut: the server's duplicate check decides for
+//! all other files.
+//!
+//! The entries are kept per scope, which is the docspell url and the
+//! collective or source the files were checked with. So a file known
+//! to one server or collective is still checked with all others.
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use crate::config::DsConfig;
+use crate::util::file::AtomicFile;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unable to read upload cache {}: {}", path.display(), source))]
+    ReadCache {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("The upload cache {} is invalid: {}", path.display(), source))]
+    ParseCache {
+        source: serde_json::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Unable to write upload cache {}: {}", path.display(), source))]
+    WriteCache {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+/// The cache, which can be shared by multiple upload threads.
+#[derive(Debug)]
+pub struct UploadCache {
+    file: PathBuf,
+    scope: String,
+    state: Mutex<State>,
+}
+
+/// The entries of each scope.
+type Scopes = BTreeMap<String, BTreeMap<PathBuf, Entry>>;
+
+#[derive(Debug, Default)]
+struct State {
+    scopes: Scopes,
+    changed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Entry {
+    size: u64,
+    modified: u64,
+    sha256: String,
+}
+
+impl UploadCache {
+    /// Returns the cache file from the config, or the default
+    /// location in the user's cache directory.
+    pub fn configured_file(cfg: &DsConfig) -> Option<PathBuf> {
+        cfg.upload_cache
+            .clone()
+            .or_else(|| dirs::cache_dir().map(|dir| dir.join("dsc").join("upload-cache.json")))
+    }
+
+    /// Reads the cache from the given file. If it doesn't exist, the
+    /// cache is empty. The entries of older versions, which had no
+    /// scope, are dropped.
+    pub fn load(file: &Path) -> Result<UploadCache, Error> {
+        let mut changed = false;
+        let scopes: Scopes = if file.exists() {
+            let data = std::fs::read(file).context(ReadCache { path: file })?;
+            match serde_json::from_slice(&data) {
+                Ok(scopes) => scopes,
+                Err(err) => {
+                    serde_json::from_slice::<BTreeMap<PathBuf, Entry>>(&data)
+                        .map_err(|_| err)
+                        .context(ParseCache { path: file })?;
+                    log::debug!("Dropping the entries of an old upload cache");
+                    changed = true;
+                    BTreeMap::new()
+                }
+            }
+        } else {
+            BTreeMap::new()
+        };
+        log::debug!(
+            "Read {} entries from upload cache {}",
+            scopes.values().map(BTreeMap::len).sum::<usize>(),
+            file.display()
+        );
+        Ok(UploadCache {
+            file: file.to_path_buf(),
+            scope: String::new(),
+            state: Mutex::new(State { scopes, changed }),
+        })
+    }
+
+    /// Uses the entries of the given scope.
+    pub fn with_scope(self, scope: String) -> UploadCache {
+        UploadCache { scope, ..self }
+    }
+
+    /// Whether the file is in the cache and hasn't changed since.
+    pub fn contains(&self, path: &Path) -> bool {
+        match Entry::key(path) {
+            Some((key, size, modified)) => self
+                .get(&key)
+                .is_some_and(|e| e.size == size && e.modified == modified),
+            None => false,
+        }
+    }
+
+    /// Whether the file is in the cache with the given hash, so its
+    /// content is the same even if it has been touched since.
+    pub fn contains_hash(&self, path: &Path, sha256: &str) -> bool {
+        match std::fs::canonicalize(path) {
+            Ok(key) => self.get(&key).is_some_and(|e| e.sha256 == sha256),
+            Err(_) => false,
+        }
+    }
+
+    fn get(&self, key: &Path) -> Option<Entry> {
+        let state = self.state.lock().unwrap();
+        state.scopes.get(&self.scope)?.get(key).cloned()
+    }
+
+    /// Remembers the file with its hash. Files that cannot be read
+    /// anymore, for example because they have been moved, are not
+    /// added.
+    pub fn insert(&self, path: &Path, sha256: String) {
+        if let Some((key, size, modified)) = Entry::key(path) {
+            let mut state = self.state.lock().unwrap();
+            let entry = Entry {
+                size,
+                modified,
+                sha256,
+            };
+            let entries = state.scopes.entry(self.scope.clone()).or_default();
+            if entries.get(&key) != Some(&entry) {
+                entries.insert(key, entry);
+                state.changed = true;
+            }
+        }
+    }
+
+    /// Removes all entries of all scopes whose file doesn't exist
+    /// anymore or has changed. Returns the number of removed entries.
+    pub fn prune(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let mut removed = 0;
+        for entries in state.scopes.values_mut() {
+            let before = entries.len();
+            entries.retain(|path, entry| match Entry::key(path) {
+                Some((_, size, modified)) => entry.size == size && entry.modified == modified,
+                None => false,
+            });
+            removed += before - entries.len();
+        }
+        state.scopes.retain(|_, entries| !entries.is_empty());
+        if removed > 0 {
+            state.changed = true;
+        }
+        removed
+    }
+
+    /// Returns the number of entries in all scopes.
+    pub fn len(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.scopes.values().map(BTreeMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the cache back to its file, if it has been changed.
+    pub fn store(&self) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        if !state.changed {
+            return Ok(());
+        }
+        log::debug!("Writing upload cache {}", self.file.display());
+        if let Some(dir) = self.file.parent() {
+            std::fs::create_dir_all(dir).context(WriteCache { path: &self.file })?;
+        }
+        let data = serde_json::to_vec(&state.scopes).map_err(|e| Error::WriteCache {
+            source: e.into(),
+            path: self.file.clone(),
+        })?;
+        let mut out = AtomicFile::create(&self.file).context(WriteCache { path: &self.file })?;
+        out.write_all(&data)
+            .and_then(|_| out.commit())
+            .context(WriteCache { path: &self.file })?;
+        state.changed = false;
+        Ok(())
+    }
+}
+
+impl Entry {
+    /// Returns the absolute path, size and modification time (in
+    /// millis) of a file.
+    fn key(path: &Path) -> Option<(PathBuf, u64, u64)> {
+        let key = std::fs::canonicalize(path).ok()?;
+        let meta = std::fs::metadata(&key).ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some((key, meta.len(), modified.as_millis() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_cache_detects_changes() {
+        let dir = std::env::temp_dir().join(format!("dsc-cache-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.txt");
+        let gone = dir.join("b.txt");
+        std::fs::write(&file, "hello").unwrap();
+        std::fs::write(&gone, "bye").unwrap();
+
+        let cache = UploadCache::load(&dir.join("cache.json")).unwrap();
+        assert!(!cache.contains(&file));
+        cache.insert(&file, "abc".into());
+        cache.insert(&gone, "def".into());
+        assert!(cache.contains(&file));
+        cache.store().unwrap();
+
+        let cache = UploadCache::load(&dir.join("cache.json")).unwrap();
+        assert_eq!(cache.len(), 2);
+        std::fs::write(&file, "hello world").unwrap();
+        std::fs::remove_file(&gone).unwrap();
+        assert!(!cache.contains(&file));
+        assert_eq!(cache.prune(), 2);
+        assert!(cache.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unit_cache_scopes() {
+        let dir = std::env::temp_dir().join(format!("dsc-cache-scope-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "hello").unwrap();
+        let cache_file = dir.join("cache.json");
+
+        let cache = UploadCache::load(&cache_file)
+            .unwrap()
+            .with_scope("http://a collective:demo".into());
+        cache.insert(&file, "abc".into());
+        assert!(cache.contains(&file));
+        assert!(cache.contains_hash(&file, "abc"));
+        assert!(!cache.contains_hash(&file, "def"));
+        cache.store().unwrap();
+
+        let other = UploadCache::load(&cache_file)
+            .unwrap()
+            .with_scope("http://b collective:demo".into());
+        assert!(!other.contains(&file));
+        assert!(!other.contains_hash(&file, "abc"));
+        assert_eq!(other.len(), 1);
+
+        // the unscoped entries of older versions are dropped
+        std::fs::write(
+            &cache_file,
+            r#"{"/tmp/x":{"size":1,"modified":2,"sha256":"abc"}}"#,
+        )
+        .unwrap();
+        assert!(UploadCache::load(&cache_file).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
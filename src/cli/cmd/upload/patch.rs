@@ -0,0 +1,407 @@
+//! Changes uploaded items with values that can't be given in the
+//! upload request, like custom fields or correspondents.
+//!
+//! Docspell processes uploads asynchronously and doesn't return the
+//! id of the new item. The checksum of the uploaded file is used to
+//! wait until the item has been created, then it is changed. If the
+//! server skips the file as a duplicate, no new item appears and the
+//! existing items with this file are changed instead.
+
+use chrono::NaiveDate;
+use snafu::{ResultExt, Snafu};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::Context;
+use crate::cli::opts::UploadMeta;
+use crate::http::payload::{BasicResult, CustomFieldDef, CustomFieldValue, IdNameList, ItemShort};
+use crate::http::{Error as HttpError, FileAuth};
+use crate::util::digest;
+
+const POLL_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error creating hash for '{}': {}", path.display(), source))]
+    DigestFile {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+
+    #[snafu(display(
+        "Changing the uploaded item requires a login, it can't be used with a source id or the integration endpoint"
+    ))]
+    RequiresLogin,
+
+    #[snafu(display("The custom field '{}' doesn't exist", name))]
+    UnknownField { name: String },
+
+    #[snafu(display("No {} named '{}' exists", kind, name))]
+    UnknownEntity { kind: &'static str, name: String },
+
+    #[snafu(display("Invalid value '{}' for {} field '{}': {}", value, ftype, name, reason))]
+    InvalidValue {
+        name: String,
+        ftype: String,
+        value: String,
+        reason: String,
+    },
+
+    #[snafu(display("The item was not created within {} seconds", secs))]
+    ItemTimeout { secs: u64 },
+
+    #[snafu(display("Setting '{}' failed: {}", name, message))]
+    UpdateFailed { name: String, message: String },
+}
+
+/// The values to set on an uploaded item.
+pub struct ItemPatch {
+    fields: Vec<CustomFieldValue>,
+    refs: Vec<ItemRef>,
+    name: Option<String>,
+    timeout: Duration,
+    /// Whether the server skips files it knows already.
+    skip_duplicates: bool,
+}
+
+/// A reference to another entity, like the correspondent.
+struct ItemRef {
+    field: &'static str,
+    id: String,
+}
+
+#[derive(Clone, Copy)]
+enum Entity {
+    Organization,
+    Person,
+    Equipment,
+}
+
+impl Entity {
+    fn name(self) -> &'static str {
+        match self {
+            Entity::Organization => "organization",
+            Entity::Person => "person",
+            Entity::Equipment => "equipment",
+        }
+    }
+
+    fn list(self, ctx: &Context) -> Result<IdNameList, HttpError> {
+        let token = &ctx.opts.session;
+        match self {
+            Entity::Organization => ctx.client.list_organizations(token, ""),
+            Entity::Person => ctx.client.list_persons(token, ""),
+            Entity::Equipment => ctx.client.list_equipments(token, ""),
+        }
+    }
+
+    /// Finds the id of the entity with the given name or id.
+    fn resolve(self, name: &str, ctx: &Context) -> Result<String, Error> {
+        let list = self.list(ctx).context(HttpClient)?;
+        list.items
+            .iter()
+            .find(|e| e.name.eq_ignore_ascii_case(name))
+            .or_else(|| list.items.iter().find(|e| e.id == name))
+            .map(|e| e.id.clone())
+            .ok_or_else(|| Error::UnknownEntity {
+                kind: self.name(),
+                name: name.to_string(),
+            })
+    }
+}
+
+/// An upload that must be changed once docspell created the item.
+pub struct Pending<'a> {
+    patch: &'a ItemPatch,
+    files: Vec<PendingFile>,
+}
+
+struct PendingFile {
+    hash: String,
+    name: String,
+    known_items: Vec<String>,
+}
+
+impl ItemPatch {
+    /// Checks the values given in `meta`. Returns `None` if there is
+    /// nothing to change after uploading.
+    pub fn resolve(
+        meta: &UploadMeta,
+        fauth: &FileAuth,
+        ctx: &Context,
+    ) -> Result<Option<ItemPatch>, Error> {
+        let ref_opts = [
+            ("corrOrg", Entity::Organization, &meta.correspondent_org),
+            ("corrPerson", Entity::Person, &meta.correspondent_person),
+            ("concPerson", Entity::Person, &meta.concerning_person),
+            (
+                "concEquipment",
+                Entity::Equipment,
+                &meta.concerning_equipment,
+            ),
+        ];
+        if meta.custom_field.is_empty()
+            && meta.item_name.is_none()
+            && ref_opts.iter().all(|(_, _, v)| v.is_none())
+        {
+            return Ok(None);
+        }
+        if !matches!(fauth, FileAuth::Session { .. }) {
+            return Err(Error::RequiresLogin);
+        }
+        let mut refs = Vec::new();
+        for (field, entity, value) in ref_opts.iter() {
+            if let Some(name) = value {
+                refs.push(ItemRef {
+                    field,
+                    id: entity.resolve(name, ctx)?,
+                });
+            }
+        }
+        let fields = resolve_fields(meta, ctx)?;
+        Ok(Some(ItemPatch {
+            fields,
+            refs,
+            name: meta.item_name.clone(),
+            timeout: Duration::from_secs(meta.item_timeout),
+            skip_duplicates: meta.skip_duplicates,
+        }))
+    }
+
+    /// Must be called before uploading the files to remember items
+    /// that already contain them.
+    pub fn prepare_files(&self, files: &[&Path], ctx: &Context) -> Result<Pending<'_>, Error> {
+        let mut hashes = Vec::with_capacity(files.len());
+        for path in files {
+            let hash = digest::digest_file_sha256(path).context(DigestFile { path: *path })?;
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            hashes.push((hash, name));
+        }
+        self.prepare(hashes, ctx)
+    }
+
+    /// Like [`ItemPatch::prepare_files`] given the sha256 checksums
+    /// together with the file names.
+    pub fn prepare(
+        &self,
+        hashes: Vec<(String, String)>,
+        ctx: &Context,
+    ) -> Result<Pending<'_>, Error> {
+        let mut files = Vec::with_capacity(hashes.len());
+        for (hash, name) in hashes {
+            let known_items = find_items(&hash, ctx)?
+                .into_iter()
+                .map(|item| item.id)
+                .collect();
+            files.push(PendingFile {
+                hash,
+                name,
+                known_items,
+            });
+        }
+        Ok(Pending { patch: self, files })
+    }
+}
+
+/// Finds the custom fields by name or id and checks the values.
+fn resolve_fields(meta: &UploadMeta, ctx: &Context) -> Result<Vec<CustomFieldValue>, Error> {
+    if meta.custom_field.is_empty() {
+        return Ok(vec![]);
+    }
+    let defs = ctx
+        .client
+        .list_custom_fields(&ctx.opts.session, "")
+        .context(HttpClient)?
+        .items;
+    let mut fields = Vec::new();
+    for nv in &meta.custom_field {
+        let def = defs
+            .iter()
+            .find(|d| d.name == nv.name || d.id == nv.name)
+            .ok_or_else(|| Error::UnknownField {
+                name: nv.name.clone(),
+            })?;
+        check_value(def, &nv.value)?;
+        fields.push(CustomFieldValue {
+            field: def.id.clone(),
+            value: nv.value.clone(),
+        });
+    }
+    Ok(fields)
+}
+
+impl Pending<'_> {
+    /// Waits for the items of all files and changes them.
+    pub fn apply(self, ctx: &Context) -> Result<(), Error> {
+        let start = Instant::now();
+        let mut items: Vec<(String, &PendingFile)> = Vec::new();
+        for file in &self.files {
+            for id in self.wait_for_items(file, start, ctx)? {
+                if !items.iter().any(|(known, _)| known == &id) {
+                    items.push((id, file));
+                }
+            }
+        }
+        let token = &ctx.opts.session;
+        for (id, file) in &items {
+            log::debug!("Changing uploaded item {}", id);
+            if let Some(template) = &self.patch.name {
+                let name = item_name(template, &file.name);
+                let result = ctx.client.set_item_name(token, id, &name);
+                check_result("name", result)?;
+            }
+            for r in &self.patch.refs {
+                let result = ctx.client.set_item_ref(token, id, r.field, &r.id);
+                check_result(r.field, result)?;
+            }
+            for fvalue in &self.patch.fields {
+                let result = ctx.client.set_field(token, id, fvalue);
+                check_result(&fvalue.field, result)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn wait_for_items(
+        &self,
+        file: &PendingFile,
+        start: Instant,
+        ctx: &Context,
+    ) -> Result<Vec<String>, Error> {
+        if self.patch.skip_duplicates && !file.known_items.is_empty() {
+            log::debug!("File {} is a duplicate, using the existing item", file.hash);
+            return Ok(file.known_items.clone());
+        }
+        loop {
+            let ids: Vec<String> = find_items(&file.hash, ctx)?
+                .into_iter()
+                .filter(|item| is_processed(item) && !file.known_items.contains(&item.id))
+                .map(|item| item.id)
+                .collect();
+            if !ids.is_empty() {
+                return Ok(ids);
+            }
+            if start.elapsed() >= self.patch.timeout {
+                if file.known_items.is_empty() {
+                    return Err(Error::ItemTimeout {
+                        secs: self.patch.timeout.as_secs(),
+                    });
+                }
+                log::warn!(
+                    "No new item was created for {}, changing the existing item(s) instead",
+                    file.name
+                );
+                return Ok(file.known_items.clone());
+            }
+            log::debug!("Waiting for the item of file {}", file.hash);
+            std::thread::sleep(POLL_DELAY);
+        }
+    }
+}
+
+/// Whether the item name uses a placeholder for the file name, so
+/// that it can be used for many items.
+pub fn is_name_template(template: &str) -> bool {
+    template.contains("{file}") || template.contains("{stem}")
+}
+
+fn item_name(template: &str, file: &str) -> String {
+    let stem = Path::new(file)
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    template.replace("{file}", file).replace("{stem}", &stem)
+}
+
+fn check_result(name: &str, result: Result<BasicResult, HttpError>) -> Result<(), Error> {
+    let result = result.context(HttpClient)?;
+    if result.success {
+        Ok(())
+    } else {
+        Err(Error::UpdateFailed {
+            name: name.to_string(),
+            message: result.message,
+        })
+    }
+}
+
+fn find_items(hash: &str, ctx: &Context) -> Result<Vec<ItemShort>, Error> {
+    let fauth = FileAuth::Session {
+        token: ctx.opts.session.clone(),
+    };
+    let result = ctx.client.file_exists(hash, &fauth).context(HttpClient)?;
+    Ok(result.items)
+}
+
+/// Items are changed only after processing, otherwise the values
+/// could be replaced.
+fn is_processed(item: &ItemShort) -> bool {
+    item.state != "premature" && item.state != "processing"
+}
+
+/// Checks the value against the type of the field, where possible.
+fn check_value(def: &CustomFieldDef, value: &str) -> Result<(), Error> {
+    let reason = match def.ftype.as_str() {
+        "numeric" | "money" => value.parse::<f64>().err().map(|e| e.to_string()),
+        "bool" => match value {
+            "true" | "false" => None,
+            _ => Some("expected true or false".to_string()),
+        },
+        "date" => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .err()
+            .map(|e| format!("expected yyyy-mm-dd, {}", e)),
+        _ => None,
+    };
+    match reason {
+        Some(reason) => Err(Error::InvalidValue {
+            name: def.name.clone(),
+            ftype: def.ftype.clone(),
+            value: value.to_string(),
+            reason,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(ftype: &str) -> CustomFieldDef {
+        CustomFieldDef {
+            id: "id".into(),
+            name: "f".into(),
+            label: None,
+            ftype: ftype.into(),
+        }
+    }
+
+    #[test]
+    fn unit_item_name() {
+        assert_eq!(
+            item_name("Scan {stem}", "2021-03 bill.pdf"),
+            "Scan 2021-03 bill"
+        );
+        assert_eq!(item_name("{file}", "a.pdf"), "a.pdf");
+        assert_eq!(item_name("Contract", "a.pdf"), "Contract");
+        assert!(is_name_template("x {stem}"));
+        assert!(!is_name_template("Contract"));
+    }
+
+    #[test]
+    fn unit_check_value() {
+        assert!(check_value(&field("money"), "12.50").is_ok());
+        assert!(check_value(&field("numeric"), "abc").is_err());
+        assert!(check_value(&field("bool"), "true").is_ok());
+        assert!(check_value(&field("bool"), "yes").is_err());
+        assert!(check_value(&field("date"), "2021-02-30").is_err());
+        assert!(check_value(&field("date"), "2021-02-28").is_ok());
+        assert!(check_value(&field("text"), "anything").is_ok());
+    }
+}
@@ -0,0 +1,174 @@
+//! Metadata for a single file, read from a json file next to it.
+//!
+//! For a file `invoice.pdf`, the sidecar is `invoice.pdf.meta.json`
+//! or `invoice.pdf.json`, where the first one is preferred. It may
+//! contain these keys, all of them are optional:
+//!
+//! ```json
+//! {
+//!   "tags": ["Invoice", "Todo"],
+//!   "folder": "Taxes",
+//!   "direction": "incoming",
+//!   "language": "deu",
+//!   "customFields": { "amount": 12.50 }
+//! }
+//! ```
+//!
+//! Values from the sidecar are more specific than the options and
+//! take precedence: `folder`, `direction` and `language` replace the
+//! options, tags are added to the ones given via `--tag` and custom
+//! fields replace the ones of the same name. Other keys are ignored.
+
+use serde::Deserialize;
+use serde_json::Value;
+use snafu::{ResultExt, Snafu};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::cli::opts::{Direction, NameVal, UploadMeta};
+
+const EXTENSIONS: [&str; 2] = [".meta.json", ".json"];
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unable to read sidecar file {}: {}", path.display(), source))]
+    ReadSidecar {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("The sidecar file {} is invalid: {}", path.display(), source))]
+    ParseSidecar {
+        source: serde_json::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Invalid direction '{}' in sidecar file {}", value, path.display()))]
+    InvalidDirection { value: String, path: PathBuf },
+}
+
+/// The metadata of a sidecar file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Sidecar {
+    #[serde(default)]
+    tags: Vec<String>,
+    folder: Option<String>,
+    direction: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    custom_fields: BTreeMap<String, Value>,
+}
+
+impl Sidecar {
+    /// Returns the sidecar file of `file`, if it exists.
+    pub fn find(file: &Path) -> Option<PathBuf> {
+        EXTENSIONS
+            .iter()
+            .map(|ext| append(file, ext))
+            .find(|p| p.is_file())
+    }
+
+    /// Whether `file` is the sidecar of another existing file.
+    pub fn is_sidecar(file: &Path) -> bool {
+        let name = file.to_string_lossy();
+        EXTENSIONS.iter().any(|ext| {
+            name.strip_suffix(ext)
+                .map(|base| !base.is_empty() && Path::new(base).is_file())
+                .unwrap_or(false)
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Sidecar, Error> {
+        let data = std::fs::read(path).context(ReadSidecar { path })?;
+        serde_json::from_slice(&data).context(ParseSidecar { path })
+    }
+
+    /// Returns the upload metadata with the values of this sidecar
+    /// applied to `meta`. The `path` is only used for error messages.
+    pub fn apply(&self, meta: &UploadMeta, path: &Path) -> Result<UploadMeta, Error> {
+        let mut result = meta.clone();
+        if let Some(folder) = &self.folder {
+            result.folder = Some(folder.clone());
+        }
+        if let Some(dir) = &self.direction {
+            result.direction =
+                Some(parse_direction(dir).ok_or_else(|| Error::InvalidDirection {
+                    value: dir.clone(),
+                    path: path.to_path_buf(),
+                })?);
+        }
+        if let Some(lang) = &self.language {
+            result.language = Some(lang.clone());
+        }
+        for tag in &self.tags {
+            if !result.tag.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                result.tag.push(tag.clone());
+            }
+        }
+        for (name, value) in &self.custom_fields {
+            result.custom_field.retain(|nv| &nv.name != name);
+            result.custom_field.push(NameVal {
+                name: name.clone(),
+                value: match value {
+                    Value::String(s) => s.clone(),
+                    v => v.to_string(),
+                },
+            });
+        }
+        Ok(result)
+    }
+}
+
+fn append(file: &Path, ext: &str) -> PathBuf {
+    let mut name = file.as_os_str().to_os_string();
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+fn parse_direction(s: &str) -> Option<Direction> {
+    match s.to_lowercase().as_str() {
+        "in" | "incoming" => Some(Direction::In),
+        "out" | "outgoing" => Some(Direction::Out),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Clap;
+
+    #[test]
+    fn unit_apply_sidecar() {
+        let meta = UploadMeta::parse_from([
+            "upload",
+            "--folder",
+            "Inbox",
+            "--language",
+            "eng",
+            "--tag",
+            "Scan",
+            "--custom-field",
+            "amount=1",
+            "--custom-field",
+            "shop=Acme",
+        ]);
+        let sidecar: Sidecar = serde_json::from_str(
+            r#"{"tags": ["Invoice", "scan"], "folder": "Taxes", "direction": "in",
+                "customFields": {"amount": 12.5}, "date": "2021-05-01"}"#,
+        )
+        .unwrap();
+        let result = sidecar.apply(&meta, Path::new("a.pdf.json")).unwrap();
+        assert_eq!(result.folder.as_deref(), Some("Taxes"));
+        assert_eq!(result.language.as_deref(), Some("eng"));
+        assert_eq!(result.direction.map(|d| d.to_value()), Some("incoming"));
+        assert_eq!(result.tag, vec!["Scan", "Invoice"]);
+        let fields: Vec<(&str, &str)> = result
+            .custom_field
+            .iter()
+            .map(|nv| (nv.name.as_str(), nv.value.as_str()))
+            .collect();
+        assert_eq!(fields, vec![("shop", "Acme"), ("amount", "12.5")]);
+    }
+}
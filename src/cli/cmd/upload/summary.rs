@@ -0,0 +1,161 @@
+//! Counting the outcome of each file of an upload.
+//!
+//! At the end of an upload of more than one file, a summary is
+//! printed to stderr: how many files have been uploaded, how many
+//! were skipped as duplicates, which ones failed and why, together
+//! with the number of uploaded bytes and the elapsed time.
+//!
+//! Each outcome is also emitted as an event, if `--events` is given.
+
+use indicatif::{HumanBytes, HumanDuration};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::cli::opts::Format;
+use crate::util::events::{self, Event, Outcome};
+
+/// The outcomes collected so far, which can be shared by multiple
+/// upload threads.
+#[derive(Debug)]
+pub struct Summary {
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    started: Instant,
+    report: Report,
+}
+
+/// The summary of an upload run.
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub uploaded: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub total_bytes: u64,
+    pub elapsed_ms: u64,
+    pub failures: Vec<FailedFile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailedFile {
+    pub file: PathBuf,
+    pub error: String,
+}
+
+impl Default for Summary {
+    fn default() -> Summary {
+        Summary {
+            state: Mutex::new(State {
+                started: Instant::now(),
+                report: Report::default(),
+            }),
+        }
+    }
+}
+
+impl Summary {
+    pub fn uploaded(&self, file: &Path) {
+        let size = std::fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+        self.add_uploaded(size);
+        events::emit(&Event::FileDone {
+            file: &file.display().to_string(),
+            outcome: Outcome::Uploaded,
+            bytes: size,
+        });
+    }
+
+    /// Counts an uploaded file of `size` bytes.
+    pub fn add_uploaded(&self, size: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.report.uploaded += 1;
+        state.report.total_bytes += size;
+    }
+
+    pub fn skipped(&self, file: &Path) {
+        self.state.lock().unwrap().report.skipped += 1;
+        events::emit(&Event::FileDone {
+            file: &file.display().to_string(),
+            outcome: Outcome::Skipped,
+            bytes: 0,
+        });
+    }
+
+    pub fn failed(&self, file: &Path, error: String) {
+        events::emit(&Event::FileError {
+            file: &file.display().to_string(),
+            error: &error,
+        });
+        let mut state = self.state.lock().unwrap();
+        state.report.failed += 1;
+        state.report.failures.push(FailedFile {
+            file: file.to_path_buf(),
+            error,
+        });
+    }
+
+    /// Returns the report of all files counted so far and starts
+    /// anew.
+    pub fn take_report(&self) -> Report {
+        let mut state = self.state.lock().unwrap();
+        let elapsed = state.started.elapsed();
+        state.started = Instant::now();
+        let mut report = std::mem::take(&mut state.report);
+        report.elapsed_ms = elapsed.as_millis() as u64;
+        report
+    }
+}
+
+impl Report {
+    /// The number of files that have been counted.
+    pub fn files(&self) -> usize {
+        self.uploaded + self.skipped + self.failed
+    }
+
+    /// Prints the report to stderr, as a json object for the json
+    /// formats and as text otherwise.
+    pub fn print(&self, format: Format) {
+        match format {
+            Format::Json | Format::Ndjson => match serde_json::to_string(self) {
+                Ok(json) => eprintln!("{}", json),
+                Err(err) => log::error!("Cannot serialize upload summary: {}", err),
+            },
+            _ => {
+                eprintln!(
+                    "Summary: {} uploaded, {} duplicate(s) skipped, {} failed, {} in {}",
+                    self.uploaded,
+                    self.skipped,
+                    self.failed,
+                    HumanBytes(self.total_bytes),
+                    HumanDuration(Duration::from_millis(self.elapsed_ms))
+                );
+                for f in &self.failures {
+                    eprintln!("Failed: {}: {}", f.file.display(), f.error);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_summary_counts_outcomes() {
+        let summary = Summary::default();
+        summary.add_uploaded(100);
+        summary.add_uploaded(20);
+        summary.skipped(Path::new("b.pdf"));
+        summary.failed(Path::new("a.pdf"), "rejected".into());
+        let report = summary.take_report();
+        assert_eq!(report.files(), 4);
+        assert_eq!(report.uploaded, 2);
+        assert_eq!(report.total_bytes, 120);
+        assert_eq!(report.failures[0].file, PathBuf::from("a.pdf"));
+        assert_eq!(summary.take_report().files(), 0);
+    }
+}
@@ -1,11 +1,15 @@
 use crate::cli::sink::Sink;
 use crate::cli::table::AsTable;
 use crate::http::payload::{BuildInfo, VersionInfo};
-use crate::http::Error as HttpError;
+use crate::http::{server_file_name, Error as HttpError};
+use crate::status;
 use clap::Clap;
-use prettytable::{cell, row, Table};
-use serde::Serialize;
+use prettytable::{row, Table};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use super::{Cmd, Context};
 use crate::cli::sink::Error as SinkError;
@@ -13,7 +17,9 @@ use crate::cli::sink::Error as SinkError;
 /// Prints version about server and client.
 ///
 /// Queries the server for its version information and prints more
-/// version details about this client.
+/// version details about this client. It also tells whether the
+/// server version is the one this client is developed against. A
+/// warning is printed, if it is known to be incompatible.
 #[derive(Clap, Debug, PartialEq)]
 pub struct Input {}
 
@@ -32,27 +38,184 @@ impl Cmd for Input {
     fn exec(&self, ctx: &Context) -> Result<(), Error> {
         let result = ctx.client.version().context(HttpClient)?;
         let vinfo = AllVersion::default(result, ctx.base_url());
+        if vinfo.compatibility == Compatibility::Incompatible {
+            status!(
+                "WARNING: The server version {} is not compatible with this client, \
+                 which is made for docspell {}.",
+                vinfo.server.version,
+                DOCSPELL_VERSION
+            );
+        }
         ctx.write_result(vinfo).context(WriteResult)?;
         Ok(())
     }
 }
 
-#[derive(Debug, Serialize)]
+/// The docspell version this client is developed against.
+pub const DOCSPELL_VERSION: &str = "0.26.0";
+
+/// The version of the docspell api used for all requests.
+pub const API_VERSION: &str = "v1";
+
+/// Servers older than this lack endpoints that are used by many
+/// commands.
+const MIN_DOCSPELL_VERSION: (u64, u64) = (0, 24);
+
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct AllVersion {
     pub client: BuildInfo,
     pub server: VersionInfo,
     pub docspell_url: String,
+    pub api_version: &'static str,
+    pub built_for: &'static str,
+    pub compatibility: Compatibility,
 }
 impl AllVersion {
     pub fn default(server: VersionInfo, docspell_url: String) -> AllVersion {
         AllVersion {
             client: BuildInfo::default(),
+            compatibility: Compatibility::of(&server.version),
             server,
             docspell_url,
+            api_version: API_VERSION,
+            built_for: DOCSPELL_VERSION,
         }
     }
 }
 
+/// How the server version relates to [`DOCSPELL_VERSION`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Compatibility {
+    /// Major and minor version are the same.
+    Same,
+    /// The minor version differs, some commands may not work.
+    Different,
+    /// The major version differs or the server is too old.
+    Incompatible,
+    /// The server version cannot be parsed.
+    Unknown,
+}
+
+impl Compatibility {
+    pub fn of(server_version: &str) -> Compatibility {
+        match (major_minor(server_version), major_minor(DOCSPELL_VERSION)) {
+            (Some(server), Some(client)) => {
+                if server.0 != client.0 || server < MIN_DOCSPELL_VERSION {
+                    Compatibility::Incompatible
+                } else if server.1 != client.1 {
+                    Compatibility::Different
+                } else {
+                    Compatibility::Same
+                }
+            }
+            _ => Compatibility::Unknown,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compatibility::Same => "same",
+            Compatibility::Different => "different",
+            Compatibility::Incompatible => "incompatible",
+            Compatibility::Unknown => "unknown",
+        }
+    }
+}
+
+/// How long the server version is remembered, before it is asked
+/// again.
+const VERSION_CACHE_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// The server version as remembered in the cache file.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedVersion {
+    version: String,
+    checked_ms: u64,
+}
+
+/// Prints a warning to stderr, if the major or minor version of the
+/// server differs from [`DOCSPELL_VERSION`]. This is disabled via
+/// `suppress_version_warning` in the config file.
+///
+/// The server version is remembered for a day beside the session
+/// file, so the server is asked and the warning printed only once
+/// during this time. Any error is only logged, the command runs
+/// regardless.
+pub fn warn_version_mismatch(ctx: &Context) {
+    if ctx.cfg.suppress_version_warning {
+        return;
+    }
+    let file = version_cache_file(&ctx.base_url());
+    let now = now_ms();
+    let cached = file
+        .as_ref()
+        .and_then(|f| std::fs::read_to_string(f).ok())
+        .and_then(|cnt| serde_json::from_str::<CachedVersion>(&cnt).ok());
+    if let Some(c) = cached {
+        if now.saturating_sub(c.checked_ms) < VERSION_CACHE_MS {
+            log::debug!("Server version {} checked recently", c.version);
+            return;
+        }
+    }
+
+    let version = match ctx.client.version() {
+        Ok(info) => info.version,
+        Err(err) => {
+            log::debug!("Cannot check the server version: {}", err);
+            return;
+        }
+    };
+    if let Some(f) = &file {
+        let entry = CachedVersion {
+            version: version.clone(),
+            checked_ms: now,
+        };
+        let written = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|cnt| std::fs::write(f, cnt).map_err(|e| e.to_string()));
+        if let Err(err) = written {
+            log::debug!("Cannot remember the server version: {}", err);
+        }
+    }
+    match Compatibility::of(&version) {
+        Compatibility::Different | Compatibility::Incompatible => status!(
+            "WARNING: The server runs docspell {}, but this client is made for {}. \
+             Some commands may not work.",
+            version,
+            DOCSPELL_VERSION
+        ),
+        Compatibility::Same | Compatibility::Unknown => {}
+    }
+}
+
+fn version_cache_file(base_url: &str) -> Option<PathBuf> {
+    let dir = dirs::config_dir()?.join("dsc");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(server_file_name("dsc-server-version", base_url)))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Parses the major and minor part of versions like `0.26.0`,
+/// `v0.26.0` or `0.27.0-SNAPSHOT`.
+fn major_minor(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.trim().trim_start_matches('v').split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts
+        .next()?
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    Some((major, minor))
+}
+
 impl AsTable for AllVersion {
     fn to_table(&self) -> Table {
         let mut table = Table::new();
@@ -61,9 +224,34 @@ impl AsTable for AllVersion {
         ct.set_titles(row!["Client (dsc)", ""]);
         let mut st = self.server.to_table();
         st.set_titles(row!["Docspell Server", self.docspell_url]);
+        let mut vt = Table::new();
+        vt.set_format(*ct.get_format());
+        vt.set_titles(row!["Compatibility", ""]);
+        vt.add_row(row!["api version", self.api_version]);
+        vt.add_row(row!["made for docspell", self.built_for]);
+        vt.add_row(row!["server version", self.compatibility.name()]);
         table.add_row(row![st]);
         table.add_row(row![ct]);
+        table.add_row(row![vt]);
         table
     }
 }
 impl Sink for AllVersion {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_compatibility_of_versions() {
+        assert_eq!(Compatibility::of("0.26.0"), Compatibility::Same);
+        assert_eq!(Compatibility::of("v0.26.3"), Compatibility::Same);
+        assert_eq!(
+            Compatibility::of("0.27.0-SNAPSHOT"),
+            Compatibility::Different
+        );
+        assert_eq!(Compatibility::of("0.20.0"), Compatibility::Incompatible);
+        assert_eq!(Compatibility::of("1.0.0"), Compatibility::Incompatible);
+        assert_eq!(Compatibility::of("nightly"), Compatibility::Unknown);
+    }
+}
@@ -11,6 +11,7 @@ use crate::cli::opts::SearchMode;
 use crate::http::payload::SearchReq;
 use crate::http::DownloadRef;
 use crate::http::Error as HttpError;
+use crate::status;
 
 /// View pdf files.
 ///
@@ -99,7 +100,7 @@ pub fn view_all(opts: &Input, ctx: &Context, parent: &Path) -> Result<(), Error>
 
         let file = download(&dref, ctx, parent)?;
         if let Some(f) = file {
-            let tool = &ctx.cfg.pdf_viewer.get(0).ok_or(Error::NoPdfViewer)?;
+            let tool = &ctx.cfg.pdf_viewer.first().ok_or(Error::NoPdfViewer)?;
             let tool_args: Vec<String> = ctx
                 .cfg
                 .pdf_viewer
@@ -118,9 +119,10 @@ pub fn view_all(opts: &Input, ctx: &Context, parent: &Path) -> Result<(), Error>
             );
             Command::new(tool).args(tool_args).output().context(Exec)?;
         } else {
-            eprintln!(
+            status!(
                 "Skip attachment: {}/{}. There was no file!",
-                dref.id, dref.name
+                dref.id,
+                dref.name
             );
         }
     }
@@ -7,11 +7,16 @@ use std::{
     time::Duration,
 };
 
+use super::upload::matching::Filter;
 use super::{upload, Cmd, Context};
-use crate::cli::opts::{EndpointOpts, FileAction, UploadMeta};
+use crate::cli::opts::{AfterUpload, CacheOpts, EndpointOpts, FileAction, UploadMeta};
 use crate::http::payload::BasicResult;
+use state::{Entry, WatchState};
 
-use crate::util::file;
+use crate::status;
+use crate::util::{events, file, shutdown};
+
+pub mod state;
 
 /// Watches a directory and uploads files to docspell.
 ///
@@ -27,7 +32,9 @@ use crate::util::file;
 /// then in combination with the `--poll` option.
 #[derive(Clap, Debug)]
 pub struct Input {
-    /// Wether to watch directories recursively or not.
+    /// Wether to watch directories recursively or not. New
+    /// subdirectories are watched, too, and files in directories
+    /// moved into the watched tree are uploaded.
     #[clap(long, short)]
     pub recursive: bool,
 
@@ -35,12 +42,53 @@ pub struct Input {
     #[clap(long = "delay", default_value = "6")]
     pub delay_secs: u64,
 
+    /// Wait until a file didn't change for this many milliseconds
+    /// before uploading it. Additionally, its size must stay the same
+    /// over this period. This helps with slow writers, like scanners
+    /// writing to a network share. If given, it is used instead of
+    /// `--delay`.
+    #[clap(long)]
+    pub debounce: Option<u64>,
+
+    /// Upload all matching files that are already present in the
+    /// directories when starting, then continue watching. Files
+    /// that are known to the server are skipped as usual, which is
+    /// useful when restarting after files accumulated in the
+    /// meantime.
+    #[clap(long)]
+    pub initial_scan: bool,
+
+    /// Record each successfully uploaded file with its modification
+    /// time and hash in this file. On restart, files found in it are
+    /// skipped, unless they have changed. This is most useful with
+    /// `--initial-scan` for long running deployments.
+    #[clap(long, value_hint = ValueHint::FilePath)]
+    pub state_file: Option<PathBuf>,
+
+    /// On SIGINT or SIGTERM, no more files are uploaded and `watch`
+    /// exits once the current upload is done. If this takes longer
+    /// than this many seconds, it quits anyways. A second Ctrl-C quits
+    /// immediately.
+    #[clap(long, default_value = "30")]
+    pub shutdown_timeout: u64,
+
     #[clap(flatten)]
     pub upload: UploadMeta,
 
     #[clap(flatten)]
     pub action: FileAction,
 
+    #[clap(flatten)]
+    pub cache: CacheOpts,
+
+    /// What to do with a file after the server accepted it: `keep`,
+    /// `delete` or `move:DIR`. When moving, a number is added to the
+    /// file name if the target exists. Files that failed to upload
+    /// are never touched. This is an alternative to `--delete` and
+    /// `--move`.
+    #[clap(long, conflicts_with_all = &["delete", "move-to"])]
+    pub after_upload: Option<AfterUpload>,
+
     /// A glob pattern for matching against each file. Note that
     /// usually you can just use the shells expansion mechanism.
     #[clap(long, short, default_value = "**/*")]
@@ -51,6 +99,24 @@ pub struct Input {
     #[clap(long, short)]
     pub not_matches: Option<String>,
 
+    /// A glob pattern that files must match to be uploaded, for
+    /// example `**/*.pdf`. It is matched against the path relative to
+    /// the watched directory. It can be given multiple times and a
+    /// file is included if it matches any of the patterns.
+    #[clap(long, multiple_occurrences = true, number_of_values = 1)]
+    pub include: Vec<String>,
+
+    /// A glob pattern to skip files, for example `**/*.tmp`. It is
+    /// matched against the path relative to the watched directory and
+    /// can be given multiple times.
+    #[clap(long, multiple_occurrences = true, number_of_values = 1)]
+    pub exclude: Vec<String>,
+
+    /// Match the `--include` and `--exclude` patterns case
+    /// sensitive.
+    #[clap(long)]
+    pub case_sensitive: bool,
+
     /// Don't upload anything, but print what would be uploaded.
     #[clap(long)]
     pub dry_run: bool,
@@ -88,6 +154,12 @@ pub enum Error {
 
     #[snafu(display("Could not find a collective for {}", path.display()))]
     NoCollective { path: PathBuf },
+
+    #[snafu(display("{}", source))]
+    State { source: state::Error },
+
+    #[snafu(display("Error installing the signal handler: {}", source))]
+    Signal { source: ctrlc::Error },
 }
 
 impl Cmd for Input {
@@ -101,6 +173,13 @@ impl Cmd for Input {
 
 pub fn watch_directories(opts: &Input, ctx: &Context) -> Result<(), Error> {
     check_is_dir(&opts.dirs)?;
+    shutdown::install(Duration::from_secs(opts.shutdown_timeout)).context(Signal)?;
+    upload::check_item_name(&opts.upload, true).context(Upload)?;
+    let filter = Filter::new(&opts.include, &opts.exclude, opts.case_sensitive).context(Upload)?;
+    let state = match &opts.state_file {
+        Some(file) => Some(WatchState::load(file).context(State)?),
+        None => None,
+    };
     let mode = if opts.recursive {
         RecursiveMode::Recursive
     } else {
@@ -108,18 +187,32 @@ pub fn watch_directories(opts: &Input, ctx: &Context) -> Result<(), Error> {
     };
     let (tx, rx) = mpsc::channel();
 
-    let mut watcher = notify::watcher(tx, Duration::from_secs(opts.delay_secs)).context(Watch)?;
+    let delay = match opts.debounce {
+        Some(millis) => Duration::from_millis(millis),
+        None => Duration::from_secs(opts.delay_secs),
+    };
+    let mut watcher = notify::watcher(tx, delay).context(Watch)?;
     for dir in &opts.dirs {
-        eprintln!("Watching directory ({:?}): {}", mode, dir.display());
+        status!("Watching directory ({:?}): {}", mode, dir.display());
         watcher.watch(dir, mode).context(Watch)?;
     }
-    eprintln!("Press Ctrl-C to quit.");
-    loop {
-        match rx.recv() {
-            Ok(event) => event_act(event, opts, ctx)?,
-            Err(e) => return Err(Error::Event { source: e }),
+    if opts.initial_scan {
+        initial_scan(opts, &filter, state.as_ref(), ctx)?;
+    }
+    status!("Press Ctrl-C to quit.");
+    while !shutdown::is_requested() {
+        match rx.recv_timeout(Duration::from_millis(250)) {
+            Ok(event) => event_act(event, opts, &filter, state.as_ref(), ctx)?,
+            Err(mpsc::RecvTimeoutError::Timeout) => (),
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(Error::Event {
+                    source: mpsc::RecvError,
+                })
+            }
         }
     }
+    status!("Stopped watching.");
+    Ok(())
 }
 
 fn check_is_dir(dirs: &[PathBuf]) -> Result<(), Error> {
@@ -131,12 +224,32 @@ fn check_is_dir(dirs: &[PathBuf]) -> Result<(), Error> {
     Ok(())
 }
 
-fn event_act(event: DebouncedEvent, opts: &Input, ctx: &Context) -> Result<(), Error> {
+fn event_act(
+    event: DebouncedEvent,
+    opts: &Input,
+    filter: &Filter,
+    state: Option<&WatchState>,
+    ctx: &Context,
+) -> Result<(), Error> {
     log::info!("Event: {:?}", event);
     match event {
-        DebouncedEvent::Create(path) => upload_and_report(path, opts, ctx)?,
-        DebouncedEvent::Write(path) => upload_and_report(path, opts, ctx)?,
-        DebouncedEvent::Chmod(path) => upload_and_report(path, opts, ctx)?,
+        DebouncedEvent::Create(path) => upload_path(path, opts, filter, state, ctx)?,
+        DebouncedEvent::Write(path) => upload_path(path, opts, filter, state, ctx)?,
+        DebouncedEvent::Chmod(path) => upload_path(path, opts, filter, state, ctx)?,
+        DebouncedEvent::Rename(src, dst) => {
+            // If the old name was accepted, the file has been uploaded
+            // already. Otherwise, like for temporary files, it is new.
+            // The old path is gone, so only the new one can be checked.
+            if !dst.is_dir() && is_accepted(&src, opts, filter) {
+                log::info!(
+                    "Not uploading renamed file again: {} -> {}",
+                    src.display(),
+                    dst.display()
+                );
+            } else {
+                upload_path(dst, opts, filter, state, ctx)?;
+            }
+        }
         DebouncedEvent::Error(err, path_opt) => {
             log::error!("Debounce event error for path {:?}: {}", path_opt, err);
             return Err(Error::Watch { source: err });
@@ -146,23 +259,176 @@ fn event_act(event: DebouncedEvent, opts: &Input, ctx: &Context) -> Result<(), E
     Ok(())
 }
 
-fn upload_and_report(path: PathBuf, opts: &Input, ctx: &Context) -> Result<(), Error> {
-    eprintln!("------------------------------------------------------------------------------");
-    eprintln!("Got: {}", path.display());
+/// Uploads the file if it matches the filter. For directories, all
+/// files below are uploaded in recursive mode.
+fn upload_path(
+    path: PathBuf,
+    opts: &Input,
+    filter: &Filter,
+    state: Option<&WatchState>,
+    ctx: &Context,
+) -> Result<(), Error> {
+    if path.is_dir() {
+        if opts.recursive {
+            for file in files_below(&path) {
+                if shutdown::is_requested() {
+                    break;
+                }
+                upload_path(file, opts, filter, state, ctx)?;
+            }
+        }
+        Ok(())
+    } else if is_accepted(&path, opts, filter) {
+        upload_and_report(path, opts, state, ctx)
+    } else {
+        log::debug!("Skipping {}, excluded by patterns", path.display());
+        Ok(())
+    }
+}
+
+/// Uploads the files already present in the watched directories. It
+/// runs after the watches are set up, so files arriving meanwhile are
+/// not missed.
+fn initial_scan(
+    opts: &Input,
+    filter: &Filter,
+    state: Option<&WatchState>,
+    ctx: &Context,
+) -> Result<(), Error> {
+    for dir in &opts.dirs {
+        status!("Scanning existing files in: {}", dir.display());
+        let files = if opts.recursive {
+            files_below(dir)
+        } else {
+            files_in(dir)
+        };
+        for file in files {
+            if shutdown::is_requested() {
+                return Ok(());
+            }
+            upload_path(file, opts, filter, state, ctx)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks the path, relative to the watched directory, against the
+/// `--include` and `--exclude` patterns.
+fn is_accepted(path: &Path, opts: &Input, filter: &Filter) -> bool {
+    let rel = opts
+        .dirs
+        .iter()
+        .find_map(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path);
+    filter.accepts(rel)
+}
+
+/// Lists all files below the given directory, recursively.
+fn files_below(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    match std::fs::read_dir(dir) {
+        Ok(entries) => {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    files.extend(files_below(&path));
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+        Err(err) => log::error!("Cannot read directory {}: {}", dir.display(), err),
+    }
+    files.sort();
+    files
+}
+
+/// Lists the files directly inside the given directory.
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect(),
+        Err(err) => {
+            log::error!("Cannot read directory {}: {}", dir.display(), err);
+            Vec::new()
+        }
+    };
+    files.sort();
+    files
+}
+
+fn upload_and_report(
+    path: PathBuf,
+    opts: &Input,
+    state: Option<&WatchState>,
+    ctx: &Context,
+) -> Result<(), Error> {
+    if shutdown::is_requested() {
+        return Ok(());
+    }
+    status!("------------------------------------------------------------------------------");
+    status!("Got: {}", path.display());
+    if let Some(millis) = opts.debounce {
+        if !wait_until_stable(&path, Duration::from_millis(millis)) {
+            status!("File is gone, skipping: {}", path.display());
+            return Ok(());
+        }
+    }
+    // The entry is created before uploading, because the file may be
+    // moved or deleted afterwards.
+    let entry = match state {
+        Some(st) => {
+            let entry = Entry::of(&path).context(DigestFile { path: &path })?;
+            if st.contains(&entry) {
+                status!("Uploaded before, skipping: {}", path.display());
+                return Ok(());
+            }
+            Some(entry)
+        }
+        None => None,
+    };
     let result = upload_file(path, opts, ctx)?;
     if result.success {
         if opts.dry_run {
-            eprintln!("Dry run. Would upload now.");
+            status!("Dry run. Would upload now.");
         } else {
-            eprintln!("Server: {}", result.message);
+            status!("Server: {}", result.message);
+            if let (Some(st), Some(entry)) = (state, entry) {
+                st.record(entry).context(State)?;
+            }
         }
     } else {
         log::error!("Error from uploading: {}", result.message);
-        eprintln!("Sevrer Error: {}", result.message);
+        events::warn(&format!("Sevrer Error: {}", result.message));
     }
     Ok(())
 }
 
+/// Waits until size and modification time of the file are the same
+/// in two consecutive checks. Returns `false` if the file doesn't
+/// exist anymore.
+fn wait_until_stable(path: &Path, interval: Duration) -> bool {
+    let state = |p: &Path| std::fs::metadata(p).map(|m| (m.len(), m.modified().ok()));
+    let mut last = match state(path) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    loop {
+        std::thread::sleep(interval);
+        match state(path) {
+            Ok(current) if current == last => return true,
+            Ok(current) => {
+                log::debug!("File {} is still changing, waiting", path.display());
+                last = current;
+            }
+            Err(_) => return false,
+        }
+    }
+}
+
 fn upload_file(path: PathBuf, opts: &Input, ctx: &Context) -> Result<BasicResult, Error> {
     let mut ep = opts.endpoint.clone();
     if let Some(cid) = find_collective(&path, &opts.dirs, &opts.endpoint)? {
@@ -172,13 +438,26 @@ fn upload_file(path: PathBuf, opts: &Input, ctx: &Context) -> Result<BasicResult
     let data = &upload::Input {
         endpoint: ep,
         multiple: true,
-        action: opts.action.clone(),
+        action: match &opts.after_upload {
+            Some(after) => after.to_file_action(),
+            None => opts.action.clone(),
+        },
         upload: opts.upload.clone(),
+        cache: opts.cache.clone(),
+        sidecar: false,
         matches: opts.matches.clone(),
         not_matches: opts.not_matches.clone(),
+        include: vec![],
+        exclude: vec![],
+        case_sensitive: false,
         traverse: false,
         poll: None,
+        concurrency: 1,
         dry_run: opts.dry_run,
+        stdin: false,
+        name: None,
+        compress: false,
+        force_compress: false,
         files: vec![path],
     };
     upload::upload_files(data, ctx).context(Upload)
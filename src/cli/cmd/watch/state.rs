@@ -0,0 +1,207 @@
+//! Remembering the files that `watch` has uploaded.
+//!
+//! The state file contains one json object per line with the
+//! absolute path, modification time and sha256 hash of a file that
+//! has been uploaded successfully. Each entry is appended with a
+//! single write, so a crash can at most leave an incomplete last
+//! line, which is ignored when reading the file again.
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use crate::util::digest;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Unable to read state file {}: {}", path.display(), source))]
+    ReadState {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("Unable to write state file {}: {}", path.display(), source))]
+    WriteState {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+/// The uploaded files read from the state file, including those
+/// appended since.
+#[derive(Debug)]
+pub struct WatchState {
+    file: PathBuf,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<PathBuf, Entry>,
+    /// Whether the file doesn't end with a newline, because the last
+    /// write was incomplete.
+    incomplete: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Entry {
+    path: PathBuf,
+    modified: u64,
+    sha256: String,
+}
+
+impl WatchState {
+    /// Reads the state from the given file. If it doesn't exist, no
+    /// file has been uploaded yet.
+    pub fn load(file: &Path) -> Result<WatchState, Error> {
+        let mut entries = HashMap::new();
+        let mut incomplete = false;
+        if file.exists() {
+            let data = std::fs::read_to_string(file).context(ReadState { path: file })?;
+            incomplete = !data.is_empty() && !data.ends_with('\n');
+            for (n, line) in data
+                .lines()
+                .enumerate()
+                .filter(|(_, l)| !l.trim().is_empty())
+            {
+                match serde_json::from_str::<Entry>(line) {
+                    Ok(entry) => {
+                        entries.insert(entry.path.clone(), entry);
+                    }
+                    Err(err) => log::warn!(
+                        "Ignoring invalid line {} in state file {}: {}",
+                        n + 1,
+                        file.display(),
+                        err
+                    ),
+                }
+            }
+        }
+        log::debug!(
+            "Read {} entries from state file {}",
+            entries.len(),
+            file.display()
+        );
+        Ok(WatchState {
+            file: file.to_path_buf(),
+            state: Mutex::new(State {
+                entries,
+                incomplete,
+            }),
+        })
+    }
+
+    /// Whether the file has been uploaded before. It is, if its hash
+    /// is the same as recorded. A changed modification time alone,
+    /// like after `touch`, doesn't make it a new file.
+    pub fn contains(&self, entry: &Entry) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .entries
+            .get(&entry.path)
+            .map(|e| e.sha256 == entry.sha256)
+            .unwrap_or(false)
+    }
+
+    /// Appends the entry of an uploaded file to the state file.
+    pub fn record(&self, entry: Entry) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        let path = &self.file;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context(WriteState { path })?;
+        }
+        let mut line = serde_json::to_string(&entry).map_err(|e| Error::WriteState {
+            source: e.into(),
+            path: path.clone(),
+        })?;
+        line.push('\n');
+        if state.incomplete {
+            line.insert(0, '\n');
+        }
+        let mut out = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(WriteState { path })?;
+        out.write_all(line.as_bytes())
+            .and_then(|_| out.sync_data())
+            .context(WriteState { path })?;
+        state.incomplete = false;
+        state.entries.insert(entry.path.clone(), entry);
+        Ok(())
+    }
+}
+
+impl Entry {
+    /// Creates the entry for a file from its absolute path,
+    /// modification time (in millis) and hash.
+    pub fn of(file: &Path) -> std::io::Result<Entry> {
+        let path = std::fs::canonicalize(file)?;
+        let modified = std::fs::metadata(&path)?
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let sha256 = digest::digest_file_sha256(&path)?;
+        Ok(Entry {
+            path,
+            modified,
+            sha256,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_state_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("dsc-state-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state_file = dir.join("state.jsonl");
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "hello").unwrap();
+
+        let state = WatchState::load(&state_file).unwrap();
+        let entry = Entry::of(&file).unwrap();
+        assert!(!state.contains(&entry));
+        state.record(entry.clone()).unwrap();
+        assert!(state.contains(&entry));
+
+        // an incomplete line, as left by a crash
+        let mut out = OpenOptions::new().append(true).open(&state_file).unwrap();
+        out.write_all(b"{\"path\":\"/x\",\"modi").unwrap();
+
+        let state = WatchState::load(&state_file).unwrap();
+        assert!(state.contains(&entry));
+        let other = dir.join("b.txt");
+        std::fs::write(&other, "bye").unwrap();
+        state.record(Entry::of(&other).unwrap()).unwrap();
+        let state = WatchState::load(&state_file).unwrap();
+        assert!(state.contains(&Entry::of(&other).unwrap()));
+        std::fs::write(&file, "hello world").unwrap();
+        let changed = Entry {
+            modified: entry.modified + 1,
+            ..Entry::of(&file).unwrap()
+        };
+        assert!(!state.contains(&changed));
+        let same_time = Entry {
+            modified: entry.modified,
+            ..changed
+        };
+        assert!(!state.contains(&same_time));
+        let touched = Entry {
+            modified: entry.modified + 1,
+            ..entry.clone()
+        };
+        assert!(state.contains(&touched));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+use clap::Clap;
+use prettytable::{row, Table};
+use schemars::JsonSchema;
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+
+use super::{Cmd, Context};
+use crate::cli::opts::EndpointOpts;
+use crate::cli::sink::{Error as SinkError, Sink};
+use crate::cli::table::{format_date_by, AsTable};
+use crate::http::{Error as HttpError, FileAuth};
+
+/// Prints the account of the current session.
+///
+/// Asks the server which user and collective the session belongs to
+/// and prints it together with the expiry of the session token and
+/// the server version. When a source id or the integration endpoint
+/// is given (or a default source id is configured), no user session
+/// is in effect and only this is reported.
+#[derive(Clap, Debug)]
+pub struct Input {
+    #[clap(flatten)]
+    pub endpoint: EndpointOpts,
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("An http error occurred: {}!", source))]
+    HttpClient { source: HttpError },
+
+    #[snafu(display("Error writing data: {}", source))]
+    WriteResult { source: SinkError },
+}
+
+/// Who is using the server and how.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WhoAmI {
+    pub docspell_url: String,
+    pub server_version: String,
+    pub auth: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collective: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_expires: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    pub message: String,
+}
+
+impl Cmd for Input {
+    type CmdError = Error;
+
+    fn exec(&self, ctx: &Context) -> Result<(), Error> {
+        let version = ctx.client.version().context(HttpClient)?;
+        let mut result = WhoAmI {
+            docspell_url: ctx.base_url(),
+            server_version: version.version,
+            auth: "session",
+            user: None,
+            collective: None,
+            token_expires: None,
+            source_id: None,
+            message: String::new(),
+        };
+        match self.endpoint.to_file_auth(ctx) {
+            FileAuth::Session { token } => {
+                let info = ctx.client.session_info(&token).context(HttpClient)?;
+                result.message = format!("Logged in as {}/{}", info.collective, info.user);
+                result.user = Some(info.user);
+                result.collective = Some(info.collective);
+                result.token_expires = Some(info.expires);
+            }
+            FileAuth::Source { id } => {
+                result.auth = "source";
+                result.source_id = Some(id);
+                result.message = "No user session, the source id is used".into();
+            }
+            FileAuth::Integration(data) => {
+                result.auth = "integration";
+                result.collective = Some(data.collective);
+                result.message = "No user session, the integration endpoint is used".into();
+            }
+        }
+        ctx.write_result(result).context(WriteResult)?;
+        Ok(())
+    }
+}
+
+impl AsTable for WhoAmI {
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_CLEAN);
+        table.add_row(row!["docspell url", self.docspell_url]);
+        table.add_row(row!["server version", self.server_version]);
+        table.add_row(row!["auth", self.auth]);
+        if let Some(user) = &self.user {
+            table.add_row(row!["user", user]);
+        }
+        if let Some(collective) = &self.collective {
+            table.add_row(row!["collective", collective]);
+        }
+        if let Some(expires) = self.token_expires {
+            let date = format_date_by(expires as i64, "%Y-%m-%d %H:%M:%S UTC");
+            table.add_row(row!["token expires", date]);
+        }
+        if let Some(id) = &self.source_id {
+            table.add_row(row!["source id", id]);
+        }
+        table.add_row(row!["message", self.message]);
+        table
+    }
+}
+impl Sink for WhoAmI {}
@@ -0,0 +1,52 @@
+//! Describes what a command would do, without doing it.
+
+use crate::http::payload::UploadMeta;
+use crate::util::file::FileActionResult;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// The result of a dry run: the endpoint that would be used, the
+/// upload metadata (if any) and what would happen to each file.
+#[derive(Serialize, Debug)]
+pub struct DryRun {
+    pub endpoint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<UploadMeta>,
+    pub files: Vec<PlannedFile>,
+}
+
+/// What would happen to a single file.
+#[derive(Serialize, Debug)]
+pub struct PlannedFile {
+    pub file: PathBuf,
+    pub exists: bool,
+    pub upload: bool,
+    pub delete: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub move_to: Option<PathBuf>,
+    /// The sidecar file with metadata for this file, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sidecar: Option<PathBuf>,
+    /// The metadata for this file, if it differs from the common one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<UploadMeta>,
+}
+
+impl PlannedFile {
+    pub fn new(file: PathBuf, exists: bool, upload: bool, action: FileActionResult) -> Self {
+        let (delete, move_to) = match action {
+            FileActionResult::Deleted(_) => (true, None),
+            FileActionResult::Moved(target) => (false, Some(target)),
+            FileActionResult::Nothing => (false, None),
+        };
+        PlannedFile {
+            file,
+            exists,
+            upload,
+            delete,
+            move_to,
+            sidecar: None,
+            meta: None,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Reduces results to a set of fields, given via `--fields`.
+//!
+//! A field is the name of a key in the json representation of a
+//! result. Nested keys are separated by dots, like `folder.name`. If
+//! a value on the way is an array, the rest of the path is applied to
+//! each element, so `tags.name` selects the names of all tags.
+
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+use super::opts::Format;
+use crate::status;
+
+/// Selects fields of json values and remembers which fields have been
+/// found.
+pub struct Projection {
+    fields: Vec<String>,
+    found: HashSet<String>,
+}
+
+impl Projection {
+    pub fn new(fields: &[String]) -> Projection {
+        Projection {
+            fields: fields.to_vec(),
+            found: HashSet::new(),
+        }
+    }
+
+    /// Whether fields can be selected for the given format. Tabular
+    /// and csv use `--columns` instead.
+    pub fn applies_to(format: Format) -> bool {
+        matches!(
+            format,
+            Format::Json | Format::Ndjson | Format::Yaml | Format::Lisp
+        )
+    }
+
+    /// Returns a copy of `value` that contains only the selected
+    /// fields.
+    pub fn apply(&mut self, value: &Value) -> Value {
+        let mut result = Value::Object(Map::new());
+        for field in &self.fields {
+            let path: Vec<&str> = field.split('.').collect();
+            if let Some(v) = pick(value, &path) {
+                self.found.insert(field.clone());
+                merge(&mut result, v);
+            }
+        }
+        result
+    }
+
+    /// Prints a warning for each field that didn't exist in any
+    /// value.
+    pub fn warn_unknown(&self) {
+        for field in self.fields.iter().filter(|f| !self.found.contains(*f)) {
+            status!("Warning: The field '{}' doesn't exist in the result", field);
+        }
+    }
+}
+
+/// Returns the value reduced to the given path, wrapped in its
+/// parent objects.
+fn pick(value: &Value, path: &[&str]) -> Option<Value> {
+    let (key, rest) = path.split_first()?;
+    match value {
+        Value::Object(map) => {
+            let v = map.get(*key)?;
+            let sub = if rest.is_empty() {
+                v.clone()
+            } else {
+                pick(v, rest)?
+            };
+            let mut obj = Map::new();
+            obj.insert(key.to_string(), sub);
+            Some(Value::Object(obj))
+        }
+        Value::Array(list) => {
+            let picked: Vec<Option<Value>> = list.iter().map(|el| pick(el, path)).collect();
+            if !list.is_empty() && picked.iter().all(Option::is_none) {
+                return None;
+            }
+            let elements = picked
+                .into_iter()
+                .map(|v| v.unwrap_or_else(|| Value::Object(Map::new())))
+                .collect();
+            Some(Value::Array(elements))
+        }
+        _ => None,
+    }
+}
+
+/// Merges two picked values, which have the same structure where
+/// both exist.
+fn merge(into: &mut Value, value: Value) {
+    match (into, value) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (k, v) in b {
+                match a.get_mut(&k) {
+                    Some(existing) => merge(existing, v),
+                    None => {
+                        a.insert(k, v);
+                    }
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (existing, v) in a.iter_mut().zip(b) {
+                merge(existing, v);
+            }
+        }
+        (into, value) => *into = value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unit_apply_fields() {
+        let item = json!({
+            "id": "abc",
+            "name": "one",
+            "folder": {"id": "f1", "name": "Fold"},
+            "tags": [{"id": "t1", "name": "Invoice"}, {"id": "t2", "name": "Paid"}]
+        });
+        let fields: Vec<String> = vec!["id", "folder.name", "tags.name", "tags.id", "nope"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let mut proj = Projection::new(&fields);
+        assert_eq!(
+            proj.apply(&item),
+            json!({
+                "id": "abc",
+                "folder": {"name": "Fold"},
+                "tags": [{"id": "t1", "name": "Invoice"}, {"id": "t2", "name": "Paid"}]
+            })
+        );
+        assert!(proj.found.contains("tags.name"));
+        assert!(!proj.found.contains("nope"));
+    }
+}
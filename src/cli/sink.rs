@@ -7,49 +7,240 @@
 //! If a type implements serdes Serialize trait and the
 //! [`super::table::AsTable`] trait, a Sink is implemented for free.
 
-use super::opts::Format;
+use super::opts::{ColorChoice, Format};
+use crate::cli::fields::Projection;
 use crate::cli::table::AsTable;
+use crate::cli::template::Template;
+use crate::util::file::AtomicFile;
+use prettytable::Table;
 use serde::Serialize;
-use snafu::Snafu;
+use snafu::{ResultExt, Snafu};
 use std::convert::From;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// Defines different outputs for a data type given via a [`Format`]
 /// argument.
 ///
-/// The formats `json` and `lisp` are handled via
+/// The formats `json`, `ndjson`, `yaml` and `lisp` are handled via
 /// [serde](https://serde.rs), the formats `tabular` and `csv` are
 /// handled by [prettytable](https://crates.io/crates/prettytable-rs)
 /// (and the csv crate).
+///
+/// The `columns` argument can be used to select and order the columns
+/// of the `tabular` and `csv` formats. It is ignored for all other
+/// formats. If it is empty, the default columns are used.
+///
+/// The `ndjson` format prints one json object per line. Collections
+/// are printed with one line per element, all other values result in
+/// exactly one line. A [`Template`] is rendered in the same way, once
+/// for each of these values.
 pub trait Sink
 where
     Self: Serialize + AsTable,
 {
-    fn write_value(format: Format, value: &Self) -> Result<(), Error> {
+    /// Writes the value to stdout.
+    fn write_value(format: Format, columns: &[String], value: &Self) -> Result<(), Error> {
+        Self::write_to(&mut Output::Stdout, format, columns, value)
+    }
+
+    fn write_to(
+        out: &mut Output,
+        format: Format,
+        columns: &[String],
+        value: &Self,
+    ) -> Result<(), Error> {
         match format {
             Format::Json => {
-                serde_json::to_writer(std::io::stdout(), &value)?;
+                serde_json::to_writer(out, &value)?;
+                Ok(())
+            }
+            Format::Ndjson => Self::write_ndjson(out, value),
+            Format::Yaml => {
+                serde_yaml::to_writer(out, &value)?;
                 Ok(())
             }
             Format::Lisp => {
-                serde_lexpr::to_writer(std::io::stdout(), &value)?;
+                serde_lexpr::to_writer(out, &value)?;
                 Ok(())
             }
-            Format::Csv => Self::write_csv(value),
-            Format::Tabular => Self::write_tabular(value),
+            Format::Csv => Self::write_csv(out, value, columns),
+            Format::Tabular => Self::write_tabular(out, value, columns),
         }
     }
 
-    fn write_tabular(value: &Self) -> Result<(), Error> {
-        let table = value.to_table();
-        table.printstd();
+    fn write_ndjson(out: &mut Output, value: &Self) -> Result<(), Error> {
+        for el in Self::records(value)? {
+            writeln!(out, "{}", el)?;
+        }
         Ok(())
     }
 
-    fn write_csv(value: &Self) -> Result<(), Error> {
-        let table = value.to_table();
-        table.to_csv(std::io::stdout())?;
+    /// Renders the template for each record and writes one line per
+    /// record.
+    fn write_template(
+        out: &mut Output,
+        value: &Self,
+        template: &Template,
+        strict: bool,
+    ) -> Result<(), Error> {
+        for el in Self::records(value)? {
+            writeln!(out, "{}", template.render(&el, strict)?)?;
+        }
         Ok(())
     }
+
+    /// Writes only the fields selected by `proj`. This is supported
+    /// for the formats json, ndjson, yaml and lisp, all other formats
+    /// are written as usual.
+    fn write_projected(
+        out: &mut Output,
+        format: Format,
+        value: &Self,
+        proj: &mut Projection,
+    ) -> Result<(), Error> {
+        match format {
+            Format::Json => serde_json::to_writer(out, &Self::project(value, proj)?)?,
+            Format::Ndjson => {
+                for el in Self::records(value)? {
+                    writeln!(out, "{}", proj.apply(&el))?;
+                }
+            }
+            Format::Yaml => serde_yaml::to_writer(out, &Self::project(value, proj)?)?,
+            Format::Lisp => serde_lexpr::to_writer(out, &Self::project(value, proj)?)?,
+            Format::Csv | Format::Tabular => Self::write_to(out, format, &[], value)?,
+        }
+        Ok(())
+    }
+
+    /// Returns the json value with only the fields selected by
+    /// `proj`. For collections, the fields are selected from each
+    /// element.
+    fn project(value: &Self, proj: &mut Projection) -> Result<serde_json::Value, Error> {
+        match serde_json::to_value(value)? {
+            serde_json::Value::Array(elements) => Ok(serde_json::Value::Array(
+                elements.iter().map(|el| proj.apply(el)).collect(),
+            )),
+            json => Ok(proj.apply(&json)),
+        }
+    }
+
+    /// Returns the values that are written one per line for `ndjson`
+    /// and templates. Collections are split into their elements.
+    fn records(value: &Self) -> Result<Vec<serde_json::Value>, Error> {
+        match serde_json::to_value(value)? {
+            serde_json::Value::Array(elements) => Ok(elements),
+            json => Ok(vec![json]),
+        }
+    }
+
+    fn write_tabular(out: &mut Output, value: &Self, columns: &[String]) -> Result<(), Error> {
+        let table = Self::select_table(value, columns)?;
+        out.print_table(&table)
+    }
+
+    fn write_csv(out: &mut Output, value: &Self, columns: &[String]) -> Result<(), Error> {
+        let table = Self::select_table(value, columns)?;
+        table.to_csv(out)?;
+        Ok(())
+    }
+
+    fn select_table(value: &Self, columns: &[String]) -> Result<Table, Error> {
+        if columns.is_empty() {
+            Ok(value.to_table())
+        } else {
+            value.to_table_columns(columns)
+        }
+    }
+}
+
+static COLOR: AtomicBool = AtomicBool::new(true);
+static FORCE_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Sets when to use colors for tables and messages. With `auto`,
+/// they are only used when writing to a terminal.
+pub fn set_color(choice: ColorChoice) {
+    COLOR.store(choice != ColorChoice::Never, Ordering::Relaxed);
+    FORCE_COLOR.store(choice == ColorChoice::Always, Ordering::Relaxed);
+    match choice {
+        ColorChoice::Auto => {}
+        ColorChoice::Always | ColorChoice::Never => {
+            let enabled = choice == ColorChoice::Always;
+            console::set_colors_enabled(enabled);
+            console::set_colors_enabled_stderr(enabled);
+        }
+    }
+}
+
+/// Where formatted values are written to.
+///
+/// Tables are colored when written to stdout and it is a terminal,
+/// or always or never as given via [`set_color`]. A
+/// file is written atomically, it only replaces an existing file when
+/// [`Output::finish`] is called.
+pub enum Output {
+    Stdout,
+    File(AtomicFile),
+}
+
+impl Output {
+    /// Writes to the given file, or to stdout if it is `None`.
+    pub fn create(file: Option<&Path>) -> Result<Output, Error> {
+        match file {
+            Some(path) => AtomicFile::create(path)
+                .map(Output::File)
+                .context(CreateFile { path }),
+            None => Ok(Output::Stdout),
+        }
+    }
+
+    pub fn print_table(&mut self, table: &Table) -> Result<(), Error> {
+        match self {
+            Output::Stdout if COLOR.load(Ordering::Relaxed) => {
+                table.print_tty(FORCE_COLOR.load(Ordering::Relaxed))?;
+                Ok(())
+            }
+            Output::Stdout => {
+                table.print(&mut std::io::stdout())?;
+                Ok(())
+            }
+            Output::File(file) => {
+                table.print(file)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Completes writing. For a file, it replaces the target file.
+    pub fn finish(self) -> Result<(), Error> {
+        match self {
+            Output::Stdout => {
+                std::io::stdout().flush()?;
+                Ok(())
+            }
+            Output::File(file) => {
+                file.commit()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Stdout => std::io::stdout().write(buf),
+            Output::File(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Stdout => std::io::stdout().flush(),
+            Output::File(file) => file.flush(),
+        }
+    }
 }
 
 /// Possible errors when serializing data.
@@ -58,11 +249,37 @@ pub enum Error {
     #[snafu(display("Error serializing to JSON"))]
     Json { source: serde_json::Error },
 
+    #[snafu(display("Error serializing to YAML"))]
+    Yaml { source: serde_yaml::Error },
+
     #[snafu(display("Error serializing to Lisp"))]
     Lisp { source: serde_lexpr::Error },
 
     #[snafu(display("Error serializing to CSV"))]
     Csv { source: csv::Error },
+
+    #[snafu(display("Unknown column '{}'. Valid columns are: {}", name, valid))]
+    UnknownColumn { name: String, valid: String },
+
+    #[snafu(display("Selecting columns is not supported for this output"))]
+    ColumnsNotSupported,
+
+    #[snafu(display("The field '{}' used in the template doesn't exist", name))]
+    MissingField { name: String },
+
+    #[snafu(display("Error writing the output: {}", source))]
+    Io { source: std::io::Error },
+
+    #[snafu(display("Error creating output file {}: {}", path.display(), source))]
+    CreateFile {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+}
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Error {
+        Error::Io { source: e }
+    }
 }
 impl From<csv::Error> for Error {
     fn from(e: csv::Error) -> Error {
@@ -74,6 +291,11 @@ impl From<serde_json::Error> for Error {
         Error::Json { source: e }
     }
 }
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Error {
+        Error::Yaml { source: e }
+    }
+}
 impl From<serde_lexpr::Error> for Error {
     fn from(e: serde_lexpr::Error) -> Error {
         Error::Lisp { source: e }
@@ -1,14 +1,104 @@
 //! Defines human readable table output for various types.
 
-use crate::cli::sink::{Error as SinkError, Sink};
+use crate::cli::cmd::config::check::ConfigCheck;
+use crate::cli::cmd::config::show::EffectiveConfig;
+use crate::cli::cmd::item::delete::DeletedItem;
+use crate::cli::cmd::item::url::ItemUrl;
+use crate::cli::cmd::search::saved::NamedSearch;
+use crate::cli::cmd::search_summary::{GroupCount, QueryCount};
+use crate::cli::dry_run::DryRun;
+use crate::cli::fields::Projection;
+use crate::cli::sink::{Error as SinkError, Output, Sink};
 use crate::http::payload::*;
 use chrono::{DateTime, TimeZone, Utc};
+use indicatif::HumanBytes;
 use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
-use prettytable::{cell, row, Table};
+use prettytable::{row, Attr, Cell, Row, Table};
+use std::io::Write;
 
 /// A trait to format a data structure into a [`prettytable::Table`].
 pub trait AsTable {
     fn to_table(&self) -> Table;
+
+    /// Formats the data into a table containing only the given
+    /// columns in the given order. Not all tables support selecting
+    /// columns, the default returns an error.
+    fn to_table_columns(&self, _columns: &[String]) -> Result<Table, SinkError> {
+        Err(SinkError::ColumnsNotSupported)
+    }
+}
+
+/// Describes the named columns of a table.
+///
+/// Rows are created containing all columns, but only the first
+/// `defaults` columns are shown if no specific columns are
+/// requested.
+pub struct Columns {
+    pub names: &'static [&'static str],
+    pub defaults: usize,
+}
+
+impl Columns {
+    /// Creates a table from the rows showing the default columns.
+    pub fn table(&self, rows: Vec<Row>) -> Table {
+        let idx: Vec<usize> = (0..self.defaults).collect();
+        self.build(&idx, rows)
+    }
+
+    /// Creates a table from the rows showing only the given columns.
+    /// An error is returned if a column name is unknown.
+    pub fn select(&self, columns: &[String], rows: Vec<Row>) -> Result<Table, SinkError> {
+        let mut idx = Vec::with_capacity(columns.len());
+        for col in columns {
+            match self
+                .names
+                .iter()
+                .position(|n| n.eq_ignore_ascii_case(col.trim()))
+            {
+                Some(i) => idx.push(i),
+                None => {
+                    return Err(SinkError::UnknownColumn {
+                        name: col.clone(),
+                        valid: self.names.join(", "),
+                    })
+                }
+            }
+        }
+        Ok(self.build(&idx, rows))
+    }
+
+    fn build(&self, idx: &[usize], rows: Vec<Row>) -> Table {
+        let mut table = mk_table();
+        table.set_titles(Row::new(
+            idx.iter()
+                .map(|i| Cell::new(self.names[*i]).style_spec("bFg"))
+                .collect(),
+        ));
+        for row in rows {
+            table.add_row(Row::new(
+                idx.iter()
+                    .map(|i| {
+                        let cell = row.get_cell(*i).cloned().unwrap_or_else(|| Cell::new(""));
+                        match column_style(self.names[*i]) {
+                            Some(attr) => cell.with_style(attr),
+                            None => cell,
+                        }
+                    })
+                    .collect(),
+            ));
+        }
+        table
+    }
+}
+
+/// Ids are dimmed and names are bold, to make them easier to tell
+/// apart. The style is only visible on terminals.
+fn column_style(name: &str) -> Option<Attr> {
+    match name {
+        "id" => Some(Attr::Dim),
+        "name" => Some(Attr::Bold),
+        _ => None,
+    }
 }
 
 /// Creates a new table with some default settings.
@@ -60,7 +150,7 @@ pub fn format_date_by(dt: i64, pattern: &str) -> String {
 /// Combines two [`IdName`] objects by their name via a separator.
 /// Returns only one value if the other is empty or the empty string
 /// if both are not present.
-fn combine(opta: &Option<IdName>, optb: &Option<IdName>, sep: &str) -> String {
+pub fn combine(opta: &Option<IdName>, optb: &Option<IdName>, sep: &str) -> String {
     match (opta, optb) {
         (Some(a), Some(b)) => format!("{}{}{}", a.name, sep, b.name),
         (Some(a), None) => a.name.clone(),
@@ -121,57 +211,77 @@ impl AsTable for ItemDetail {
 }
 impl Sink for ItemDetail {}
 
+const ITEM_COLUMNS: Columns = Columns {
+    names: &[
+        "id",
+        "name",
+        "state",
+        "date",
+        "due",
+        "correspondent",
+        "concerning",
+        "folder",
+        "tags",
+        "fields",
+        "files",
+        "source",
+        "direction",
+    ],
+    defaults: 11,
+};
+
+/// Creates a row for an item containing all [`ITEM_COLUMNS`].
+fn item_row(item: &Item) -> Row {
+    let tag_list: Vec<String> = item.tags.iter().map(|t| t.name.clone()).collect();
+    let field_list: Vec<String> = item
+        .customfields
+        .iter()
+        .map(|f| format!("{} {}", f.name_or_label(), f.value))
+        .collect();
+    row![
+        item.id[0..8],
+        item.name,
+        item.state,
+        format_date(item.date),
+        item.due_date.map(format_date).unwrap_or_default(),
+        combine(&item.corr_org, &item.corr_person, "/"),
+        combine(&item.conc_person, &item.conc_equip, "/"),
+        item.folder.as_ref().map(|a| a.name.as_str()).unwrap_or(""),
+        tag_list.join(", "),
+        field_list.join(", "),
+        item.attachments.len(),
+        item.source,
+        str_or_empty(item.direction.as_ref()),
+    ]
+}
+
 impl AsTable for Item {
     fn to_table(&self) -> Table {
-        let mut table = mk_table();
-        table.set_titles(row![bFg =>
-            "id",
-            "name",
-            "state",
-            "date",
-            "due",
-            "correspondent",
-            "concerning",
-            "folder",
-            "tags",
-            "fields",
-            "files"
-        ]);
-
-        let tag_list: Vec<String> = self.tags.iter().map(|t| t.name.clone()).collect();
-        let field_list: Vec<String> = self
-            .customfields
-            .iter()
-            .map(|f| format!("{} {}", f.name_or_label(), f.value))
-            .collect();
-        table.add_row(row![
-            self.id[0..8],
-            self.name,
-            self.state,
-            format_date(self.date),
-            self.due_date.map(format_date).unwrap_or_else(|| "".into()),
-            combine(&self.corr_org, &self.corr_person, "/"),
-            combine(&self.conc_person, &self.conc_equip, "/"),
-            self.folder
-                .as_ref()
-                .map(|a| a.name.as_str())
-                .unwrap_or_else(|| ""),
-            tag_list.join(", "),
-            field_list.join(", "),
-            self.attachments.len(),
-        ]);
+        ITEM_COLUMNS.table(vec![item_row(self)])
+    }
 
-        table
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        ITEM_COLUMNS.select(columns, vec![item_row(self)])
     }
 }
 impl Sink for Item {}
 
+const RESET_PASSWORD_COLUMNS: Columns = Columns {
+    names: &["success", "new password", "message"],
+    defaults: 3,
+};
+
+fn reset_password_rows(resp: &ResetPasswordResp) -> Vec<Row> {
+    vec![row![resp.success, resp.new_password, resp.message]]
+}
+
 impl AsTable for ResetPasswordResp {
     fn to_table(&self) -> Table {
-        let mut table = mk_table();
-        table.set_titles(row![bFg => "success", "new password", "message"]);
-        table.add_row(row![self.success, self.new_password, self.message,]);
-        table
+        RESET_PASSWORD_COLUMNS.table(reset_password_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        RESET_PASSWORD_COLUMNS.select(columns, reset_password_rows(self))
     }
 }
 impl Sink for ResetPasswordResp {}
@@ -206,14 +316,40 @@ impl AsTable for InviteResult {
 }
 impl Sink for InviteResult {}
 
-impl AsTable for Vec<SourceAndTags> {
+impl AsTable for Vec<InviteResult> {
     fn to_table(&self) -> Table {
         let mut table = mk_table();
-        table.set_titles(
-            row![bFg => "id", "name", "enabled", "prio", "folder", "file filter", "language"],
-        );
-        for item in self {
+        table.set_titles(row![bFg => "success", "key", "message"]);
+        for inv in self {
             table.add_row(row![
+                inv.success,
+                str_or_empty(inv.key.as_ref()),
+                inv.message
+            ]);
+        }
+        table
+    }
+}
+impl Sink for Vec<InviteResult> {}
+
+const SOURCE_COLUMNS: Columns = Columns {
+    names: &[
+        "id",
+        "name",
+        "enabled",
+        "prio",
+        "folder",
+        "file filter",
+        "language",
+    ],
+    defaults: 7,
+};
+
+fn source_rows(sources: &[SourceAndTags]) -> Vec<Row> {
+    sources
+        .iter()
+        .map(|item| {
+            row![
                 item.source.id[0..8],
                 item.source.abbrev,
                 item.source.enabled,
@@ -221,26 +357,50 @@ impl AsTable for Vec<SourceAndTags> {
                 str_or_empty(item.source.folder.as_ref()),
                 str_or_empty(item.source.file_filter.as_ref()),
                 str_or_empty(item.source.language.as_ref())
-            ]);
-        }
-        table
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<SourceAndTags> {
+    fn to_table(&self) -> Table {
+        SOURCE_COLUMNS.table(source_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        SOURCE_COLUMNS.select(columns, source_rows(self))
     }
 }
 impl Sink for Vec<SourceAndTags> {}
 
-impl AsTable for Vec<CheckFileResult> {
-    fn to_table(&self) -> Table {
-        let mut table = mk_table();
-        table.set_titles(row![bFg => "exists", "items", "file"]);
-        for el in self {
+const CHECK_FILE_COLUMNS: Columns = Columns {
+    names: &["exists", "items", "file", "hash", "error"],
+    defaults: 4,
+};
+
+fn check_file_rows(results: &[CheckFileResult]) -> Vec<Row> {
+    results
+        .iter()
+        .map(|el| {
             let item_list: Vec<String> = el.items.iter().map(|i| i.id[0..8].into()).collect();
-            table.add_row(row![
+            row![
                 el.exists,
                 item_list.join(", "),
-                str_or_empty(el.file.as_ref())
-            ]);
-        }
-        table
+                str_or_empty(el.file.as_ref()),
+                str_or_empty(el.hash.as_ref()),
+                str_or_empty(el.error.as_ref())
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<CheckFileResult> {
+    fn to_table(&self) -> Table {
+        CHECK_FILE_COLUMNS.table(check_file_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        CHECK_FILE_COLUMNS.select(columns, check_file_rows(self))
     }
 }
 impl Sink for Vec<CheckFileResult> {}
@@ -258,6 +418,20 @@ impl AsTable for BasicResult {
 }
 impl Sink for BasicResult {}
 
+impl AsTable for IdResult {
+    fn to_table(&self) -> Table {
+        let mut table = mk_table();
+        table.set_titles(row![bFg =>
+            "success",
+            "id",
+            "message",
+        ]);
+        table.add_row(row![self.success, self.id, self.message,]);
+        table
+    }
+}
+impl Sink for IdResult {}
+
 impl AsTable for VersionInfo {
     fn to_table(&self) -> Table {
         let mut table = mk_table();
@@ -275,6 +449,7 @@ impl AsTable for BuildInfo {
         table.add_row(row!["build date", self.build_date]);
         table.add_row(row!["version", self.build_version]);
         table.add_row(row!["commit", self.git_commit]);
+        table.add_row(row!["target", self.cargo_target_triple]);
         table.add_row(row!["rustc host", self.rustc_host_triple]);
         table.add_row(row!["llvm", self.rustc_llvm_version]);
         table.add_row(row!["rust version", self.rustc_version]);
@@ -283,46 +458,156 @@ impl AsTable for BuildInfo {
 }
 impl Sink for BuildInfo {}
 
-impl AsTable for Vec<&TagCount> {
-    fn to_table(&self) -> Table {
-        let mut table = mk_table();
-        table.set_titles(row![bFg => "id", "name", "category", "count"]);
-        for item in self {
-            table.add_row(row![
+const TAG_COUNT_COLUMNS: Columns = Columns {
+    names: &["id", "name", "category", "count"],
+    defaults: 4,
+};
+
+fn tag_count_rows(tags: &[&TagCount]) -> Vec<Row> {
+    tags.iter()
+        .map(|item| {
+            row![
                 item.tag.id[0..8],
                 item.tag.name,
                 str_or_empty(item.tag.category.as_ref()),
                 item.count,
-            ]);
-        }
-        table
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<&TagCount> {
+    fn to_table(&self) -> Table {
+        TAG_COUNT_COLUMNS.table(tag_count_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        TAG_COUNT_COLUMNS.select(columns, tag_count_rows(self))
     }
 }
 impl Sink for Vec<&TagCount> {}
 
-impl AsTable for Vec<&CatCount> {
+impl AsTable for Vec<TagCount> {
     fn to_table(&self) -> Table {
-        let mut table = mk_table();
-        table.set_titles(row![bFg => "name", "count"]);
-        for item in self {
-            table.add_row(row![
+        let tags: Vec<&TagCount> = self.iter().collect();
+        TAG_COUNT_COLUMNS.table(tag_count_rows(&tags))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        let tags: Vec<&TagCount> = self.iter().collect();
+        TAG_COUNT_COLUMNS.select(columns, tag_count_rows(&tags))
+    }
+}
+impl Sink for Vec<TagCount> {}
+
+const FOLDER_COLUMNS: Columns = Columns {
+    names: &["id", "name", "owner", "members", "created"],
+    defaults: 4,
+};
+
+fn folder_rows(folders: &[FolderItem]) -> Vec<Row> {
+    folders
+        .iter()
+        .map(|f| {
+            row![
+                f.id,
+                f.name,
+                f.owner.name,
+                f.member_count,
+                format_date(f.created),
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<FolderItem> {
+    fn to_table(&self) -> Table {
+        FOLDER_COLUMNS.table(folder_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        FOLDER_COLUMNS.select(columns, folder_rows(self))
+    }
+}
+impl Sink for Vec<FolderItem> {}
+
+const ATTACHMENT_COLUMNS: Columns = Columns {
+    names: &["id", "name", "size", "type", "converted"],
+    defaults: 4,
+};
+
+fn attachment_rows(attachments: &[Attachment]) -> Vec<Row> {
+    attachments
+        .iter()
+        .map(|a| {
+            row![
+                a.id,
+                str_or_empty(a.name.as_ref()),
+                HumanBytes(a.size),
+                a.content_type,
+                a.converted,
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<Attachment> {
+    fn to_table(&self) -> Table {
+        ATTACHMENT_COLUMNS.table(attachment_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        ATTACHMENT_COLUMNS.select(columns, attachment_rows(self))
+    }
+}
+impl Sink for Vec<Attachment> {}
+
+const CAT_COUNT_COLUMNS: Columns = Columns {
+    names: &["name", "count"],
+    defaults: 2,
+};
+
+fn cat_count_rows(cats: &[&CatCount]) -> Vec<Row> {
+    cats.iter()
+        .map(|item| {
+            row![
                 item.name.clone().unwrap_or_else(|| "(no category)".into()),
                 item.count,
-            ]);
-        }
-        table
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<&CatCount> {
+    fn to_table(&self) -> Table {
+        CAT_COUNT_COLUMNS.table(cat_count_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        CAT_COUNT_COLUMNS.select(columns, cat_count_rows(self))
     }
 }
 impl Sink for Vec<&CatCount> {}
 
-impl AsTable for Vec<FieldStats> {
-    fn to_table(&self) -> Table {
-        let mut table = mk_table();
-        table.set_titles(
-            row![bFg => "id", "name/label", "type", "count", "sum", "avg", "max", "min"],
-        );
-        for item in self {
-            table.add_row(row![
+const FIELD_STATS_COLUMNS: Columns = Columns {
+    names: &[
+        "id",
+        "name/label",
+        "type",
+        "count",
+        "sum",
+        "avg",
+        "max",
+        "min",
+    ],
+    defaults: 8,
+};
+
+fn field_stats_rows(stats: &[FieldStats]) -> Vec<Row> {
+    stats
+        .iter()
+        .map(|item| {
+            row![
                 item.id[0..8],
                 item.label.as_ref().unwrap_or(&item.name),
                 item.ftype,
@@ -331,9 +616,18 @@ impl AsTable for Vec<FieldStats> {
                 item.avg,
                 item.max,
                 item.min
-            ]);
-        }
-        table
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<FieldStats> {
+    fn to_table(&self) -> Table {
+        FIELD_STATS_COLUMNS.table(field_stats_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        FIELD_STATS_COLUMNS.select(columns, field_stats_rows(self))
     }
 }
 impl Sink for Vec<FieldStats> {}
@@ -347,78 +641,405 @@ impl AsTable for Summary {
     }
 }
 impl Sink for Summary {
-    fn write_tabular(value: &Self) -> Result<(), SinkError> {
-        println!("All");
-        value.to_table().printstd();
+    fn write_tabular(out: &mut Output, value: &Self, columns: &[String]) -> Result<(), SinkError> {
+        let table = Self::select_table(value, columns)?;
+        writeln!(out, "All")?;
+        out.print_table(&table)?;
 
-        println!("\nTags");
-        Sink::write_tabular(&value.tag_cloud.without_empty())?;
+        writeln!(out, "\nTags")?;
+        Sink::write_tabular(out, &value.tag_cloud.without_empty(), &[])?;
 
-        println!("\nCategories");
-        Sink::write_tabular(&value.tag_category_cloud.without_empty())?;
+        writeln!(out, "\nCategories")?;
+        Sink::write_tabular(out, &value.tag_category_cloud.without_empty(), &[])?;
 
-        println!("\nCustom Fields");
-        Sink::write_tabular(&value.field_stats)?;
+        writeln!(out, "\nCustom Fields")?;
+        Sink::write_tabular(out, &value.field_stats, &[])?;
 
         Ok(())
     }
 
-    fn write_csv(value: &Self) -> Result<(), SinkError> {
-        value.to_table().to_csv(std::io::stdout())?;
-        println!();
-        Sink::write_csv(&value.tag_cloud.without_empty())?;
-        println!();
-        Sink::write_csv(&value.tag_category_cloud.without_empty())?;
-        println!();
-        Sink::write_csv(&value.field_stats)?;
+    fn write_csv(out: &mut Output, value: &Self, columns: &[String]) -> Result<(), SinkError> {
+        Self::select_table(value, columns)?.to_csv(&mut *out)?;
+        writeln!(out)?;
+        Sink::write_csv(out, &value.tag_cloud.without_empty(), &[])?;
+        writeln!(out)?;
+        Sink::write_csv(out, &value.tag_category_cloud.without_empty(), &[])?;
+        writeln!(out)?;
+        Sink::write_csv(out, &value.field_stats, &[])?;
         Ok(())
     }
 }
 
+const ITEM_URL_COLUMNS: Columns = Columns {
+    names: &["id", "name", "url"],
+    defaults: 3,
+};
+
+fn item_url_rows(urls: &[ItemUrl]) -> Vec<Row> {
+    urls.iter()
+        .map(|u| row![u.id[0..8], u.name, u.url])
+        .collect()
+}
+
+impl AsTable for Vec<ItemUrl> {
+    fn to_table(&self) -> Table {
+        ITEM_URL_COLUMNS.table(item_url_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        ITEM_URL_COLUMNS.select(columns, item_url_rows(self))
+    }
+}
+impl Sink for Vec<ItemUrl> {}
+
+const GROUP_COUNT_COLUMNS: Columns = Columns {
+    names: &["name", "count"],
+    defaults: 2,
+};
+
+fn group_count_rows(groups: &[GroupCount]) -> Vec<Row> {
+    groups.iter().map(|g| row![g.name, g.count]).collect()
+}
+
+impl AsTable for Vec<GroupCount> {
+    fn to_table(&self) -> Table {
+        GROUP_COUNT_COLUMNS.table(group_count_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        GROUP_COUNT_COLUMNS.select(columns, group_count_rows(self))
+    }
+}
+impl Sink for Vec<GroupCount> {}
+
+const QUERY_COUNT_COLUMNS: Columns = Columns {
+    names: &["name", "count", "error", "query"],
+    defaults: 3,
+};
+
+fn query_count_rows(counts: &[QueryCount]) -> Vec<Row> {
+    counts
+        .iter()
+        .map(|c| {
+            row![
+                c.name,
+                c.count.map(|n| n.to_string()).unwrap_or_default(),
+                c.error.as_deref().unwrap_or_default(),
+                c.query
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<QueryCount> {
+    fn to_table(&self) -> Table {
+        QUERY_COUNT_COLUMNS.table(query_count_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        QUERY_COUNT_COLUMNS.select(columns, query_count_rows(self))
+    }
+}
+impl Sink for Vec<QueryCount> {}
+
+impl SearchResult {
+    fn item_rows(&self) -> Vec<Row> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.items.iter())
+            .map(item_row)
+            .collect()
+    }
+}
+
 impl AsTable for SearchResult {
     fn to_table(&self) -> Table {
-        let mut table = mk_table();
-        table.set_titles(row![bFg =>
-            "id",
-            "name",
-            "state",
-            "date",
-            "due",
-            "correspondent",
-            "concerning",
-            "folder",
-            "tags",
-            "fields",
-            "files"
-        ]);
-        for group in &self.groups {
-            for item in &group.items {
-                let tag_list: Vec<String> = item.tags.iter().map(|t| t.name.clone()).collect();
-                let field_list: Vec<String> = item
-                    .customfields
-                    .iter()
-                    .map(|f| format!("{} {}", f.name_or_label(), f.value))
-                    .collect();
-                table.add_row(row![
-                    item.id[0..8],
-                    item.name,
-                    item.state,
-                    format_date(item.date),
-                    item.due_date.map(format_date).unwrap_or_else(|| "".into()),
-                    combine(&item.corr_org, &item.corr_person, "/"),
-                    combine(&item.conc_person, &item.conc_equip, "/"),
-                    item.folder
-                        .as_ref()
-                        .map(|a| a.name.as_str())
-                        .unwrap_or_else(|| ""),
-                    tag_list.join(", "),
-                    field_list.join(", "),
-                    item.attachments.len(),
-                ]);
-            }
+        ITEM_COLUMNS.table(self.item_rows())
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        ITEM_COLUMNS.select(columns, self.item_rows())
+    }
+}
+impl Sink for SearchResult {
+    fn records(value: &Self) -> Result<Vec<serde_json::Value>, SinkError> {
+        let mut records = Vec::new();
+        for item in value.groups.iter().flat_map(|g| g.items.iter()) {
+            records.push(serde_json::to_value(item)?);
         }
+        Ok(records)
+    }
 
-        table
+    /// Selects the fields of each item, the groups are kept.
+    fn project(value: &Self, proj: &mut Projection) -> Result<serde_json::Value, SinkError> {
+        let mut groups = Vec::with_capacity(value.groups.len());
+        for group in &value.groups {
+            groups.push(project_group(group, proj)?);
+        }
+        Ok(serde_json::json!({ "groups": groups }))
+    }
+}
+
+/// Selects the fields of each item of the group.
+pub fn project_group(group: &Group, proj: &mut Projection) -> Result<serde_json::Value, SinkError> {
+    let mut items = Vec::with_capacity(group.items.len());
+    for item in &group.items {
+        items.push(proj.apply(&serde_json::to_value(item)?));
+    }
+    Ok(serde_json::json!({ "name": group.name, "items": items }))
+}
+
+const SAVED_SEARCH_COLUMNS: Columns = Columns {
+    names: &["name", "query", "format", "columns"],
+    defaults: 4,
+};
+
+fn saved_search_rows(searches: &[NamedSearch]) -> Vec<Row> {
+    searches
+        .iter()
+        .map(|s| {
+            row![
+                s.name,
+                s.search.query,
+                s.search
+                    .format
+                    .map(|f| format!("{:?}", f))
+                    .unwrap_or_default(),
+                s.search.columns.join(",")
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for Vec<NamedSearch> {
+    fn to_table(&self) -> Table {
+        SAVED_SEARCH_COLUMNS.table(saved_search_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        SAVED_SEARCH_COLUMNS.select(columns, saved_search_rows(self))
+    }
+}
+impl Sink for Vec<NamedSearch> {}
+
+const DRY_RUN_COLUMNS: Columns = Columns {
+    names: &["file", "exists", "upload", "delete", "move to", "sidecar"],
+    defaults: 5,
+};
+
+fn dry_run_rows(dry_run: &DryRun) -> Vec<Row> {
+    dry_run
+        .files
+        .iter()
+        .map(|f| {
+            row![
+                f.file.display(),
+                f.exists,
+                f.upload,
+                f.delete,
+                f.move_to
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default(),
+                f.sidecar
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or_default()
+            ]
+        })
+        .collect()
+}
+
+impl AsTable for DryRun {
+    fn to_table(&self) -> Table {
+        DRY_RUN_COLUMNS.table(dry_run_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        DRY_RUN_COLUMNS.select(columns, dry_run_rows(self))
+    }
+}
+impl Sink for DryRun {
+    fn write_tabular(out: &mut Output, value: &Self, columns: &[String]) -> Result<(), SinkError> {
+        writeln!(out, "Dry run, nothing is changed.")?;
+        writeln!(out, "Endpoint: {}", value.endpoint)?;
+        if let Some(meta) = &value.meta {
+            writeln!(out, "Metadata: {}", serde_json::to_string(meta)?)?;
+        }
+        out.print_table(&Self::select_table(value, columns)?)?;
+        Ok(())
+    }
+}
+
+const DELETED_ITEM_COLUMNS: Columns = Columns {
+    names: &["id", "name", "deleted", "message"],
+    defaults: 4,
+};
+
+fn deleted_item_rows(items: &[DeletedItem]) -> Vec<Row> {
+    items
+        .iter()
+        .map(|item| row![item.id, item.name, item.deleted, item.message])
+        .collect()
+}
+
+impl AsTable for Vec<DeletedItem> {
+    fn to_table(&self) -> Table {
+        DELETED_ITEM_COLUMNS.table(deleted_item_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        DELETED_ITEM_COLUMNS.select(columns, deleted_item_rows(self))
+    }
+}
+impl Sink for Vec<DeletedItem> {}
+
+const CONFIG_VALUE_COLUMNS: Columns = Columns {
+    names: &["name", "value", "origin"],
+    defaults: 3,
+};
+
+fn config_value_rows(cfg: &EffectiveConfig) -> Vec<Row> {
+    cfg.values
+        .iter()
+        .map(|v| {
+            let value = match &v.value {
+                serde_json::Value::Null => String::new(),
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            row![v.name, value, v.origin.name()]
+        })
+        .collect()
+}
+
+impl AsTable for EffectiveConfig {
+    fn to_table(&self) -> Table {
+        CONFIG_VALUE_COLUMNS.table(config_value_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        CONFIG_VALUE_COLUMNS.select(columns, config_value_rows(self))
+    }
+}
+impl Sink for EffectiveConfig {
+    fn write_tabular(out: &mut Output, value: &Self, columns: &[String]) -> Result<(), SinkError> {
+        match &value.config_file {
+            Some(file) => writeln!(out, "Config file: {}", file.display())?,
+            None => writeln!(out, "Config file: none, using defaults")?,
+        }
+        if let Some(profile) = &value.profile {
+            writeln!(out, "Profile: {}", profile)?;
+        }
+        out.print_table(&Self::select_table(value, columns)?)?;
+        Ok(())
+    }
+}
+
+const CONFIG_ISSUE_COLUMNS: Columns = Columns {
+    names: &["level", "line", "column", "message"],
+    defaults: 4,
+};
+
+fn config_issue_rows(check: &ConfigCheck) -> Vec<Row> {
+    let num = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_default();
+    check
+        .issues
+        .iter()
+        .map(|i| row![i.level.name(), num(i.line), num(i.column), i.message])
+        .collect()
+}
+
+impl AsTable for ConfigCheck {
+    fn to_table(&self) -> Table {
+        CONFIG_ISSUE_COLUMNS.table(config_issue_rows(self))
+    }
+
+    fn to_table_columns(&self, columns: &[String]) -> Result<Table, SinkError> {
+        CONFIG_ISSUE_COLUMNS.select(columns, config_issue_rows(self))
+    }
+}
+impl Sink for ConfigCheck {
+    fn write_tabular(out: &mut Output, value: &Self, columns: &[String]) -> Result<(), SinkError> {
+        if !value.issues.is_empty() {
+            out.print_table(&Self::select_table(value, columns)?)?;
+        }
+        let file = match &value.file {
+            Some(f) => f.display().to_string(),
+            None => "No config file found, the defaults are used".into(),
+        };
+        let state = if value.valid { "ok" } else { "invalid" };
+        writeln!(out, "{}: {}", file, state)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_COLUMNS: Columns = Columns {
+        names: &["a", "b", "c"],
+        defaults: 2,
+    };
+
+    fn test_rows() -> Vec<Row> {
+        vec![row!["a1", "b1", "c1"], row!["a2", "b2", "c2"]]
+    }
+
+    #[test]
+    fn unit_columns_default() {
+        let table = TEST_COLUMNS.table(test_rows());
+        assert_eq!(table.get_row(0).unwrap().len(), 2);
+        assert_eq!(
+            table.get_row(1).unwrap().get_cell(1).unwrap().get_content(),
+            "b2"
+        );
+    }
+
+    #[test]
+    fn unit_columns_select() {
+        let cols = vec!["c".to_string(), "A".to_string()];
+        let table = TEST_COLUMNS.select(&cols, test_rows()).unwrap();
+        assert_eq!(table.get_row(0).unwrap().len(), 2);
+        assert_eq!(
+            table.get_row(0).unwrap().get_cell(0).unwrap().get_content(),
+            "c1"
+        );
+        assert_eq!(
+            table.get_row(0).unwrap().get_cell(1).unwrap().get_content(),
+            "a1"
+        );
+    }
+
+    #[test]
+    fn unit_column_style() {
+        let columns = Columns {
+            names: &["id", "name", "size"],
+            defaults: 3,
+        };
+        let table = columns.table(vec![row!["2wKt", "Invoice", "12"]]);
+        let row = table.get_row(0).unwrap();
+        assert_eq!(
+            row.get_cell(0),
+            Some(&Cell::new("2wKt").with_style(Attr::Dim))
+        );
+        assert_eq!(
+            row.get_cell(1),
+            Some(&Cell::new("Invoice").with_style(Attr::Bold))
+        );
+        assert_eq!(row.get_cell(2), Some(&Cell::new("12")));
+    }
+
+    #[test]
+    fn unit_columns_unknown() {
+        let cols = vec!["x".to_string()];
+        match TEST_COLUMNS.select(&cols, test_rows()) {
+            Err(SinkError::UnknownColumn { name, valid }) => {
+                assert_eq!(name, "x");
+                assert_eq!(valid, "a, b, c");
+            }
+            _ => panic!("Expected an unknown column error"),
+        }
     }
 }
-impl Sink for SearchResult {}
@@ -0,0 +1,146 @@
+//! A simple template to render each result as a line of text.
+//!
+//! Placeholders are written as `{{name}}` and refer to a field of the
+//! json representation of a result. Nested fields and array elements
+//! are accessed via dots, like `{{folder.name}}` or
+//! `{{tags.0.name}}`. Fields containing unix timestamps can be
+//! formatted as date with the `date` filter: `{{date | date}}`.
+
+use serde_json::Value;
+use std::str::FromStr;
+
+use super::sink::Error;
+use super::table::format_date;
+
+/// A parsed template, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field {
+        path: String,
+        filter: Option<Filter>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Filter {
+    Date,
+}
+
+impl Template {
+    /// Renders the template using the fields of `value`. If a field
+    /// doesn't exist, it is rendered as the empty string or results
+    /// in an error if `strict` is true. Fields that are `null` are
+    /// always rendered empty.
+    pub fn render(&self, value: &Value, strict: bool) -> Result<String, Error> {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Field { path, filter } => match lookup(value, path) {
+                    Some(v) => out.push_str(&render_value(v, *filter)),
+                    None if strict => return Err(Error::MissingField { name: path.clone() }),
+                    None => {}
+                },
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| format!("Unclosed placeholder in template: {}", &rest[start..]))?;
+            parts.push(parse_field(&after[..end])?);
+            rest = &after[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        Ok(Template { parts })
+    }
+}
+
+fn parse_field(s: &str) -> Result<Part, String> {
+    let (path, filter) = match s.split_once('|') {
+        Some((path, filter)) => match filter.trim() {
+            "date" => (path.trim(), Some(Filter::Date)),
+            other => return Err(format!("Unknown template filter '{}'", other)),
+        },
+        None => (s.trim(), None),
+    };
+    if path.is_empty() {
+        Err("Empty placeholder in template".into())
+    } else {
+        Ok(Part::Field {
+            path: path.to_string(),
+            filter,
+        })
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |v, key| match v {
+        Value::Object(map) => map.get(key),
+        Value::Array(list) => key.parse::<usize>().ok().and_then(|idx| list.get(idx)),
+        _ => None,
+    })
+}
+
+fn render_value(value: &Value, filter: Option<Filter>) -> String {
+    match (value, filter) {
+        (Value::Number(n), Some(Filter::Date)) if n.is_i64() => {
+            format_date(n.as_i64().unwrap_or_default())
+        }
+        (Value::Null, _) => String::new(),
+        (Value::String(s), _) => s.clone(),
+        (v, _) => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unit_render_template() {
+        let item = json!({
+            "id": "abc",
+            "name": "one",
+            "date": 1599566400000i64,
+            "due_date": null,
+            "tags": [{"name": "Invoice"}]
+        });
+        let tpl: Template = "{{name}} ({{ date | date }}) -> {{id}} {{tags.0.name}}"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            tpl.render(&item, true).unwrap(),
+            "one (2020-09-08) -> abc Invoice"
+        );
+
+        let tpl: Template = "{{due_date}}|{{folder.name}}".parse().unwrap();
+        assert_eq!(tpl.render(&item, false).unwrap(), "|");
+        assert!(tpl.render(&item, true).is_err());
+
+        assert!("{{name".parse::<Template>().is_err());
+        assert!("{{name | upper}}".parse::<Template>().is_err());
+    }
+}
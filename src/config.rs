@@ -1,8 +1,10 @@
 //! Module for reading the configuration file.
 
-use crate::cli::opts::Format;
+use crate::cli::opts::{Direction, Format};
+use crate::http::SessionStore;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
+use std::collections::BTreeMap;
 use std::default;
 use std::path::{Path, PathBuf};
 
@@ -13,9 +15,67 @@ pub struct DsConfig {
     pub default_format: Format,
     pub admin_secret: Option<String>,
     pub default_source_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_source_ids: Vec<String>,
     pub pass_entry: Option<String>,
     pub default_account: Option<String>,
     pub pdf_viewer: Vec<String>,
+    #[serde(default)]
+    pub session_store: SessionStore,
+    #[serde(default)]
+    pub session_file: Option<PathBuf>,
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u64,
+    #[serde(default = "default_read_timeout")]
+    pub read_timeout: u64,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub client_cert: Option<PathBuf>,
+    #[serde(default)]
+    pub client_key: Option<PathBuf>,
+    #[serde(default)]
+    pub ca_cert: Option<PathBuf>,
+    #[serde(default)]
+    pub insecure: bool,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub suppress_version_warning: bool,
+    #[serde(default)]
+    pub upload_cache: Option<PathBuf>,
+    #[serde(default)]
+    pub default_language: Option<String>,
+    #[serde(default)]
+    pub default_direction: Option<Direction>,
+    #[serde(default)]
+    pub default_folder: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub format: BTreeMap<String, Format>,
+    #[serde(default)]
+    pub default_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, Profile>,
+    /// The name of the profile that has been applied.
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    /// The file this config has been read from, if any.
+    #[serde(skip)]
+    pub config_file: Option<PathBuf>,
+}
+
+/// A named set of values overriding the main configuration.
+///
+/// It is defined in a `[profile.NAME]` section of the config file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Profile {
+    pub docspell_url: Option<String>,
+    pub default_source_id: Option<String>,
+    pub session_file: Option<PathBuf>,
 }
 
 /// Error states when reading and writing the config file.
@@ -43,6 +103,21 @@ pub enum ConfigError {
     },
     #[snafu(display("The config directory could not be found"))]
     NoConfigDir,
+    #[snafu(display("The config file {} given by {} does not exist", path.display(), origin))]
+    ConfigNotFound { path: PathBuf, origin: String },
+    #[snafu(display("The config file url '{}' is not a valid local file url", url))]
+    InvalidFileUrl { url: String },
+    #[snafu(display("Cannot resolve the config file {}: {}", path.display(), source))]
+    ResolvePath {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+    #[snafu(display("The profile '{}' is not defined in the config file", name))]
+    UnknownProfile { name: String },
+    #[snafu(display("The env variable '{}' used in '{}' is not set", name, key))]
+    UnsetVariable { name: String, key: String },
+    #[snafu(display("Invalid variable in '{}', expected ${{NAME}}", key))]
+    InvalidVariable { key: String },
 }
 
 impl default::Default for DsConfig {
@@ -52,9 +127,32 @@ impl default::Default for DsConfig {
             default_format: Format::Tabular,
             admin_secret: None,
             default_source_id: None,
+            default_source_ids: vec![],
             pass_entry: None,
             default_account: None,
             pdf_viewer: vec!["zathura".into(), "{}".into()],
+            session_store: SessionStore::File,
+            session_file: None,
+            connect_timeout: default_connect_timeout(),
+            read_timeout: default_read_timeout(),
+            proxy: None,
+            client_cert: None,
+            client_key: None,
+            ca_cert: None,
+            insecure: false,
+            user_agent: None,
+            suppress_version_warning: false,
+            upload_cache: None,
+            default_language: None,
+            default_direction: None,
+            default_folder: None,
+            default_tags: vec![],
+            aliases: BTreeMap::new(),
+            format: BTreeMap::new(),
+            default_profile: None,
+            profile: BTreeMap::new(),
+            active_profile: None,
+            config_file: None,
         }
     }
 }
@@ -68,39 +166,188 @@ impl DsConfig {
     /// default location is used (which is ~/.config/dsc/config.toml`
     /// on linuxes).
     pub fn read(file: Option<&PathBuf>) -> Result<DsConfig, ConfigError> {
-        if let Some(cfg_file) = &file {
-            log::debug!(
-                "Looking for {} in {}",
-                cfg_file.to_path_buf().display(),
-                std::env::current_dir()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_else(|_| "unknown directory".into())
-            );
-            let given_path = cfg_file.as_path().canonicalize().context(ReadFile {
-                path: cfg_file.as_path().to_path_buf(),
-            })?;
-            log::debug!("Load config from: {:}", given_path.display());
-            load_from(&given_path)
-        } else {
-            match std::env::var(DSC_CONFIG).ok() {
-                Some(cfg_file) => {
-                    log::debug!("Loading config file given by env variable");
-                    Self::read(Some(&PathBuf::from(cfg_file)))
-                }
-                None => {
-                    let mut dir = config_dir()?;
-                    dir.push("dsc");
-                    dir.push("config.toml");
-                    if dir.exists() {
-                        log::debug!("Load config from: {:}", dir.display());
-                        load_from(&dir)
-                    } else {
-                        log::debug!("No config file present; using default config");
-                        Ok(DsConfig::default())
-                    }
-                }
+        match Self::locate(file)? {
+            Some(cfg_file) => {
+                log::debug!(
+                    "Looking for {} in {}",
+                    cfg_file.display(),
+                    std::env::current_dir()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_else(|_| "unknown directory".into())
+                );
+                let given_path = cfg_file
+                    .canonicalize()
+                    .context(ReadFile { path: &cfg_file })?;
+                log::debug!("Load config from: {:}", given_path.display());
+                load_from(&given_path)
+            }
+            None => {
+                log::debug!("No config file present; using default config");
+                Ok(DsConfig::default())
+            }
+        }
+    }
+
+    /// Returns the config file that [`DsConfig::read`] would use, or
+    /// `None` if there is no config file and the defaults are used.
+    ///
+    /// A config file given explicitly, via the argument or `DSC_CONFIG`,
+    /// must exist. It may be given as a `file://` url, relative paths
+    /// are resolved against the current directory.
+    pub fn locate(file: Option<&PathBuf>) -> Result<Option<PathBuf>, ConfigError> {
+        if let Some(cfg_file) = file {
+            return explicit_file(&cfg_file.to_string_lossy(), "option --config").map(Some);
+        }
+        match std::env::var(DSC_CONFIG).ok().filter(|s| !s.is_empty()) {
+            Some(cfg_file) => {
+                log::debug!("Using config file given by env variable");
+                explicit_file(&cfg_file, &format!("env variable {}", DSC_CONFIG)).map(Some)
+            }
+            None => {
+                let mut dir = config_dir()?;
+                dir.push("dsc");
+                dir.push("config.toml");
+                Ok(Some(dir).filter(|f| f.exists()))
+            }
+        }
+    }
+
+    /// Applies the values of a profile to this config.
+    ///
+    /// The profile is the one given, or the one from the env variable
+    /// `DSC_PROFILE` or the `default_profile` setting, in this order.
+    /// If neither is set, the config is returned unchanged.
+    pub fn select_profile(self, name: Option<&str>) -> Result<DsConfig, ConfigError> {
+        let name = name
+            .map(String::from)
+            .or_else(|| std::env::var(DSC_PROFILE).ok().filter(|s| !s.is_empty()))
+            .or_else(|| self.default_profile.clone());
+        match name {
+            Some(n) => self.apply_profile(&n),
+            None => Ok(self),
+        }
+    }
+
+    fn apply_profile(mut self, name: &str) -> Result<DsConfig, ConfigError> {
+        let profile =
+            self.profile
+                .get(name)
+                .cloned()
+                .ok_or_else(|| ConfigError::UnknownProfile {
+                    name: name.to_string(),
+                })?;
+        log::debug!("Using profile '{}': {:?}", name, profile);
+        if let Some(url) = profile.docspell_url {
+            self.docspell_url = url;
+        }
+        if profile.default_source_id.is_some() {
+            self.default_source_id = profile.default_source_id;
+            self.default_source_ids.clear();
+        }
+        self.active_profile = Some(name.to_string());
+        Ok(self)
+    }
+
+    /// Replaces references to env variables in the string values.
+    ///
+    /// A `${NAME}` is replaced by the value of the env variable
+    /// `NAME`, which must be set. Use `$$` for a literal `$`. The
+    /// query aliases and values that aren't text or paths are left
+    /// alone.
+    pub fn expand_env(self) -> Result<DsConfig, ConfigError> {
+        self.expand_with(&|name| std::env::var(name).ok())
+    }
+
+    fn expand_with(mut self, lookup: &dyn Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let exp = |key: &str, s: &mut String| -> Result<(), ConfigError> {
+            *s = expand_vars(s, key, lookup)?;
+            Ok(())
+        };
+        let exp_path = |key: &str, p: &mut PathBuf| -> Result<(), ConfigError> {
+            if let Some(s) = p.to_str() {
+                *p = PathBuf::from(expand_vars(s, key, lookup)?);
+            }
+            Ok(())
+        };
+
+        exp("docspell_url", &mut self.docspell_url)?;
+        let strings = [
+            ("admin_secret", &mut self.admin_secret),
+            ("default_source_id", &mut self.default_source_id),
+            ("pass_entry", &mut self.pass_entry),
+            ("default_account", &mut self.default_account),
+            ("proxy", &mut self.proxy),
+            ("user_agent", &mut self.user_agent),
+            ("default_profile", &mut self.default_profile),
+            ("default_language", &mut self.default_language),
+            ("default_folder", &mut self.default_folder),
+        ];
+        for (key, value) in strings {
+            if let Some(s) = value {
+                exp(key, s)?;
+            }
+        }
+        for arg in self.pdf_viewer.iter_mut() {
+            exp("pdf_viewer", arg)?;
+        }
+        for id in self.default_source_ids.iter_mut() {
+            exp("default_source_ids", id)?;
+        }
+        for tag in self.default_tags.iter_mut() {
+            exp("default_tags", tag)?;
+        }
+        let paths = [
+            ("client_cert", &mut self.client_cert),
+            ("client_key", &mut self.client_key),
+            ("ca_cert", &mut self.ca_cert),
+            ("upload_cache", &mut self.upload_cache),
+            ("session_file", &mut self.session_file),
+        ];
+        for (key, value) in paths {
+            if let Some(p) = value {
+                exp_path(key, p)?;
+            }
+        }
+        for (name, profile) in self.profile.iter_mut() {
+            if let Some(url) = &mut profile.docspell_url {
+                exp(&format!("profile.{}.docspell_url", name), url)?;
+            }
+            if let Some(id) = &mut profile.default_source_id {
+                exp(&format!("profile.{}.default_source_id", name), id)?;
+            }
+            if let Some(file) = &mut profile.session_file {
+                exp_path(&format!("profile.{}.session_file", name), file)?;
             }
         }
+        Ok(self)
+    }
+
+    /// Returns the format to use for the given subcommand, if none is
+    /// given via `--format`. This is the one from the `[format]`
+    /// table, or the `default_format` if the subcommand has no entry.
+    pub fn format_for(&self, command: &str) -> Format {
+        self.format
+            .get(command)
+            .copied()
+            .unwrap_or(self.default_format)
+    }
+
+    /// Returns the file to store the session in, if it differs from
+    /// the default location. The `session_file` of the active profile
+    /// is used first, then the one of the main config. Otherwise each
+    /// profile uses its own file.
+    pub fn session_file(&self) -> Option<PathBuf> {
+        let name = self.active_profile.as_ref();
+        if let Some(file) = name.and_then(|n| self.profile.get(n)?.session_file.clone()) {
+            return Some(file);
+        }
+        if let Some(file) = &self.session_file {
+            return Some(file.clone());
+        }
+        let name = name?;
+        config_dir()
+            .ok()
+            .map(|dir| dir.join("dsc").join(format!("dsc-token-{}.json", name)))
     }
 
     /// Write the default configuration to the default config file.
@@ -132,6 +379,14 @@ impl DsConfig {
     }
 }
 
+fn default_connect_timeout() -> u64 {
+    30
+}
+
+fn default_read_timeout() -> u64 {
+    60
+}
+
 fn load_from(file: &Path) -> Result<DsConfig, ConfigError> {
     let cnt = std::fs::read_to_string(file).map_err(|e| ConfigError::ReadFile {
         source: e,
@@ -143,6 +398,90 @@ fn load_from(file: &Path) -> Result<DsConfig, ConfigError> {
             path: file.to_path_buf(),
         })
     })
+    .and_then(DsConfig::expand_env)
+    .map(|cfg| DsConfig {
+        config_file: Some(file.to_path_buf()),
+        ..cfg
+    })
+}
+
+/// Reads the top-level table of the given config file, as it is
+/// written without any defaults applied.
+pub fn read_table(file: &Path) -> Result<toml::value::Table, ConfigError> {
+    let cnt = std::fs::read_to_string(file).context(ReadFile { path: file })?;
+    toml::from_str(&cnt).context(ParseFile { path: file })
+}
+
+/// Replaces `${NAME}` with the value of the variable and `$$` with a
+/// single `$`. Any other `$` is kept as is.
+fn expand_vars(
+    s: &str,
+    key: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String, ConfigError> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(idx) = rest.find('$') {
+        result.push_str(&rest[..idx]);
+        let after = &rest[idx + 1..];
+        if let Some(r) = after.strip_prefix('$') {
+            result.push('$');
+            rest = r;
+        } else if let Some(r) = after.strip_prefix('{') {
+            let end = r.find('}').ok_or_else(|| ConfigError::InvalidVariable {
+                key: key.to_string(),
+            })?;
+            let name = &r[..end];
+            if name.is_empty() {
+                return Err(ConfigError::InvalidVariable {
+                    key: key.to_string(),
+                });
+            }
+            let value = lookup(name).ok_or_else(|| ConfigError::UnsetVariable {
+                name: name.to_string(),
+                key: key.to_string(),
+            })?;
+            result.push_str(&value);
+            rest = &r[end + 1..];
+        } else {
+            result.push('$');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Returns the absolute path of a config file that is given
+/// explicitly, failing if it doesn't exist. The `origin` tells where
+/// it was given for the error message.
+fn explicit_file(raw: &str, origin: &str) -> Result<PathBuf, ConfigError> {
+    let path = resolve_file(raw)?;
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(ConfigError::ConfigNotFound {
+            path,
+            origin: origin.into(),
+        })
+    }
+}
+
+fn resolve_file(raw: &str) -> Result<PathBuf, ConfigError> {
+    let path = if raw.starts_with("file://") {
+        reqwest::Url::parse(raw)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+            .ok_or_else(|| ConfigError::InvalidFileUrl { url: raw.into() })?
+    } else {
+        PathBuf::from(raw)
+    };
+    if path.is_relative() {
+        let dir = std::env::current_dir().context(ResolvePath { path: &path })?;
+        Ok(dir.join(path))
+    } else {
+        Ok(path)
+    }
 }
 
 fn config_dir() -> Result<PathBuf, ConfigError> {
@@ -167,11 +506,92 @@ fn write_to(cfg: &DsConfig, file: &Path) -> Result<(), ConfigError> {
     });
 
     cnt.and_then(|c| {
-        std::fs::write(&file, &c).map_err(|e| ConfigError::CreateDefault {
+        std::fs::write(file, &c).map_err(|e| ConfigError::CreateDefault {
             source: e,
             path: file.to_path_buf(),
         })
     })
 }
 
-const DSC_CONFIG: &str = "DSC_CONFIG";
+pub const DSC_CONFIG: &str = "DSC_CONFIG";
+pub const DSC_PROFILE: &str = "DSC_PROFILE";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_resolve_file() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(resolve_file("dsc.toml").unwrap(), cwd.join("dsc.toml"));
+        assert_eq!(
+            resolve_file("file:///etc/dsc/my%20config.toml").unwrap(),
+            PathBuf::from("/etc/dsc/my config.toml")
+        );
+        assert!(resolve_file("file://host/dsc.toml").is_err());
+        assert!(matches!(
+            explicit_file("does-not-exist.toml", "test"),
+            Err(ConfigError::ConfigNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn unit_apply_profile() {
+        let cfg: DsConfig = toml::from_str(
+            r#"
+docspell_url = "http://localhost:7880"
+default_format = "Tabular"
+default_source_id = "main-source"
+pdf_viewer = []
+
+[profile.test]
+docspell_url = "http://test:7880"
+"#,
+        )
+        .unwrap();
+        let cfg = cfg.apply_profile("test").unwrap();
+        assert_eq!(cfg.docspell_url, "http://test:7880");
+        assert_eq!(cfg.default_source_id, Some("main-source".into()));
+        assert_eq!(cfg.active_profile, Some("test".into()));
+        assert!(cfg.apply_profile("other").is_err());
+    }
+
+    #[test]
+    fn unit_format_for_command() {
+        let cfg: DsConfig = toml::from_str(
+            r#"
+docspell_url = "http://localhost:7880"
+default_format = "Csv"
+pdf_viewer = []
+
+[format]
+search = "tabular"
+item = "Json"
+"#,
+        )
+        .unwrap();
+        assert!(matches!(cfg.format_for("search"), Format::Tabular));
+        assert!(matches!(cfg.format_for("item"), Format::Json));
+        assert!(matches!(cfg.format_for("tag"), Format::Csv));
+    }
+
+    #[test]
+    fn unit_expand_vars() {
+        let lookup = |name: &str| match name {
+            "TOKEN" => Some("abc".to_string()),
+            _ => None,
+        };
+        let exp = |s| expand_vars(s, "key", &lookup);
+        assert_eq!(exp("${TOKEN}").unwrap(), "abc");
+        assert_eq!(exp("a-${TOKEN}-b").unwrap(), "a-abc-b");
+        assert_eq!(exp("$${TOKEN} $x").unwrap(), "${TOKEN} $x");
+        assert!(matches!(
+            exp("${OTHER}"),
+            Err(ConfigError::UnsetVariable { .. })
+        ));
+        assert!(matches!(
+            exp("${TOKEN"),
+            Err(ConfigError::InvalidVariable { .. })
+        ));
+    }
+}
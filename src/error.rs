@@ -1,15 +1,255 @@
 //! Global error types.
 
-use crate::cli::cmd;
+use crate::cli::cmd::{self, admin, folder, item, tag};
+use crate::cli::sink;
 use crate::config;
+use crate::http::{self, SessionError};
+use serde::Serialize;
 use snafu::Snafu;
 
+/// The exit codes of the `dsc` binary.
+///
+/// Scripts can use them to react differently to, for example,
+/// authentication and network failures.
+pub mod exit_code {
+    /// The command succeeded.
+    pub const SUCCESS: i32 = 0;
+    /// Any error without a more specific code.
+    pub const GENERIC: i32 = 1;
+    /// The command was invoked with invalid arguments.
+    pub const USAGE: i32 = 2;
+    /// Not logged in, the session expired or access was denied.
+    pub const AUTH: i32 = 3;
+    /// The requested item or other entity doesn't exist.
+    pub const NOT_FOUND: i32 = 4;
+    /// The server could not be reached or didn't answer in time.
+    pub const NETWORK: i32 = 5;
+}
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     Cmd { source: cmd::CmdError },
     Config { source: config::ConfigError },
 }
 
+impl Error {
+    /// Returns the exit code for this error.
+    ///
+    /// The chain of sources is searched for the first error that
+    /// determines a specific code, so that for example a 401 response
+    /// results in [`exit_code::AUTH`] for every command.
+    pub fn exit_code(&self) -> i32 {
+        let mut current: Option<&(dyn std::error::Error + 'static)> = Some(self);
+        while let Some(err) = current {
+            if let Some(code) = specific_code(err) {
+                return code;
+            }
+            current = err.source();
+        }
+        exit_code::GENERIC
+    }
+}
+
+/// An error as it is printed for the json formats, so that scripts
+/// can tell the cause without parsing the message.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    /// The complete message, as printed for the other formats.
+    pub error: String,
+    /// One of `usage`, `auth`, `not-found`, `network`, `config` or
+    /// `error` for everything else.
+    pub kind: &'static str,
+    pub exit_code: i32,
+    /// The status of the http response causing the error, if any.
+    pub http_status: Option<u16>,
+    /// The message of the innermost error.
+    pub detail: String,
+}
+
+impl Error {
+    /// Returns the report of this error for machine readable output.
+    pub fn report(&self) -> ErrorReport {
+        let exit_code = self.exit_code();
+        let kind = match exit_code {
+            exit_code::USAGE => "usage",
+            exit_code::AUTH => "auth",
+            exit_code::NOT_FOUND => "not-found",
+            exit_code::NETWORK => "network",
+            _ if matches!(self, Error::Config { .. }) => "config",
+            _ => "error",
+        };
+        let mut http_status = None;
+        let mut innermost: &(dyn std::error::Error + 'static) = self;
+        let mut current: Option<&(dyn std::error::Error + 'static)> = Some(self);
+        while let Some(err) = current {
+            http_status = http_status.or_else(|| status_of(err));
+            innermost = err;
+            current = err.source();
+        }
+        ErrorReport {
+            error: self.to_string(),
+            kind,
+            exit_code,
+            http_status,
+            detail: innermost.to_string(),
+        }
+    }
+}
+
+/// Returns whether the error is caused by a connection error or a
+/// timeout.
+fn is_network(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(err);
+    while let Some(err) = current {
+        if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+            if e.is_connect() || e.is_timeout() {
+                return true;
+            }
+        }
+        current = err.source();
+    }
+    false
+}
+
+fn status_of(err: &(dyn std::error::Error + 'static)) -> Option<u16> {
+    if let Some(http::Error::UnexpectedStatus { status, .. }) = err.downcast_ref::<http::Error>() {
+        Some(*status)
+    } else {
+        err.downcast_ref::<reqwest::Error>()
+            .and_then(|e| e.status())
+            .map(|s| s.as_u16())
+    }
+}
+
+fn specific_code(err: &(dyn std::error::Error + 'static)) -> Option<i32> {
+    if let Some(cmd::CmdError::Offline { .. }) = err.downcast_ref::<cmd::CmdError>() {
+        Some(exit_code::NETWORK)
+    } else if let Some(e) = err.downcast_ref::<http::Error>() {
+        match e {
+            http::Error::LoginFailed | http::Error::IntEndpointAuth { .. } => Some(exit_code::AUTH),
+            http::Error::UnexpectedStatus { status, .. } => status_code(*status),
+            http::Error::ItemNotFound { .. } => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<SessionError>() {
+        match e {
+            SessionError::NoSessionFile
+            | SessionError::NotLoggedIn
+            | SessionError::InvalidAuthToken { .. } => Some(exit_code::AUTH),
+            // the cause is found further down, if the server is unreachable
+            SessionError::RefreshSession { source } if !is_network(source.as_ref()) => {
+                Some(exit_code::AUTH)
+            }
+            SessionError::ReadSessionFile { source, .. }
+                if source.kind() == std::io::ErrorKind::NotFound =>
+            {
+                Some(exit_code::AUTH)
+            }
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+        match e.status() {
+            Some(status) => status_code(status.as_u16()),
+            None if e.is_timeout() || e.is_connect() => Some(exit_code::NETWORK),
+            None => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::set::Error>() {
+        match e {
+            item::set::Error::ItemNotFound | item::set::Error::FolderNotFound { .. } => {
+                Some(exit_code::NOT_FOUND)
+            }
+            item::set::Error::NoChanges => Some(exit_code::USAGE),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::set_folder::Error>() {
+        match e {
+            item::set_folder::Error::FolderNotFound { .. } => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::ids::Error>() {
+        match e {
+            item::ids::Error::IdNotFound { .. } => Some(exit_code::NOT_FOUND),
+            item::ids::Error::InvalidId { .. } => Some(exit_code::USAGE),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::get::Error>() {
+        match e {
+            item::get::Error::ItemNotFound => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::url::Error>() {
+        match e {
+            item::url::Error::ItemNotFound { .. } => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::export::Error>() {
+        match e {
+            item::export::Error::ItemNotFound { .. } => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::delete::Error>() {
+        match e {
+            item::delete::Error::ConfirmRequired => Some(exit_code::USAGE),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::attachment::FindError>() {
+        match e {
+            item::attachment::FindError::ItemNotFound { .. }
+            | item::attachment::FindError::AttachmentNotFound { .. }
+            | item::attachment::FindError::NotInItem { .. } => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<item::empty_trash::Error>() {
+        match e {
+            item::empty_trash::Error::ConfirmRequired => Some(exit_code::USAGE),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<admin::reprocess::Error>() {
+        match e {
+            admin::reprocess::Error::ConfirmRequired => Some(exit_code::USAGE),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<tag::rename::Error>() {
+        match e {
+            tag::rename::Error::TagNotFound { .. } => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<tag::delete::Error>() {
+        match e {
+            tag::delete::Error::TagNotFound { .. } => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<folder::delete::Error>() {
+        match e {
+            folder::delete::Error::FolderNotFound { .. } => Some(exit_code::NOT_FOUND),
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<sink::Error>() {
+        match e {
+            sink::Error::UnknownColumn { .. } | sink::Error::ColumnsNotSupported => {
+                Some(exit_code::USAGE)
+            }
+            _ => None,
+        }
+    } else if let Some(e) = err.downcast_ref::<config::ConfigError>() {
+        match e {
+            config::ConfigError::UnknownProfile { .. } => Some(exit_code::USAGE),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+fn status_code(status: u16) -> Option<i32> {
+    match status {
+        401 | 403 => Some(exit_code::AUTH),
+        404 => Some(exit_code::NOT_FOUND),
+        408 | 504 => Some(exit_code::NETWORK),
+        _ => None,
+    }
+}
+
 impl From<config::ConfigError> for Error {
     fn from(e: config::ConfigError) -> Error {
         Error::Config { source: e }
@@ -23,3 +263,75 @@ impl From<cmd::CmdError> for Error {
 }
 
 pub type Result<A> = std::result::Result<A, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn http_error(err: http::Error) -> Error {
+        Error::Cmd {
+            source: cmd::CmdError::HttpClient { source: err },
+        }
+    }
+
+    #[test]
+    fn unit_exit_code() {
+        let unauthorized = http_error(http::Error::UnexpectedStatus {
+            status: 401,
+            url: "/".into(),
+        });
+        assert_eq!(unauthorized.exit_code(), exit_code::AUTH);
+        let not_found = http_error(http::Error::ItemNotFound { id: "x".into() });
+        assert_eq!(not_found.exit_code(), exit_code::NOT_FOUND);
+        let other = http_error(http::Error::UnexpectedStatus {
+            status: 500,
+            url: "/".into(),
+        });
+        assert_eq!(other.exit_code(), exit_code::GENERIC);
+        let offline = Error::Cmd {
+            source: cmd::CmdError::Offline { command: "search" },
+        };
+        assert_eq!(offline.exit_code(), exit_code::NETWORK);
+    }
+
+    #[test]
+    fn unit_exit_code_refresh_session() {
+        let refresh = |source: http::Error| {
+            http_error(http::Error::Session {
+                source: SessionError::RefreshSession {
+                    source: Box::new(source),
+                },
+            })
+        };
+        assert_eq!(
+            refresh(http::Error::LoginFailed).exit_code(),
+            exit_code::AUTH
+        );
+        let refused = reqwest::blocking::get("http://127.0.0.1:1").unwrap_err();
+        let unreachable = refresh(http::Error::Http {
+            source: refused,
+            url: "http://127.0.0.1:1".into(),
+        });
+        assert_eq!(unreachable.exit_code(), exit_code::NETWORK);
+        assert_eq!(unreachable.report().kind, "network");
+    }
+
+    #[test]
+    fn unit_error_report() {
+        let report = http_error(http::Error::UnexpectedStatus {
+            status: 404,
+            url: "/".into(),
+        })
+        .report();
+        assert_eq!(report.kind, "not-found");
+        assert_eq!(report.exit_code, exit_code::NOT_FOUND);
+        assert_eq!(report.http_status, Some(404));
+        assert_eq!(report.detail, "Unexpected response status: 404");
+        let report = Error::Config {
+            source: config::ConfigError::NoConfigDir,
+        }
+        .report();
+        assert_eq!(report.kind, "config");
+        assert_eq!(report.http_status, None);
+    }
+}
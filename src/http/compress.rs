@@ -0,0 +1,141 @@
+//! Compressing request bodies with gzip.
+//!
+//! Uploads may be sent with `Content-Encoding: gzip`, which helps
+//! for text based documents. Files in formats that are compressed
+//! already, like pdf, jpg or zip, are not worth compressing again.
+//! Their type is guessed from the file extension and, if it is not
+//! known, from the first bytes of the file.
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::blocking::{Body, Request};
+use reqwest::header::{HeaderValue, CONTENT_ENCODING};
+use snafu::{ResultExt, Snafu};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// How many bytes of a file are looked at to detect its type.
+pub const SNIFF_LEN: usize = 8192;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Cannot read the request body: {}", source))]
+    ReadBody { source: reqwest::Error },
+
+    #[snafu(display("Cannot compress the request body: {}", source))]
+    Encode { source: std::io::Error },
+}
+
+/// Returns whether the content of a file with the given name and
+/// first bytes is compressed already.
+pub fn is_compressed(name: &str, head: &[u8]) -> bool {
+    match mime_guess::from_path(name).first() {
+        Some(mime) => is_compressed_mime(mime.essence_str()),
+        None => infer::get(head).is_some_and(|kind| is_compressed_mime(kind.mime_type())),
+    }
+}
+
+/// Like [`is_compressed`] for a file on disk. Files that cannot be
+/// read are treated as not compressed.
+pub fn is_compressed_file(path: &Path) -> bool {
+    let mut head = Vec::new();
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(SNIFF_LEN as u64).read_to_end(&mut head);
+    }
+    is_compressed(&path.display().to_string(), &head)
+}
+
+fn is_compressed_mime(mime: &str) -> bool {
+    let (kind, sub) = mime.split_once('/').unwrap_or((mime, ""));
+    match kind {
+        "image" => !matches!(sub, "bmp" | "tiff" | "svg+xml" | "x-icon"),
+        "audio" | "video" => true,
+        "application" => {
+            matches!(
+                sub,
+                "pdf"
+                    | "zip"
+                    | "gzip"
+                    | "x-gzip"
+                    | "x-bzip2"
+                    | "x-xz"
+                    | "x-7z-compressed"
+                    | "vnd.rar"
+                    | "x-rar-compressed"
+                    | "zstd"
+                    | "epub+zip"
+            ) || sub.starts_with("vnd.openxmlformats")
+                || sub.starts_with("vnd.oasis.opendocument")
+        }
+        _ => false,
+    }
+}
+
+/// Replaces the body of the request with its gzip compressed form
+/// and sets the `Content-Encoding` header. The savings are logged.
+pub fn gzip_body(req: &mut Request) -> Result<(), Error> {
+    let body = match req.body_mut() {
+        Some(body) => body,
+        None => return Ok(()),
+    };
+    let data = body.buffer().context(ReadBody)?;
+    let mut enc = GzEncoder::new(Vec::with_capacity(data.len() / 2), Compression::default());
+    enc.write_all(data).context(Encode)?;
+    let gz = enc.finish().context(Encode)?;
+    log::info!(
+        "Compressed request body from {} to {} bytes ({}% saved)",
+        data.len(),
+        gz.len(),
+        savings(data.len(), gz.len())
+    );
+    *body = Body::from(gz);
+    req.headers_mut()
+        .insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+    Ok(())
+}
+
+fn savings(before: usize, after: usize) -> i64 {
+    if before == 0 {
+        0
+    } else {
+        (before as i64 - after as i64) * 100 / before as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_detect_compressed() {
+        assert!(is_compressed("scan.pdf", b""));
+        assert!(is_compressed("photo.JPG", b""));
+        assert!(is_compressed("files.zip", b""));
+        assert!(is_compressed("letter.docx", b""));
+        assert!(!is_compressed("notes.txt", b""));
+        assert!(!is_compressed("mail.eml", b"From: me"));
+        assert!(is_compressed("scan", b"%PDF-1.4\n"));
+        assert!(!is_compressed("notes", b"just some text"));
+    }
+
+    #[test]
+    fn unit_gzip_body() {
+        let data = "some text that compresses well ".repeat(100);
+        let mut req = reqwest::blocking::Client::new()
+            .post("http://localhost/upload")
+            .body(data.clone())
+            .build()
+            .unwrap();
+        gzip_body(&mut req).unwrap();
+        assert_eq!(req.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+        let gz = req.body().and_then(|b| b.as_bytes()).unwrap();
+        assert!(gz.len() < data.len());
+        let mut plain = String::new();
+        flate2::read::GzDecoder::new(gz)
+            .read_to_string(&mut plain)
+            .unwrap();
+        assert_eq!(plain, data);
+    }
+}
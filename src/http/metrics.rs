@@ -0,0 +1,82 @@
+//! Measuring the time of each request.
+//!
+//! Every request is logged at debug level with its method, path,
+//! status, the time until the response arrived and the size of the
+//! response as given by the server. The totals of all requests are
+//! logged when the command completes, see [`log_total`].
+
+use indicatif::HumanBytes;
+use reqwest::blocking::Response;
+use reqwest::Method;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct Totals {
+    requests: u64,
+    failed: u64,
+    elapsed: Duration,
+    bytes: u64,
+}
+
+static TOTALS: Mutex<Totals> = Mutex::new(Totals {
+    requests: 0,
+    failed: 0,
+    elapsed: Duration::from_secs(0),
+    bytes: 0,
+});
+
+/// Records a request that took `elapsed` until the response (or the
+/// error) arrived.
+pub fn record(
+    method: &Method,
+    path: &str,
+    result: &Result<Response, reqwest::Error>,
+    elapsed: Duration,
+) {
+    let size = result.as_ref().ok().and_then(|r| r.content_length());
+    {
+        let mut totals = TOTALS.lock().unwrap();
+        totals.requests += 1;
+        totals.elapsed += elapsed;
+        totals.bytes += size.unwrap_or(0);
+        if result
+            .as_ref()
+            .map(|r| !r.status().is_success())
+            .unwrap_or(true)
+        {
+            totals.failed += 1;
+        }
+    }
+    if log::log_enabled!(log::Level::Debug) {
+        let status = match result {
+            Ok(resp) => resp.status().as_u16().to_string(),
+            Err(_) => "error".into(),
+        };
+        let size = size
+            .map(|n| HumanBytes(n).to_string())
+            .unwrap_or_else(|| "unknown size".into());
+        log::debug!(
+            "{} {} -> {} in {} ms, {}",
+            method,
+            path,
+            status,
+            elapsed.as_millis(),
+            size
+        );
+    }
+}
+
+/// Logs the number of requests, their total time and size.
+pub fn log_total() {
+    let totals = TOTALS.lock().unwrap();
+    if totals.requests > 0 {
+        log::debug!(
+            "{} request(s), {} failed, took {} ms in total, {} received",
+            totals.requests,
+            totals.failed,
+            totals.elapsed.as_millis(),
+            HumanBytes(totals.bytes)
+        );
+    }
+}
@@ -1,8 +1,9 @@
 //! Defines payloads for requests and responses and their `De/Serialize` instances.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ItemDetail {
     pub id: String,
     pub direction: String,
@@ -31,7 +32,7 @@ pub struct ItemDetail {
     pub customfields: Vec<CustomField>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Attachment {
     pub id: String,
     pub name: Option<String>,
@@ -50,12 +51,12 @@ impl Attachment {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Account {
     pub account: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ResetPasswordResp {
     pub success: bool,
     pub message: String,
@@ -63,7 +64,7 @@ pub struct ResetPasswordResp {
     pub new_password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct AuthRequest {
     #[serde(alias = "account")]
     pub account: String,
@@ -73,7 +74,7 @@ pub struct AuthRequest {
     pub remember_me: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct AuthResp {
     pub collective: String,
     pub user: String,
@@ -84,7 +85,17 @@ pub struct AuthResp {
     pub valid_ms: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// The account of the current session and the lifetime of its
+/// token, given as unix timestamps in millis.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SessionInfo {
+    pub collective: String,
+    pub user: String,
+    pub created: u64,
+    pub expires: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct UploadMeta {
     pub multiple: bool,
     pub direction: Option<String>,
@@ -103,12 +114,68 @@ pub struct UploadMeta {
     pub attachments_only: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct StringList {
     pub items: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A list of ids, for example of items.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct IdList {
+    pub ids: Vec<String>,
+}
+
+/// A list of item ids together with references (ids or names) to
+/// other entities, like tags.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ItemsAndRefs {
+    pub items: Vec<String>,
+    pub refs: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ItemsAndRef {
+    pub items: Vec<String>,
+    #[serde(rename = "ref")]
+    pub item_ref: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OptionalText {
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct MoveAttachment {
+    pub source: String,
+    pub target: String,
+}
+
+/// When and which items to remove from the trash. The `min_age` is
+/// given in milliseconds.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct EmptyTrashSetting {
+    pub schedule: String,
+    #[serde(alias = "minAge", rename(serialize = "minAge"))]
+    pub min_age: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OptionalDate {
+    pub date: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OptionalId {
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct DirectionValue {
+    pub direction: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Registration {
     #[serde(alias = "collectiveName", rename(serialize = "collectiveName"))]
     pub collective_name: String,
@@ -117,36 +184,36 @@ pub struct Registration {
     pub invite: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct InviteResult {
     pub success: bool,
     pub message: String,
     pub key: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct GenInvite {
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SourceList {
     pub items: Vec<SourceAndTags>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SourceAndTags {
     pub source: Source,
     pub tags: TagList,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct TagList {
     pub count: u32,
     pub items: Vec<Tag>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Source {
     pub id: String,
     pub abbrev: String,
@@ -160,14 +227,45 @@ pub struct Source {
     pub created: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A source to create, together with the names of tags to add to
+/// every item uploaded via it.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SourceTagIn {
+    pub source: NewSource,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct NewSource {
+    pub id: String,
+    pub abbrev: String,
+    pub description: Option<String>,
+    pub counter: u32,
+    pub enabled: bool,
+    pub priority: String,
+    pub folder: Option<String>,
+    #[serde(alias = "fileFilter", rename(serialize = "fileFilter"))]
+    pub file_filter: Option<String>,
+    pub language: Option<String>,
+    pub created: i64,
+    #[serde(alias = "attachmentsOnly", rename(serialize = "attachmentsOnly"))]
+    pub attachments_only: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CheckFileResult {
     pub exists: bool,
     pub items: Vec<ItemShort>,
     pub file: Option<String>,
+    /// The SHA-256 of the file, which is set by the client.
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// Why the file could not be checked, which is set by the client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ItemShort {
     pub id: String,
     pub name: String,
@@ -178,13 +276,67 @@ pub struct ItemShort {
     pub item_date: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct BasicResult {
     pub success: bool,
     pub message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The jobs of the collective, as shown in docspell's processing
+/// queue.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct JobQueueState {
+    pub progress: Vec<JobDetail>,
+    pub completed: Vec<JobDetail>,
+    pub queued: Vec<JobDetail>,
+}
+
+impl JobQueueState {
+    pub fn jobs(&self) -> impl Iterator<Item = &JobDetail> {
+        self.progress
+            .iter()
+            .chain(self.queued.iter())
+            .chain(self.completed.iter())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct JobDetail {
+    pub id: String,
+    pub subject: String,
+    pub state: String,
+    #[serde(default)]
+    pub submitted: i64,
+    #[serde(default)]
+    pub retries: i32,
+    /// The progress in percent, if the job reports it.
+    #[serde(default)]
+    pub progress: Option<i32>,
+}
+
+impl JobDetail {
+    /// Whether the job is done, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.state.to_lowercase().as_str(),
+            "success" | "failed" | "cancelled"
+        )
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.state.eq_ignore_ascii_case("success")
+    }
+}
+
+/// The result of creating something, with the id of the new entity.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IdResult {
+    pub success: bool,
+    pub message: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct VersionInfo {
     pub version: String,
     #[serde(alias = "builtAtMillis")]
@@ -197,7 +349,7 @@ pub struct VersionInfo {
     pub git_version: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct BuildInfo {
     pub build_date: &'static str,
     pub build_version: &'static str,
@@ -222,7 +374,7 @@ impl Default for BuildInfo {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Tag {
     pub id: String,
     pub name: String,
@@ -230,24 +382,31 @@ pub struct Tag {
     pub created: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct IdName {
     pub id: String,
     pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A list of entities like organizations or persons, where only the
+/// id and name is of interest.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct IdNameList {
+    pub items: Vec<IdName>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct TagCount {
     pub tag: Tag,
     pub count: u32,
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CatCount {
     pub name: Option<String>,
     pub count: u32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct TagCloud {
     pub items: Vec<TagCount>,
 }
@@ -257,7 +416,7 @@ impl TagCloud {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CatCloud {
     pub items: Vec<CatCount>,
 }
@@ -267,7 +426,7 @@ impl CatCloud {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct FieldStats {
     pub id: String,
     pub name: String,
@@ -280,7 +439,7 @@ pub struct FieldStats {
     pub min: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct FolderStats {
     pub id: String,
     pub name: String,
@@ -288,7 +447,29 @@ pub struct FolderStats {
     pub count: u32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FolderItem {
+    pub id: String,
+    pub name: String,
+    pub owner: IdName,
+    pub created: i64,
+    #[serde(alias = "isMember", default)]
+    pub is_member: bool,
+    #[serde(alias = "memberCount", default)]
+    pub member_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct NewFolder {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FolderList {
+    pub items: Vec<FolderItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Summary {
     pub count: u32,
     #[serde(alias = "tagCloud")]
@@ -301,7 +482,7 @@ pub struct Summary {
     pub folder_stats: Vec<FolderStats>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Attach {
     pub id: String,
     pub position: u32,
@@ -319,7 +500,7 @@ impl Attach {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct CustomField {
     pub id: String,
     pub name: String,
@@ -333,19 +514,33 @@ impl CustomField {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CustomFieldValue {
     pub field: String,
     pub value: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// The definition of a custom field of a collective.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CustomFieldDef {
+    pub id: String,
+    pub name: String,
+    pub label: Option<String>,
+    pub ftype: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CustomFieldList {
+    pub items: Vec<CustomFieldDef>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Highlight {
     pub name: String,
     pub lines: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Item {
     pub id: String,
     pub name: String,
@@ -371,18 +566,18 @@ pub struct Item {
     pub highlighting: Vec<Highlight>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Group {
     pub name: String,
     pub items: Vec<Item>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct SearchResult {
     pub groups: Vec<Group>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SearchReq {
     pub offset: u32,
     pub limit: u32,
@@ -393,7 +588,7 @@ pub struct SearchReq {
     pub search_mode: SearchMode,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub enum SearchMode {
     Normal,
     Trashed,
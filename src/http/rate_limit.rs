@@ -0,0 +1,80 @@
+//! Limiting the number of requests per second.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A token bucket that allows a number of requests per second.
+///
+/// It is shared by all threads using the same client, so the limit
+/// applies to all requests together. The bucket holds at most one
+/// token, which means requests are spread evenly instead of being
+/// sent in bursts.
+#[derive(Debug)]
+pub struct RateLimit {
+    per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimit {
+    /// Creates a limit of `per_second` requests, which must be
+    /// positive.
+    pub fn new(per_second: f64) -> RateLimit {
+        RateLimit {
+            per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: 1.0,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Blocks until the next request may be sent.
+    pub fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if wait > Duration::ZERO {
+            log::debug!("Rate limit reached, waiting {:?}", wait);
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Takes a token and returns how long to wait until it is
+    /// available. The bucket may go negative, so waiting threads are
+    /// served in order.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(1.0);
+        bucket.updated = now;
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.per_second)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_reserve_spreads_requests() {
+        let limit = RateLimit::new(4.0);
+        let now = Instant::now();
+        limit.bucket.lock().unwrap().updated = now;
+        assert_eq!(limit.reserve(now), Duration::ZERO);
+        assert_eq!(limit.reserve(now), Duration::from_millis(250));
+        assert_eq!(limit.reserve(now), Duration::from_millis(500));
+        // the bucket is full again, but holds only one token
+        let later = now + Duration::from_secs(2);
+        assert_eq!(limit.reserve(later), Duration::ZERO);
+        assert_eq!(limit.reserve(later), Duration::from_millis(250));
+    }
+}
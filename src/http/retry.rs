@@ -0,0 +1,172 @@
+//! Retrying requests that failed due to transient errors.
+
+use chrono::DateTime;
+use reqwest::blocking::Response;
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Defines how often and how long to wait before retrying a request.
+///
+/// Only connection errors, timeouts and responses with status 5xx or
+/// 429 are retried. The delay is doubled with each attempt and a
+/// random amount of up to half of it is added.
+///
+/// If the response has a `Retry-After` header, the time it asks for
+/// is waited instead, but at most `max_retry_after`.
+#[derive(Debug, Clone)]
+pub struct Retry {
+    pub max_retries: u32,
+    pub delay: Duration,
+    pub max_retry_after: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            max_retries: 0,
+            delay: Duration::from_millis(500),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Retry {
+    pub fn new(max_retries: u32, delay: Duration) -> Retry {
+        Retry {
+            max_retries,
+            delay,
+            ..Retry::default()
+        }
+    }
+
+    pub fn with_max_retry_after(self, max_retry_after: Duration) -> Retry {
+        Retry {
+            max_retry_after,
+            ..self
+        }
+    }
+
+    /// Checks the outcome of the `attempt`-th retry (starting with 0)
+    /// and returns how long to wait before the next attempt. If
+    /// `None` is returned, the result should be used as is.
+    pub fn next_delay(
+        &self,
+        attempt: u32,
+        result: &Result<Response, reqwest::Error>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        let (reason, asked) = match result {
+            Ok(resp) if is_transient_status(resp.status()) => {
+                let asked = resp
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| parse_retry_after(v, SystemTime::now()));
+                (format!("status {}", resp.status()), asked)
+            }
+            Ok(_) => return None,
+            Err(err) if is_transient_error(err) => (err.to_string(), None),
+            Err(_) => return None,
+        };
+        let wait = match asked {
+            Some(d) if d > self.max_retry_after => {
+                log::info!(
+                    "Server asks to retry after {:?}, waiting only {:?}",
+                    d,
+                    self.max_retry_after
+                );
+                self.max_retry_after
+            }
+            Some(d) => d,
+            None => self.backoff(attempt),
+        };
+        log::info!(
+            "Request failed ({}), retrying in {:?} ({}/{})",
+            reason,
+            wait,
+            attempt + 1,
+            self.max_retries
+        );
+        Some(wait)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.delay.saturating_mul(2u32.saturating_pow(attempt));
+        let jitter_max = base.as_millis() as u64 / 2;
+        let jitter = if jitter_max > 0 {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.subsec_nanos() as u64)
+                .unwrap_or(0);
+            nanos % jitter_max
+        } else {
+            0
+        };
+        base + Duration::from_millis(jitter)
+    }
+}
+
+/// Parses the value of a `Retry-After` header, which is either a
+/// number of seconds or a http date. A date in the past means to
+/// retry immediately.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let at = UNIX_EPOCH + Duration::from_secs(date.timestamp().max(0) as u64);
+    Some(at.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn is_transient_error(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout() || err.is_request()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_backoff_doubles() {
+        let retry = Retry::new(3, Duration::from_millis(100));
+        for attempt in 0..3 {
+            let base = 100 * 2u64.pow(attempt);
+            let wait = retry.backoff(attempt).as_millis() as u64;
+            assert!(wait >= base && wait < base + base / 2 + 1);
+        }
+    }
+
+    #[test]
+    fn unit_parse_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(1445412480);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn unit_transient_status() {
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient_status(StatusCode::BAD_REQUEST));
+        assert!(!is_transient_status(StatusCode::CONFLICT));
+    }
+}
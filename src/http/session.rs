@@ -3,8 +3,8 @@
 //! Docspell returns an authentication token for a login via account +
 //! password. This token must be used for all secured endpoints.
 //!
-//! This token is stored on disk and also refreshed if it is almost
-//! expired.
+//! This token is stored on disk or in the OS keychain and also
+//! refreshed if it is almost expired.
 //!
 //! This is for internal use only.
 
@@ -16,9 +16,25 @@ use std::{
 
 use super::payload::AuthResp;
 use super::Client;
+use crate::util::events;
+use serde::{Deserialize, Serialize};
 
-const TOKEN_FILENAME: &str = "dsc-token.json";
-const DSC_SESSION: &str = "DSC_SESSION";
+const TOKEN_FILE_PREFIX: &str = "dsc-token";
+pub const DSC_SESSION: &str = "DSC_SESSION";
+const KEYRING_SERVICE: &str = "dsc";
+
+/// Where to store the session token.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStore {
+    /// A json file beneath the user's config directory.
+    #[default]
+    File,
+    /// The OS keychain, like macOS Keychain, Windows Credential
+    /// Manager or Secret Service on Linux. If it is not available,
+    /// the file is used.
+    Keyring,
+}
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -52,24 +68,28 @@ pub enum Error {
     #[snafu(display("Error serializing auth response: {}", source))]
     SerializeSession { source: serde_json::Error },
 
-    #[snafu(display("Error refreshing session. Use the `login` command. {}", mesg))]
-    RefreshSession { mesg: String },
+    #[snafu(display("Error refreshing session. Use the `login` command. {}", source))]
+    RefreshSession { source: Box<super::Error> },
+
+    #[snafu(display("Error accessing the keychain: {}", source))]
+    Keyring { source: keyring::Error },
 }
 
-pub fn store_session(resp: &AuthResp) -> Result<(), Error> {
-    match dirs::config_dir() {
-        Some(mut dir) => {
-            dir.push("dsc");
-            dir.push(TOKEN_FILENAME);
-            if !dir.exists() {
-                log::debug!("Creating directory to store config at {:?}", dir.parent());
-                std::fs::create_dir_all(dir.parent().unwrap())
-                    .context(StoreSessionFile { path: dir.clone() })?;
-            }
-            write_token_file(resp, &dir)
+pub fn store_session(resp: &AuthResp, client: &Client) -> Result<(), Error> {
+    if client.session_store == SessionStore::Keyring {
+        match keyring_store(resp, client) {
+            Ok(()) => return Ok(()),
+            Err(err) => keyring_unavailable(&err),
+        }
+    }
+    let file = get_token_file(client)?;
+    if !file.exists() {
+        if let Some(dir) = file.parent() {
+            log::debug!("Creating directory to store config at {:?}", dir);
+            std::fs::create_dir_all(dir).context(StoreSessionFile { path: file.clone() })?;
         }
-        None => Err(Error::NoSessionFile),
     }
+    write_token_file(resp, &file)
 }
 
 /// Loads the session token from defined places. Uses in this order:
@@ -87,8 +107,7 @@ pub fn session_token(token: &Option<String>, client: &Client) -> Result<String,
             Ok((token, None))
         }
         None => {
-            let file = get_token_file().map_err(|_err| Error::NotLoggedIn)?;
-            let resp = read_token_file(&file)?;
+            let resp = read_session(client)?;
             let token = get_token(&resp)?;
             Ok((token, Some(resp.valid_ms)))
         }
@@ -100,10 +119,10 @@ pub fn session_token(token: &Option<String>, client: &Client) -> Result<String,
         let resp = client
             .session_login(&token)
             .map_err(|err| Error::RefreshSession {
-                mesg: err.to_string(),
+                source: Box::new(err),
             })?;
         if no_token {
-            store_session(&resp)?;
+            store_session(&resp, client)?;
         } else {
             log::debug!("Not storing new session, since it was given as argument");
         }
@@ -113,8 +132,59 @@ pub fn session_token(token: &Option<String>, client: &Client) -> Result<String,
     }
 }
 
-pub fn drop_session() -> Result<(), Error> {
-    let path = get_token_file()?;
+/// Tries to obtain a new session after the server rejected `token`.
+///
+/// The session is refreshed first. If this fails and credentials are
+/// available, a new login is done. The new session is stored, if
+/// `token` is the stored one.
+pub fn refresh_session(token: &str, client: &Client) -> Result<String, Error> {
+    let stored = read_session(client)
+        .ok()
+        .and_then(|r| r.token)
+        .filter(|t| t == token)
+        .is_some();
+    log::info!("Session token rejected. Trying to refresh");
+    match client.session_login(token) {
+        Ok(resp) => {
+            if stored {
+                store_session(&resp, client)?;
+            }
+            get_token(&resp)
+        }
+        Err(err) => {
+            log::debug!("Refreshing session failed: {}", err);
+            match client
+                .credentials
+                .as_ref()
+                .filter(|_| stored)
+                .and_then(|f| f())
+            {
+                Some(req) => {
+                    log::info!("Login again for account {}", req.account);
+                    let resp = client.login(&req).map_err(|e| Error::RefreshSession {
+                        source: Box::new(e),
+                    })?;
+                    get_token(&resp)
+                }
+                None => Err(Error::RefreshSession {
+                    source: Box::new(err),
+                }),
+            }
+        }
+    }
+}
+
+pub fn drop_session(client: &Client) -> Result<(), Error> {
+    if client.session_store == SessionStore::Keyring {
+        match keyring_entry(client).and_then(|e| e.delete_password().context(Keyring)) {
+            Ok(())
+            | Err(Error::Keyring {
+                source: keyring::Error::NoEntry,
+            }) => {}
+            Err(err) => keyring_unavailable(&err),
+        }
+    }
+    let path = get_token_file(client)?;
     if path.exists() {
         std::fs::remove_file(&path).context(DeleteSessionFile { path })?;
     }
@@ -123,6 +193,44 @@ pub fn drop_session() -> Result<(), Error> {
 
 // --- helper
 
+fn read_session(client: &Client) -> Result<AuthResp, Error> {
+    if client.session_store == SessionStore::Keyring {
+        match keyring_entry(client).and_then(|e| e.get_password().context(Keyring)) {
+            Ok(cnt) => return serde_json::from_str(&cnt).context(SerializeSession),
+            Err(Error::Keyring {
+                source: keyring::Error::NoEntry,
+            }) => log::debug!("No session in keychain, looking for a session file"),
+            Err(err) => keyring_unavailable(&err),
+        }
+    }
+    let file = get_token_file(client).map_err(|_err| Error::NotLoggedIn)?;
+    if client.legacy_session && client.session_file.is_none() && !file.exists() {
+        migrate_legacy_file(&file);
+    }
+    read_token_file(&file)
+}
+
+/// The keychain entry is named after the session file, so that
+/// different profiles use different entries.
+fn keyring_entry(client: &Client) -> Result<keyring::Entry, Error> {
+    let file = get_token_file(client)?;
+    keyring::Entry::new(KEYRING_SERVICE, &file.display().to_string()).context(Keyring)
+}
+
+fn keyring_store(resp: &AuthResp, client: &Client) -> Result<(), Error> {
+    let cnt = serde_json::to_string(resp).context(SerializeSession)?;
+    log::debug!("Storing session in keychain");
+    keyring_entry(client)?.set_password(&cnt).context(Keyring)
+}
+
+fn keyring_unavailable(err: &Error) {
+    log::warn!("Keychain not available, using session file: {}", err);
+    events::warn(&format!(
+        "Warning: {}. Using the session file instead.",
+        err
+    ));
+}
+
 fn near_expiry(created: u64, valid: Option<u64>) -> bool {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)
@@ -143,7 +251,8 @@ fn near_expiry(created: u64, valid: Option<u64>) -> bool {
     }
 }
 
-fn extract_creation_time(token: &str) -> Result<u64, Error> {
+/// Returns the time in millis when the token has been created.
+pub fn extract_creation_time(token: &str) -> Result<u64, Error> {
     match token.split('-').next() {
         Some(ms) => ms.parse().map_err(|_e| Error::InvalidAuthToken {
             token: token.to_string(),
@@ -160,21 +269,73 @@ fn get_token_from_env() -> Option<String> {
         .and_then(|s| s.into_string().ok())
 }
 
-fn get_token_file() -> Result<PathBuf, Error> {
+/// Returns the session file. By default, it is named after the
+/// docspell url, so sessions of different servers are kept apart.
+fn get_token_file(client: &Client) -> Result<PathBuf, Error> {
+    if let Some(file) = &client.session_file {
+        return Ok(file.clone());
+    }
     match dirs::config_dir() {
         Some(mut dir) => {
             dir.push("dsc");
-            dir.push(TOKEN_FILENAME);
+            dir.push(token_file_name(&client.base_url));
             Ok(dir)
         }
         None => Err(Error::NoSessionFile),
     }
 }
 
+/// Moves the session file of older versions, which was the same for
+/// all servers, to `file`. This is only done for the configured
+/// server, which is the one the old session belongs to in the usual
+/// case of a single server.
+fn migrate_legacy_file(file: &Path) {
+    let legacy = file.with_file_name(format!("{}.json", TOKEN_FILE_PREFIX));
+    if legacy != file && legacy.exists() {
+        log::info!(
+            "Moving session file {} to {}",
+            legacy.display(),
+            file.display()
+        );
+        if let Err(err) = std::fs::rename(&legacy, file) {
+            log::warn!("Cannot move session file {}: {}", legacy.display(), err);
+        }
+    }
+}
+
+/// Returns the file name for the session of the given server, which
+/// consists of the host, port and path of the url.
+fn token_file_name(base_url: &str) -> String {
+    server_file_name(TOKEN_FILE_PREFIX, base_url)
+}
+
+/// Returns a file name for data about the given server, starting
+/// with `prefix` followed by the host, port and path of the url.
+pub fn server_file_name(prefix: &str, base_url: &str) -> String {
+    let server = base_url
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(base_url);
+    let name: String = server
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.trim_matches('_') {
+        "" => format!("{}.json", prefix),
+        n => format!("{}-{}.json", prefix, n),
+    }
+}
+
 fn read_token_file(path: &Path) -> Result<AuthResp, Error> {
-    let _flock = acquire_lock(path, false)?;
+    acquire_lock(path, false)?;
 
-    let cnt = std::fs::read_to_string(&path).context(ReadSessionFile { path })?;
+    let cnt = std::fs::read_to_string(path).context(ReadSessionFile { path })?;
     let resp: AuthResp = serde_json::from_str(&cnt).context(SerializeSession)?;
     Ok(resp)
 }
@@ -242,4 +403,32 @@ mod tests {
             "1626345633653-ZGVtby9kZW1v-$2a$10$63d9R5xyDMYusXNdPdfKYO-e0jDd0o2KgBdrHv3PN+qTM+cFPM=";
         assert_eq!(extract_creation_time(token).unwrap(), 1626345633653);
     }
+
+    #[test]
+    fn unit_token_file_name() {
+        assert_eq!(
+            token_file_name("http://localhost:7880"),
+            "dsc-token-localhost_7880.json"
+        );
+        assert_eq!(
+            token_file_name("https://Docs.Example.com/docspell/"),
+            "dsc-token-docs.example.com_docspell.json"
+        );
+        assert_eq!(token_file_name(""), "dsc-token.json");
+    }
+
+    #[test]
+    fn unit_migrate_legacy_file() {
+        let dir = std::env::temp_dir().join(format!("dsc-session-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join("dsc-token.json");
+        let file = dir.join(token_file_name("http://localhost:7880"));
+        std::fs::write(&legacy, "{}").unwrap();
+
+        migrate_legacy_file(&file);
+        assert!(!legacy.exists());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "{}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,212 @@
+//! Logging requests and responses at trace level.
+//!
+//! Headers carrying credentials are never logged, their values are
+//! replaced by `***`. The same applies to values of json keys looking
+//! like a token, password or secret in response bodies.
+//!
+//! Only textual bodies are logged, because they must be read into
+//! memory for this. The source id in upload urls is a secret, too,
+//! and is replaced in logged urls.
+
+use reqwest::blocking::{Request, Response};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{ResponseBuilderExt, Url};
+use serde_json::Value;
+
+use super::util::{DOCSPELL_ADMIN, DOCSPELL_AUTH};
+
+/// Bodies are shortened to this many characters.
+const MAX_BODY: usize = 4096;
+
+/// Headers whose values are never logged.
+const SECRET_HEADERS: [&str; 6] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    DOCSPELL_AUTH,
+    DOCSPELL_ADMIN,
+];
+
+const SECRET_KEYS: [&str; 3] = ["token", "password", "secret"];
+
+const REDACTED: &str = "***";
+
+/// Url paths followed by a secret, the id of a source.
+const SECRET_PATHS: [&str; 2] = ["/api/v1/open/upload/item/", "/api/v1/open/checkfile/"];
+
+pub fn enabled() -> bool {
+    log::log_enabled!(log::Level::Trace)
+}
+
+pub fn request(req: &Request) {
+    log::trace!(
+        "Request: {} {} headers: {}",
+        req.method(),
+        redact_url(req.url()),
+        headers(req.headers())
+    );
+}
+
+/// Logs the response and returns it. If its body is logged, the
+/// response is rebuilt from the body read into memory, keeping its
+/// url for error messages.
+pub fn response(resp: Response) -> Response {
+    log::trace!(
+        "Response: {} {:?} headers: {}",
+        resp.status(),
+        resp.version(),
+        headers(resp.headers())
+    );
+    if !is_textual(resp.headers()) {
+        return resp;
+    }
+
+    let mut builder = http::Response::builder()
+        .status(resp.status())
+        .version(resp.version())
+        .url(resp.url().clone());
+    if let Some(h) = builder.headers_mut() {
+        *h = resp.headers().clone();
+    }
+    let body = match resp.bytes() {
+        Ok(body) => {
+            log::trace!("Response body: {}", redact_body(&body));
+            body.to_vec()
+        }
+        Err(err) => {
+            log::trace!("Response body cannot be read: {}", err);
+            Vec::new()
+        }
+    };
+    match builder.body(body) {
+        Ok(r) => Response::from(r),
+        Err(err) => {
+            log::error!("Cannot rebuild response: {}", err);
+            Response::from(http::Response::new(Vec::new()))
+        }
+    }
+}
+
+fn headers(map: &HeaderMap) -> String {
+    map.iter()
+        .map(|(name, value)| {
+            let secret = value.is_sensitive()
+                || SECRET_HEADERS
+                    .iter()
+                    .any(|h| name.as_str().eq_ignore_ascii_case(h));
+            if secret {
+                format!("{}: {}", name, REDACTED)
+            } else {
+                format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn redact_url(url: &Url) -> String {
+    let text = url.to_string();
+    for path in SECRET_PATHS.iter() {
+        if let Some(idx) = text.find(path) {
+            let start = idx + path.len();
+            let end = text[start..]
+                .find(['/', '?', '#'])
+                .map_or(text.len(), |n| start + n);
+            if start < end {
+                return format!("{}{}{}", &text[..start], REDACTED, &text[end..]);
+            }
+        }
+    }
+    text
+}
+
+fn is_textual(map: &HeaderMap) -> bool {
+    map.get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| {
+            let ct = ct.to_lowercase();
+            ct.starts_with("text/") || ct.contains("json") || ct.contains("xml")
+        })
+        .unwrap_or(false)
+}
+
+fn redact_body(body: &[u8]) -> String {
+    let text = match serde_json::from_slice::<Value>(body) {
+        Ok(mut json) => {
+            redact_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => String::from_utf8_lossy(body).into_owned(),
+    };
+    match text.char_indices().nth(MAX_BODY) {
+        Some((idx, _)) => format!("{}… ({} bytes)", &text[..idx], body.len()),
+        None => text,
+    }
+}
+
+fn redact_json(json: &mut Value) {
+    match json {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SECRET_KEYS.iter().any(|k| key.contains(k)) && !value.is_null() {
+                    *value = Value::String(REDACTED.into());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn unit_redact_secrets() {
+        let mut map = HeaderMap::new();
+        map.insert(DOCSPELL_AUTH, HeaderValue::from_static("abc"));
+        map.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        assert_eq!(
+            headers(&map),
+            "x-docspell-auth: ***, content-type: application/json"
+        );
+        let body = br#"{"success":true,"token":"abc","user":{"newPassword":"x"}}"#;
+        assert_eq!(
+            redact_body(body),
+            r#"{"success":true,"token":"***","user":{"newPassword":"***"}}"#
+        );
+        let url = Url::parse("http://localhost:7880/api/v1/open/upload/item/5Ld3?x=1").unwrap();
+        assert_eq!(
+            redact_url(&url),
+            "http://localhost:7880/api/v1/open/upload/item/***?x=1"
+        );
+        let url = Url::parse("http://localhost:7880/api/v1/open/checkfile/5Ld3/ab12").unwrap();
+        assert_eq!(
+            redact_url(&url),
+            "http://localhost:7880/api/v1/open/checkfile/***/ab12"
+        );
+        let url = Url::parse("http://localhost:7880/api/v1/sec/upload/item").unwrap();
+        assert_eq!(redact_url(&url), url.as_str());
+    }
+
+    #[test]
+    fn unit_rebuild_error_response() {
+        let url = Url::parse("http://localhost:7880/api/v1/sec/item/abc").unwrap();
+        let resp = http::Response::builder()
+            .status(404)
+            .header(CONTENT_TYPE, "application/json")
+            .url(url.clone())
+            .body(r#"{"success":false}"#)
+            .unwrap();
+        let resp = response(Response::from(resp));
+        assert_eq!(resp.url(), &url);
+        let err = resp.error_for_status().unwrap_err();
+        assert_eq!(err.url(), Some(&url));
+    }
+}
@@ -9,6 +9,46 @@ pub fn filename_from_header(header_value: &str) -> Option<&str> {
         .map(|rest| rest.trim_matches('"'))
 }
 
+/// Extracts a SHA-256 checksum from an ETag header. Weak tags are
+/// accepted, but anything not looking like a hex encoded SHA-256 is
+/// ignored.
+pub fn sha256_from_etag(header_value: &str) -> Option<String> {
+    let tag = header_value
+        .trim()
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .to_lowercase();
+    if tag.len() == 64 && tag.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(tag)
+    } else {
+        None
+    }
+}
+
+/// Checks that the given url to docspell is an absolute http(s) url
+/// and returns it without trailing slashes, so that endpoints can be
+/// appended to it.
+pub fn normalize_base_url(url: &str) -> Result<String, String> {
+    let url = url.trim();
+    if !url.contains("://") {
+        return Err(format!("the scheme is missing, try 'http://{}'", url));
+    }
+    let parsed = reqwest::Url::parse(url).map_err(|err| err.to_string())?;
+    if !["http", "https"].contains(&parsed.scheme()) {
+        return Err(format!(
+            "unsupported scheme '{}', expected http or https",
+            parsed.scheme()
+        ));
+    }
+    if parsed.host_str().map(str::is_empty).unwrap_or(true) {
+        return Err("the host is missing".into());
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err("it must not contain a query or fragment".into());
+    }
+    Ok(url.trim_end_matches('/').to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -20,4 +60,37 @@ mod tests {
             Some("test.jpg")
         );
     }
+
+    #[test]
+    fn unit_sha256_from_etag() {
+        let hash = "a".repeat(64);
+        assert_eq!(
+            sha256_from_etag(&format!("\"{}\"", hash)),
+            Some(hash.clone())
+        );
+        assert_eq!(sha256_from_etag(&format!("W/\"{}\"", hash)), Some(hash));
+        assert_eq!(sha256_from_etag("\"abc\""), None);
+    }
+
+    #[test]
+    fn unit_normalize_base_url() {
+        for url in &[
+            "http://localhost:7880",
+            "http://localhost:7880/",
+            " http://localhost:7880// ",
+        ] {
+            let base = normalize_base_url(url).unwrap();
+            assert_eq!(
+                format!("{}/api/v1/sec/source", base),
+                "http://localhost:7880/api/v1/sec/source"
+            );
+        }
+        assert_eq!(
+            normalize_base_url("https://host/docspell/").unwrap(),
+            "https://host/docspell"
+        );
+        assert!(normalize_base_url("localhost:7880").is_err());
+        assert!(normalize_base_url("ftp://host").is_err());
+        assert!(normalize_base_url("http://host/?a=b").is_err());
+    }
 }
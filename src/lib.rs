@@ -9,27 +9,55 @@ mod util;
 
 pub use cli::execute_cmd;
 
-use clap::Clap;
-use cli::opts::MainOpts;
+use clap::{FromArgMatches, IntoApp};
+use cli::opts::{ColorChoice, MainOpts};
+use cli::sink;
 use config::DsConfig;
-use error::Result;
+use error::{Error, Result};
 use std::path::PathBuf;
+use util::events::{self, Event};
 
 /// Reads the program arguments into the `MainOpts` data structure.
+///
+/// The colors for help texts and errors are decided here, so that
+/// they are the same for everything printed afterwards. The same
+/// applies to whether events are printed.
 pub fn read_args() -> MainOpts {
     log::debug!("Parsing command line options…");
-    let m = MainOpts::parse();
+    let args = std::env::args_os().map(|a| a.to_string_lossy().into_owned());
+    let color = ColorChoice::from_args(args);
+    let app = MainOpts::into_app().global_setting(color.app_setting());
+    let m = MainOpts::from_arg_matches(&app.get_matches()).expect("IntoApp validated everything");
+    sink::set_color(m.common_opts.color_choice());
+    events::set_enabled(m.common_opts.events);
 
     log::debug!("Parsed options: {:?}", m);
     m
 }
 
+/// Reports a failure with `--events`. Errors of the command are part
+/// of its `finished` event already, others get their own. Returns
+/// `false` if events are disabled and the error must be printed.
+pub fn report_event_error(err: &Error) -> bool {
+    if !events::is_enabled() {
+        return false;
+    }
+    if let Error::Config { .. } = err {
+        events::emit(&Event::Finished {
+            success: false,
+            error: Some(&err.to_string()),
+        });
+    }
+    true
+}
+
 /// Reads the config file.
 ///
 /// If the file is not given, it is searched in the default location.
-/// If the file is given, it is used without a fallback.
-pub fn read_config(file: &Option<PathBuf>) -> Result<DsConfig> {
-    let f = DsConfig::read(file.as_ref())?;
+/// If the file is given, it is used without a fallback. The given
+/// profile is applied afterwards, see [`DsConfig::select_profile`].
+pub fn read_config(file: &Option<PathBuf>, profile: &Option<String>) -> Result<DsConfig> {
+    let f = DsConfig::read(file.as_ref())?.select_profile(profile.as_deref())?;
     log::debug!("Config: {:?}", f);
     Ok(f)
 }
@@ -38,7 +66,7 @@ pub fn read_config(file: &Option<PathBuf>) -> Result<DsConfig> {
 /// corresponding command.
 pub fn execute() -> Result<()> {
     let opts = read_args();
-    let cfg = read_config(&opts.config)?;
+    let cfg = read_config(&opts.config, &opts.common_opts.profile)?;
     execute_cmd(cfg, opts)?;
     Ok(())
 }
@@ -1,4 +1,6 @@
-use dsc::error::{Error, Result};
+use dsc::cli::opts::Format;
+use dsc::config::DsConfig;
+use dsc::error::Result;
 use std::env;
 use std::process;
 
@@ -6,20 +8,35 @@ const LOG_LEVEL: &str = "RUST_LOG";
 
 fn main() {
     let error_style = console::Style::new().red().bright();
-    let result = execute();
+    let mut json_errors = false;
+    let result = execute(&mut json_errors);
     if let Err(err) = result {
-        eprintln!("{}", error_style.apply_to(&err));
-        process::exit(exit_code(&err));
+        if dsc::report_event_error(&err) {
+            process::exit(err.exit_code());
+        }
+        match serde_json::to_string(&err.report()) {
+            Ok(json) if json_errors => eprintln!("{}", json),
+            _ => eprintln!("{}", error_style.apply_to(&err)),
+        }
+        process::exit(err.exit_code());
     }
 }
 
-fn execute() -> Result<()> {
+/// Runs the command. Once the config is loaded, `json_errors` tells
+/// whether an error should be printed as json. This is the case for
+/// the json formats, given via `--format` or the config file, also
+/// when a `--template` is used.
+fn execute(json_errors: &mut bool) -> Result<()> {
     let opts = dsc::read_args();
     let remove_env = match opts.common_opts.verbose {
         1 => set_log_level("info"),
         n => {
-            if n > 1 {
+            if n > 2 {
+                set_log_level("debug,dsc=trace")
+            } else if n > 1 {
                 set_log_level("debug")
+            } else if opts.common_opts.is_quiet() {
+                set_log_level("error")
             } else {
                 false
             }
@@ -27,7 +44,20 @@ fn execute() -> Result<()> {
     };
     env_logger::init();
 
-    let cfg = dsc::read_config(&opts.config)?;
+    let cfg = match dsc::read_config(&opts.config, &opts.common_opts.profile) {
+        Err(err) if opts.subcmd.ignores_config_errors() => {
+            log::debug!("Using the default config, reading failed: {}", err);
+            Ok(DsConfig::default())
+        }
+        result => result,
+    };
+    let format = opts.common_opts.format.or_else(|| {
+        cfg.as_ref()
+            .ok()
+            .map(|cfg| cfg.format_for(opts.subcmd.name()))
+    });
+    *json_errors = is_json(format);
+    let cfg = cfg?;
     let result = dsc::execute_cmd(cfg, opts);
     if remove_env {
         env::remove_var(LOG_LEVEL);
@@ -36,6 +66,10 @@ fn execute() -> Result<()> {
     Ok(())
 }
 
+fn is_json(format: Option<Format>) -> bool {
+    matches!(format, Some(Format::Json) | Some(Format::Ndjson))
+}
+
 fn set_log_level(level: &str) -> bool {
     let current = env::var_os(LOG_LEVEL);
     if current.is_none() {
@@ -45,10 +79,3 @@ fn set_log_level(level: &str) -> bool {
         false
     }
 }
-
-fn exit_code(err: &Error) -> i32 {
-    match err {
-        Error::Config { source: _ } => 1,
-        Error::Cmd { source: _ } => 2,
-    }
-}
@@ -1,4 +1,11 @@
 pub mod digest;
 pub mod dupes;
+pub mod events;
 pub mod file;
+pub mod name_pattern;
+pub mod parallel;
 pub mod pass;
+pub mod progress;
+pub mod query_alias;
+pub mod quiet;
+pub mod shutdown;
@@ -19,12 +19,57 @@ pub fn digest<D: Digest + Default, R: io::Read>(reader: &mut R) -> Result<String
     loop {
         let n = match reader.read(&mut buffer) {
             Ok(n) => n,
-            Err(_) => return Err(io::Error::new(io::ErrorKind::Other, "Could not read file")),
+            Err(_) => return Err(io::Error::other("Could not read file")),
         };
         sh.update(&buffer[..n]);
         if n == 0 || n < BUFFER_SIZE {
             break;
         }
     }
-    Ok(hex::encode(&sh.finalize()))
+    Ok(hex::encode(sh.finalize()))
+}
+
+/// A writer that computes the digest of all bytes written through it.
+pub struct DigestWriter<W, D> {
+    inner: W,
+    digest: D,
+}
+
+impl<W: io::Write, D: Digest + Default> DigestWriter<W, D> {
+    pub fn new(inner: W) -> DigestWriter<W, D> {
+        DigestWriter {
+            inner,
+            digest: D::default(),
+        }
+    }
+
+    /// Adds the bytes from the reader to the digest, without writing
+    /// them. This is used when appending to existing data.
+    pub fn update_from<R: io::Read>(&mut self, reader: &mut R) -> Result<(), io::Error> {
+        let mut buffer = [0u8; BUFFER_SIZE];
+        loop {
+            let n = reader.read(&mut buffer)?;
+            if n == 0 {
+                return Ok(());
+            }
+            self.digest.update(&buffer[..n]);
+        }
+    }
+
+    /// Returns the hex encoded digest of all bytes written.
+    pub fn finish(self) -> String {
+        hex::encode(self.digest.finalize())
+    }
+}
+
+impl<W: io::Write, D: Digest> io::Write for DigestWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.digest.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
 }
@@ -0,0 +1,187 @@
+//! Structured events on stderr, enabled with `--events`.
+//!
+//! Each event is printed as a json object on its own line, with a
+//! `type` field telling what happened. This allows front-ends to show
+//! the progress of a command without parsing the messages meant for
+//! humans, which are not printed when events are enabled. Warnings
+//! and errors that would be printed are emitted as `warning` events
+//! instead, so that stderr only contains json lines.
+
+use serde::Serialize;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// A `file-progress` event is emitted each time this many bytes have
+/// been transferred.
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    Started {
+        command: &'a str,
+    },
+    FileProgress {
+        file: &'a str,
+        bytes: u64,
+        total: Option<u64>,
+    },
+    FileDone {
+        file: &'a str,
+        outcome: Outcome,
+        bytes: u64,
+    },
+    FileError {
+        file: &'a str,
+        error: &'a str,
+    },
+    Warning {
+        message: &'a str,
+    },
+    Finished {
+        success: bool,
+        error: Option<&'a str>,
+    },
+}
+
+/// What happened to a file.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    Uploaded,
+    Downloaded,
+    Skipped,
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Prints the event to stderr, if events are enabled.
+pub fn emit(event: &Event) {
+    if is_enabled() {
+        match serde_json::to_string(event) {
+            Ok(json) => eprintln!("{}", json),
+            Err(err) => log::error!("Cannot serialize event {:?}: {}", event, err),
+        }
+    }
+}
+
+/// Prints a warning to stderr, or emits it as a `warning` event if
+/// events are enabled.
+pub fn warn(message: &str) {
+    if is_enabled() {
+        emit(&Event::Warning { message });
+    } else {
+        eprintln!("{}", message);
+    }
+}
+
+/// Wraps a reader or writer to emit `file-progress` events for the
+/// bytes passing through.
+pub struct Tracked<T> {
+    inner: T,
+    file: String,
+    total: Option<u64>,
+    bytes: u64,
+    reported: u64,
+}
+
+impl<T> Tracked<T> {
+    pub fn new(inner: T, file: String, total: Option<u64>) -> Tracked<T> {
+        Tracked {
+            inner,
+            file,
+            total,
+            bytes: 0,
+            reported: 0,
+        }
+    }
+
+    /// Starts counting at `bytes`, for continuing a transfer.
+    pub fn with_offset(mut self, bytes: u64) -> Tracked<T> {
+        self.bytes = bytes;
+        self.reported = bytes;
+        self
+    }
+
+    fn add(&mut self, n: usize) {
+        self.bytes += n as u64;
+        if self.bytes - self.reported >= PROGRESS_STEP {
+            self.report();
+        }
+    }
+
+    fn report(&mut self) {
+        if self.bytes != self.reported {
+            self.reported = self.bytes;
+            emit(&Event::FileProgress {
+                file: &self.file,
+                bytes: self.bytes,
+                total: self.total,
+            });
+        }
+    }
+}
+
+impl<R: Read> Read for Tracked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n == 0 {
+            self.report();
+        } else {
+            self.add(n);
+        }
+        Ok(n)
+    }
+}
+
+impl<W: Write> Write for Tracked<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.add(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.report();
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_event_json() {
+        let done = Event::FileDone {
+            file: "a.pdf",
+            outcome: Outcome::Uploaded,
+            bytes: 12,
+        };
+        assert_eq!(
+            serde_json::to_string(&done).unwrap(),
+            r#"{"type":"file-done","file":"a.pdf","outcome":"uploaded","bytes":12}"#
+        );
+        let finished = Event::Finished {
+            success: true,
+            error: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&finished).unwrap(),
+            r#"{"type":"finished","success":true,"error":null}"#
+        );
+        let warning = Event::Warning { message: "careful" };
+        assert_eq!(
+            serde_json::to_string(&warning).unwrap(),
+            r#"{"type":"warning","message":"careful"}"#
+        );
+    }
+}
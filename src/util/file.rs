@@ -17,6 +17,90 @@ pub fn splice_name(fname: &str, suffix: &i32) -> String {
     }
 }
 
+/// Returns `path` if it doesn't exist. Otherwise a number is put in
+/// the file name, counting up until no file with this name exists.
+pub fn unique_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let name = match path.file_name() {
+        Some(n) => n.to_string_lossy().to_string(),
+        None => return path,
+    };
+    let mut counter = 1;
+    loop {
+        let next = path.with_file_name(splice_name(&name, &counter));
+        if !next.exists() {
+            return next;
+        }
+        counter += 1;
+    }
+}
+
+/// A file that is written atomically.
+///
+/// The data is written into a temporary file next to the target,
+/// which is renamed to the target by [`AtomicFile::commit`]. If it is
+/// dropped before, the temporary file is removed and an existing
+/// target file is left untouched.
+pub struct AtomicFile {
+    target: PathBuf,
+    tmp: PathBuf,
+    file: Option<io::BufWriter<std::fs::File>>,
+}
+
+impl AtomicFile {
+    pub fn create(target: &Path) -> io::Result<AtomicFile> {
+        let name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let tmp = target.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+        let file = std::fs::File::create(&tmp)?;
+        Ok(AtomicFile {
+            target: target.to_path_buf(),
+            tmp,
+            file: Some(io::BufWriter::new(file)),
+        })
+    }
+
+    /// Replaces the target file with the written data.
+    pub fn commit(mut self) -> io::Result<()> {
+        if let Some(file) = self.file.take() {
+            let file = file.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+            std::fs::rename(&self.tmp, &self.target)?;
+        }
+        Ok(())
+    }
+
+    fn writer(&mut self) -> io::Result<&mut io::BufWriter<std::fs::File>> {
+        self.file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("File already committed"))
+    }
+}
+
+impl io::Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer()?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer()?.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            if let Err(err) = std::fs::remove_file(&self.tmp) {
+                log::debug!("Could not remove {}: {}", self.tmp.display(), err);
+            }
+        }
+    }
+}
+
 fn delete_parent_if_empty(file: &Path, root: Option<&PathBuf>) -> Result<(), std::io::Error> {
     match (root, file.parent()) {
         (Some(r), Some(p)) => {
@@ -63,7 +147,7 @@ pub fn safe_filepath(name: &str, path_delimiter: &Option<String>) -> String {
         Some(delimiter) => name.split(delimiter).map(safe_filename).collect(),
         None => vec![safe_filename(name)],
     };
-    return path_segments.join("/");
+    path_segments.join("/")
 }
 
 #[cfg(windows)]
@@ -90,7 +174,9 @@ impl FileAction {
         root: Option<&PathBuf>,
     ) -> Result<FileActionResult, std::io::Error> {
         match &self.move_to {
-            Some(target) => Self::move_file(file, root, target).map(FileActionResult::Moved),
+            Some(target) => self
+                .move_file(file, root, target)
+                .map(FileActionResult::Moved),
             None => {
                 if self.delete {
                     Self::delete_file(file, root)
@@ -102,11 +188,17 @@ impl FileAction {
         }
     }
 
-    fn move_file(
-        file: &Path,
-        root: Option<&PathBuf>,
-        target: &Path,
-    ) -> Result<PathBuf, std::io::Error> {
+    /// Returns what [`FileAction::execute`] would do, without
+    /// touching the file.
+    pub fn plan(&self, file: &Path, root: Option<&PathBuf>) -> FileActionResult {
+        match &self.move_to {
+            Some(target) => FileActionResult::Moved(self.target_file(file, root, target)),
+            None if self.delete => FileActionResult::Deleted(file.to_path_buf()),
+            None => FileActionResult::Nothing,
+        }
+    }
+
+    fn target_file(&self, file: &Path, root: Option<&PathBuf>, target: &Path) -> PathBuf {
         let target_file = match root {
             Some(r) => {
                 let part = file.strip_prefix(r).unwrap();
@@ -114,7 +206,21 @@ impl FileAction {
             }
             None => target.join(file.file_name().unwrap()),
         };
-        log::debug!(
+        if self.keep_existing {
+            unique_path(target_file)
+        } else {
+            target_file
+        }
+    }
+
+    fn move_file(
+        &self,
+        file: &Path,
+        root: Option<&PathBuf>,
+        target: &Path,
+    ) -> Result<PathBuf, std::io::Error> {
+        let target_file = self.target_file(file, root, target);
+        log::info!(
             "Move file '{}' -> '{}'",
             file.display(),
             &target_file.display()
@@ -131,7 +237,7 @@ impl FileAction {
     }
 
     fn delete_file(file: &Path, root: Option<&PathBuf>) -> Result<(), std::io::Error> {
-        log::debug!("Deleting file: {}", file.display());
+        log::info!("Deleting file: {}", file.display());
         std::fs::remove_file(file)?;
         delete_parent_if_empty(file, root)?;
         Ok(())
@@ -148,4 +254,41 @@ mod tests {
         assert_eq!(splice_name("abc", &1), "abc_1");
         assert_eq!(splice_name("stuff.tar.gz", &2), "stuff.tar_2.gz");
     }
+
+    #[test]
+    fn unit_move_keep_existing() {
+        let dir = std::env::temp_dir().join(format!("dsc-move-{}", std::process::id()));
+        let target = dir.join("target");
+        std::fs::create_dir_all(&target).unwrap();
+        std::fs::write(target.join("a.txt"), "old").unwrap();
+        let mut action = FileAction {
+            delete: false,
+            move_to: Some(target.clone()),
+            keep_existing: false,
+        };
+
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "new").unwrap();
+        assert_eq!(
+            action.move_file(&file, None, &target).unwrap(),
+            target.join("a.txt")
+        );
+        assert_eq!(
+            std::fs::read_to_string(target.join("a.txt")).unwrap(),
+            "new"
+        );
+
+        action.keep_existing = true;
+        std::fs::write(&file, "newer").unwrap();
+        assert_eq!(
+            action.move_file(&file, None, &target).unwrap(),
+            target.join("a_1.txt")
+        );
+        assert_eq!(
+            std::fs::read_to_string(target.join("a.txt")).unwrap(),
+            "new"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,111 @@
+//! Matching item names against a simple pattern.
+//!
+//! A pattern may contain `*` for any text and `?` for a single
+//! character. Without any of them, it matches anywhere in the name.
+//! The docspell query only supports `*`, so a `?` becomes a `*` in
+//! the query and the results are matched exactly afterwards. This is
+//! also where case-sensitive matching happens, because the server
+//! always ignores the case.
+
+/// A pattern to match item names against.
+#[derive(Debug, Clone)]
+pub struct NamePattern {
+    pattern: Vec<char>,
+    case_sensitive: bool,
+}
+
+impl NamePattern {
+    pub fn new(pattern: &str, case_sensitive: bool) -> NamePattern {
+        let pattern = if pattern.contains(['*', '?']) {
+            pattern.to_string()
+        } else {
+            format!("*{}*", pattern)
+        };
+        let pattern = if case_sensitive {
+            pattern.chars().collect()
+        } else {
+            pattern.to_lowercase().chars().collect()
+        };
+        NamePattern {
+            pattern,
+            case_sensitive,
+        }
+    }
+
+    /// Returns the query term selecting the names matching this
+    /// pattern, or possibly some more.
+    pub fn to_query(&self) -> String {
+        let value: String = self
+            .pattern
+            .iter()
+            .map(|c| match c {
+                '?' => "*".to_string(),
+                '"' | '\\' => format!("\\{}", c),
+                c => c.to_string(),
+            })
+            .collect();
+        format!("name:\"{}\"", value)
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let name: Vec<char> = if self.case_sensitive {
+            name.chars().collect()
+        } else {
+            name.to_lowercase().chars().collect()
+        };
+        wildcard_match(&self.pattern, &name)
+    }
+}
+
+/// Matches the whole text, where `*` matches any sequence and `?`
+/// any single character. When a `*` doesn't lead to a match, it is
+/// retried with one more character, so this doesn't backtrack
+/// exponentially.
+fn wildcard_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    star = Some((sp, st + 1));
+                    p = sp + 1;
+                    t = st + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_match_names() {
+        let p = NamePattern::new("invoice", false);
+        assert!(p.matches("Invoice 2023"));
+        assert!(p.matches("my-INVOICE.pdf"));
+        assert!(!p.matches("receipt"));
+        assert_eq!(p.to_query(), "name:\"*invoice*\"");
+
+        let p = NamePattern::new("Inv*20??", true);
+        assert!(p.matches("Invoice 2023"));
+        assert!(!p.matches("invoice 2023"));
+        assert!(!p.matches("Invoice 2023 copy"));
+        assert_eq!(p.to_query(), "name:\"Inv*20**\"");
+
+        let p = NamePattern::new("say \"hi\"", false);
+        assert_eq!(p.to_query(), "name:\"*say \\\"hi\\\"*\"");
+    }
+}
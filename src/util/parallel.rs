@@ -0,0 +1,83 @@
+//! Running jobs on a number of threads.
+//!
+//! The threads take the next job from a shared queue until it is
+//! empty, so the jobs are started in order even though they may
+//! finish in any order.
+
+use std::sync::Mutex;
+
+/// Returns how many threads to use for `total` jobs: the given
+/// concurrency, but at least one and not more than there are jobs.
+pub fn workers(concurrency: usize, total: usize) -> usize {
+    concurrency.clamp(1, total.max(1))
+}
+
+/// Calls `f` for each job using `workers` threads and returns when
+/// all jobs are done.
+pub fn for_each<I, F>(jobs: I, workers: usize, f: F)
+where
+    I: Iterator + Send,
+    I::Item: Send,
+    F: Fn(I::Item) + Sync,
+{
+    let queue = Mutex::new(jobs);
+    std::thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                match next {
+                    Some(job) => f(job),
+                    None => break,
+                }
+            });
+        }
+    });
+}
+
+/// Like [`for_each`], but returns the results of `f` in the order of
+/// the jobs.
+pub fn map<I, F, R>(jobs: I, workers: usize, f: F) -> Vec<R>
+where
+    I: Iterator + Send,
+    I::Item: Send,
+    F: Fn(I::Item) -> R + Sync,
+    R: Send,
+{
+    let results: Mutex<Vec<(usize, R)>> = Mutex::new(Vec::new());
+    for_each(jobs.enumerate(), workers, |(idx, job)| {
+        let result = f(job);
+        results.lock().unwrap().push((idx, result));
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn unit_workers() {
+        assert_eq!(workers(4, 10), 4);
+        assert_eq!(workers(4, 2), 2);
+        assert_eq!(workers(0, 10), 1);
+        assert_eq!(workers(4, 0), 1);
+    }
+
+    #[test]
+    fn unit_map_keeps_order() {
+        let squares = map(1..=20u64, 4, |n| {
+            std::thread::sleep(std::time::Duration::from_millis(20 - n));
+            n * n
+        });
+        assert_eq!(squares, (1..=20u64).map(|n| n * n).collect::<Vec<_>>());
+
+        let count = AtomicUsize::new(0);
+        for_each(0..7, 3, |_| {
+            count.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(count.into_inner(), 7);
+    }
+}
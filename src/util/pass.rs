@@ -23,5 +23,5 @@ pub fn pass_password(entry: &str) -> Result<String, io::Error> {
 }
 
 fn io_err(msg: &str) -> io::Error {
-    io::Error::new(io::ErrorKind::Other, msg)
+    io::Error::other(msg)
 }
@@ -0,0 +1,119 @@
+//! A progress bar on stderr for long running transfers.
+
+use super::{events, quiet};
+use indicatif::{ProgressBar, ProgressBarIter, ProgressStyle};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const FILES_TEMPLATE: &str = "{spinner} [{elapsed_precise}] [{bar:40}] {bytes}/{total_bytes} {msg}";
+const BYTES_TEMPLATE: &str = "{spinner} [{elapsed_precise}] [{bar:40}] {bytes}/{total_bytes}";
+const SPINNER_TEMPLATE: &str = "{spinner} [{elapsed_precise}] {bytes}";
+
+/// Wraps a progress bar that may be hidden.
+///
+/// If hidden, messages given to [`Progress::println`] are printed to
+/// stderr as usual, unless `--quiet` is given.
+pub struct Progress {
+    bar: ProgressBar,
+    files_done: AtomicU64,
+    files_total: u64,
+}
+
+impl Progress {
+    /// A progress that doesn't show anything.
+    pub fn hidden() -> Progress {
+        Progress::new(ProgressBar::hidden(), 0)
+    }
+
+    /// A progress for transferring `files` files with `bytes` bytes
+    /// in total.
+    pub fn files(enabled: bool, files: u64, bytes: u64) -> Progress {
+        if !enabled {
+            return Progress::hidden();
+        }
+        let progress = Progress::new(
+            ProgressBar::new(bytes).with_style(style(FILES_TEMPLATE)),
+            files,
+        );
+        progress.update_message(0);
+        progress
+    }
+
+    /// A progress for transferring a single file, where the length
+    /// may be unknown.
+    pub fn bytes(enabled: bool, len: Option<u64>) -> Progress {
+        if !enabled {
+            return Progress::hidden();
+        }
+        let bar = match len {
+            Some(n) => ProgressBar::new(n).with_style(style(BYTES_TEMPLATE)),
+            None => ProgressBar::new_spinner().with_style(style(SPINNER_TEMPLATE)),
+        };
+        Progress::new(bar, 0)
+    }
+
+    /// Marks one file with the given size as done.
+    pub fn inc_file(&self, size: u64) {
+        self.bar.inc(size);
+        let done = self.files_done.fetch_add(1, Ordering::Relaxed) + 1;
+        self.update_message(done);
+    }
+
+    /// Adds `bytes` to the expected total, for when the size of the
+    /// files is only known while transferring them.
+    pub fn inc_length(&self, bytes: u64) {
+        self.bar.inc_length(bytes);
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.bar.is_hidden()
+    }
+
+    /// Prints a message above the progress bar.
+    pub fn println<S: AsRef<str>>(&self, msg: S) {
+        if !quiet::is_quiet() {
+            self.print_error(msg);
+        }
+    }
+
+    /// Prints an error above the progress bar. Errors are also
+    /// printed with `--quiet`, but not with `--events`, where they
+    /// are reported by `file-error` events.
+    pub fn print_error<S: AsRef<str>>(&self, msg: S) {
+        if events::is_enabled() {
+            log::debug!("{}", msg.as_ref());
+        } else if self.bar.is_hidden() {
+            eprintln!("{}", msg.as_ref());
+        } else {
+            self.bar.println(msg);
+        }
+    }
+
+    /// Wraps a writer to count the written bytes.
+    pub fn wrap_write<W: Write>(&self, w: W) -> ProgressBarIter<W> {
+        self.bar.wrap_write(w)
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+
+    fn new(bar: ProgressBar, files_total: u64) -> Progress {
+        Progress {
+            bar,
+            files_done: AtomicU64::new(0),
+            files_total,
+        }
+    }
+
+    fn update_message(&self, done: u64) {
+        self.bar
+            .set_message(format!("({}/{} files)", done, self.files_total));
+    }
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ")
+}
@@ -0,0 +1,157 @@
+//! Expanding aliases in search queries.
+//!
+//! Aliases are reusable query fragments defined in the config file,
+//! like `recent = "date>-30d"`. A query refers to them as `@recent`.
+//! The `@` must start a term, so it is left alone in quoted values or
+//! in the middle of a word like an email address. Aliases may refer
+//! to other aliases. If the fragment consists of more than one term,
+//! it is put into a `(& …)` group so that it stays one term where it
+//! is used.
+
+use snafu::{OptionExt, Snafu};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("The query alias '@{}' is not defined", name))]
+    UnknownAlias { name: String },
+
+    #[snafu(display("The query aliases refer to each other: {}", chain))]
+    AliasCycle { chain: String },
+}
+
+/// Replaces all `@name` terms in the query with the fragment defined
+/// for `name`.
+pub fn expand(query: &str, aliases: &BTreeMap<String, String>) -> Result<String, Error> {
+    if !query.contains('@') {
+        return Ok(query.to_string());
+    }
+    let mut stack = Vec::new();
+    let expanded = expand_in(query, aliases, &mut stack)?;
+    log::debug!("Query with aliases expanded: {}", expanded);
+    Ok(expanded)
+}
+
+fn expand_in(
+    query: &str,
+    aliases: &BTreeMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Result<String, Error> {
+    let mut out = String::with_capacity(query.len());
+    let mut quoted = false;
+    let mut prev: Option<char> = None;
+    let mut rest = query;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        let starts_term = match prev {
+            Some(p) => p.is_whitespace() || "(&|!".contains(p),
+            None => true,
+        };
+        if c == '@' && !quoted && starts_term {
+            let len = rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len());
+            if len > 0 {
+                let name = &rest[..len];
+                out.push_str(&resolve(name, aliases, stack)?);
+                rest = &rest[len..];
+                prev = name.chars().last();
+                continue;
+            }
+        }
+        if c == '"' {
+            quoted = !quoted;
+        }
+        out.push(c);
+        prev = Some(c);
+    }
+    Ok(out)
+}
+
+fn resolve(
+    name: &str,
+    aliases: &BTreeMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Result<String, Error> {
+    if stack.iter().any(|n| n == name) {
+        let mut chain: Vec<String> = stack.iter().map(|n| format!("@{}", n)).collect();
+        chain.push(format!("@{}", name));
+        return Err(Error::AliasCycle {
+            chain: chain.join(" -> "),
+        });
+    }
+    let fragment = aliases.get(name).context(UnknownAlias { name })?;
+    stack.push(name.to_string());
+    let expanded = expand_in(fragment.trim(), aliases, stack)?;
+    stack.pop();
+    if has_many_terms(&expanded) {
+        Ok(format!("(& {})", expanded))
+    } else {
+        Ok(expanded)
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Whether there is whitespace outside of quotes and groups.
+fn has_many_terms(query: &str) -> bool {
+    let mut quoted = false;
+    let mut depth = 0;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            c if c.is_whitespace() && !quoted && depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(defs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        defs.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn unit_expand_aliases() {
+        let defs = aliases(&[
+            ("recent", "date>-30d"),
+            ("bills", "tag:invoice @recent"),
+            ("todo", "(| tag:todo tag:inbox)"),
+        ]);
+        assert_eq!(expand("@recent", &defs).unwrap(), "date>-30d");
+        assert_eq!(
+            expand("@bills corr:acme", &defs).unwrap(),
+            "(& tag:invoice date>-30d) corr:acme"
+        );
+        assert_eq!(
+            expand("(| @todo !@recent)", &defs).unwrap(),
+            "(| (| tag:todo tag:inbox) !date>-30d)"
+        );
+        assert_eq!(
+            expand("corr:a@b.com name:\"@recent\"", &defs).unwrap(),
+            "corr:a@b.com name:\"@recent\""
+        );
+        assert!(matches!(
+            expand("@nope", &defs),
+            Err(Error::UnknownAlias { .. })
+        ));
+    }
+
+    #[test]
+    fn unit_detect_alias_cycle() {
+        let defs = aliases(&[("a", "tag:x @b"), ("b", "@a"), ("c", "@c")]);
+        match expand("@a", &defs) {
+            Err(Error::AliasCycle { chain }) => assert_eq!(chain, "@a -> @b -> @a"),
+            other => panic!("expected a cycle: {:?}", other),
+        }
+        assert!(matches!(expand("@c", &defs), Err(Error::AliasCycle { .. })));
+    }
+}
@@ -0,0 +1,25 @@
+//! Suppresses informational messages when running with `--quiet`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether informational messages are suppressed.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Prints an informational message to stderr, like `eprintln!`. It is
+/// not printed if `--quiet` is given.
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::util::quiet::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
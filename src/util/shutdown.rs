@@ -0,0 +1,38 @@
+//! Stopping long running commands gracefully on SIGINT or SIGTERM.
+//!
+//! The first signal only requests to stop, the command finishes what
+//! it's doing and exits when it checks [`is_requested`]. If that
+//! takes longer than the given timeout, or another signal arrives,
+//! the process exits immediately.
+
+use super::events;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the handler for SIGINT and SIGTERM.
+pub fn install(timeout: Duration) -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(move || {
+        if REQUESTED.swap(true, Ordering::SeqCst) {
+            events::warn("Quitting immediately.");
+            std::process::exit(130);
+        }
+        crate::status!(
+            "Stopping after the current upload. Press Ctrl-C again to quit immediately."
+        );
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            events::warn(&format!(
+                "Not finished after {}s, quitting.",
+                timeout.as_secs()
+            ));
+            std::process::exit(1);
+        });
+    })
+}
+
+/// Whether a signal asked to stop.
+pub fn is_requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
@@ -40,7 +40,7 @@ fn remote_register() -> Result<()> {
     let mut cmd = mk_cmd()?;
     let out = cmd
         .arg("register")
-        .args(&["-c", "demo2", "-l", "demo2", "--password", "test"])
+        .args(["-c", "demo2", "-l", "demo2", "--password", "test"])
         .output()?;
 
     let out: BasicResult = serde_json::from_slice(out.stdout.as_slice())?;
@@ -62,7 +62,7 @@ fn remote_upload_web() -> Result<()> {
 fn remote_upload_single_delete() -> Result<()> {
     let testname = "hello.txt";
     let testpath = Path::new(testname);
-    let mut testfile = std::fs::File::create(&testpath)?;
+    let mut testfile = std::fs::File::create(testpath)?;
     writeln!(&mut testfile, "hello world!")?;
 
     assert!(testpath.exists());
@@ -82,7 +82,7 @@ fn remote_upload_int_endpoint() -> Result<()> {
     let mut cmd = mk_cmd()?;
     let assert = cmd
         .arg("upload")
-        .args(&[
+        .args([
             "-c",
             "demo",
             "-i",
@@ -233,6 +233,7 @@ fn remote_search_summary() -> Result<()> {
 fn remote_download() -> Result<()> {
     let mut cmd = mk_cmd()?;
     let out = cmd
+        .arg("--quiet")
         .arg("download")
         .arg("--target")
         .arg("files_test")
@@ -251,6 +252,7 @@ fn remote_download() -> Result<()> {
 fn remote_download_zip() -> Result<()> {
     let mut cmd = mk_cmd()?;
     let out = cmd
+        .arg("--quiet")
         .arg("download")
         .arg("--target")
         .arg("zip_test/test.zip")
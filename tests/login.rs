@@ -5,7 +5,7 @@ use assert_cmd::prelude::*;
 #[test]
 fn remote_login() -> Result<()> {
     let mut cmd = mk_cmd()?;
-    let assert = cmd.arg("login").args(&["--password", "test"]).assert();
+    let assert = cmd.arg("login").args(["--password", "test"]).assert();
     assert.success().stderr("");
     Ok(())
 }